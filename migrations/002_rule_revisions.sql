@@ -0,0 +1,18 @@
+-- Rule revision history
+-- Each row is a snapshot of a rule as it was *before* an edit, re-import or
+-- revert, so the newest revision plus the live row describe the latest change.
+
+CREATE TABLE IF NOT EXISTS rule_revisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    category TEXT NOT NULL,
+    subcategory TEXT,
+    content TEXT NOT NULL,
+    source TEXT NOT NULL,
+    page INTEGER,
+    changed_by TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_rule_revisions_rule ON rule_revisions(rule_id, id);
@@ -0,0 +1,16 @@
+-- Published errata for rules
+-- Each entry replaces original_text with corrected_text in the rule content
+-- once published_on (YYYY-MM-DD) has been reached.
+
+CREATE TABLE IF NOT EXISTS rule_errata (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule_id TEXT NOT NULL,
+    published_on TEXT NOT NULL,
+    original_text TEXT NOT NULL,
+    corrected_text TEXT NOT NULL,
+    source_note TEXT,
+    created_at TEXT NOT NULL,
+    UNIQUE (rule_id, published_on, original_text)
+);
+
+CREATE INDEX IF NOT EXISTS idx_rule_errata_rule ON rule_errata(rule_id, published_on);
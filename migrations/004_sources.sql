@@ -0,0 +1,27 @@
+-- First-class rule sources (books) and editions
+-- rules.source keeps the display name; rules.source_id links to sources.
+
+CREATE TABLE IF NOT EXISTS sources (
+    abbreviation TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    edition TEXT NOT NULL,
+    published_year INTEGER,
+    display_order INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT OR IGNORE INTO sources (abbreviation, name, edition, published_year, display_order)
+VALUES
+    ('PHB', 'Player''s Handbook 2024', '2024', 2024, 1),
+    ('DMG', 'Dungeon Master''s Guide 2024', '2024', 2024, 2),
+    ('MM', 'Monster Manual 2025', '2024', 2025, 3);
+
+ALTER TABLE rules ADD COLUMN source_id TEXT REFERENCES sources(abbreviation);
+
+UPDATE rules
+SET source_id = (
+    SELECT abbreviation FROM sources
+    WHERE LOWER(sources.name) = LOWER(rules.source)
+       OR LOWER(sources.abbreviation) = LOWER(rules.source)
+);
+
+CREATE INDEX IF NOT EXISTS idx_rules_source_id ON rules(source_id);
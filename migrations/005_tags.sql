@@ -0,0 +1,15 @@
+-- Many-to-many tags for rules ("reaction", "movement", "mounted combat")
+-- tags.slug is the lowercase hyphenated form used in URLs and tag: filters.
+
+CREATE TABLE IF NOT EXISTS tags (
+    slug TEXT PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS rule_tags (
+    rule_id TEXT NOT NULL,
+    tag_slug TEXT NOT NULL REFERENCES tags(slug),
+    PRIMARY KEY (rule_id, tag_slug)
+);
+
+CREATE INDEX IF NOT EXISTS idx_rule_tags_tag ON rule_tags(tag_slug);
@@ -0,0 +1,13 @@
+-- Cross-reference graph between rules
+-- kind is 'explicit' for [[rule:id]] links written in the content and
+-- 'mention' for titles/aliases of other rules found in the text.
+-- Rebuilt from rule content by search::links::index_rule_links.
+
+CREATE TABLE IF NOT EXISTS rule_links (
+    source_rule_id TEXT NOT NULL,
+    target_rule_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    PRIMARY KEY (source_rule_id, target_rule_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_rule_links_target ON rule_links(target_rule_id);
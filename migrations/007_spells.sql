@@ -0,0 +1,25 @@
+-- Structured spell records parsed from Spells-category rules by import_rules
+-- One row per spell rule (rule_id = rules.id); classes live in spell_classes.
+
+CREATE TABLE IF NOT EXISTS spells (
+    rule_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    level INTEGER NOT NULL,
+    school TEXT NOT NULL,
+    casting_time TEXT NOT NULL,
+    casting_time_kind TEXT NOT NULL,
+    range TEXT NOT NULL,
+    components TEXT NOT NULL,
+    duration TEXT NOT NULL,
+    concentration INTEGER NOT NULL DEFAULT 0,
+    ritual INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS spell_classes (
+    rule_id TEXT NOT NULL,
+    class TEXT NOT NULL,
+    PRIMARY KEY (rule_id, class)
+);
+
+CREATE INDEX IF NOT EXISTS idx_spells_level ON spells(level, school);
+CREATE INDEX IF NOT EXISTS idx_spell_classes_class ON spell_classes(class);
@@ -0,0 +1,55 @@
+-- Structured equipment parsed from the `- **Name:** ...` item lines of
+-- Equipment-category rules by import_rules and the admin editor.
+-- Every row points back at the rule that lists it (rule_id = rules.id).
+-- Costs are stored in copper pieces so they sort across coin types.
+
+CREATE TABLE IF NOT EXISTS weapons (
+    id TEXT PRIMARY KEY,
+    rule_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    category TEXT NOT NULL,
+    damage_dice TEXT NOT NULL,
+    damage_type TEXT NOT NULL,
+    average_damage REAL NOT NULL,
+    properties TEXT NOT NULL DEFAULT '',
+    mastery TEXT NOT NULL DEFAULT '',
+    cost_cp INTEGER,
+    weight_lb REAL
+);
+
+-- Property names as slugs (finesse, two-handed) for filtering
+CREATE TABLE IF NOT EXISTS weapon_properties (
+    weapon_id TEXT NOT NULL,
+    property TEXT NOT NULL,
+    PRIMARY KEY (weapon_id, property)
+);
+
+CREATE TABLE IF NOT EXISTS armor (
+    id TEXT PRIMARY KEY,
+    rule_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    category TEXT NOT NULL,
+    ac_formula TEXT NOT NULL,
+    base_ac INTEGER NOT NULL,
+    adds_dex INTEGER NOT NULL DEFAULT 0,
+    dex_cap INTEGER,
+    strength_requirement INTEGER,
+    stealth_disadvantage INTEGER NOT NULL DEFAULT 0,
+    cost_cp INTEGER,
+    weight_lb REAL
+);
+
+CREATE TABLE IF NOT EXISTS gear (
+    id TEXT PRIMARY KEY,
+    rule_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    category TEXT NOT NULL,
+    cost_cp INTEGER,
+    weight_lb REAL,
+    description TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS idx_weapons_rule ON weapons(rule_id);
+CREATE INDEX IF NOT EXISTS idx_weapon_properties_property ON weapon_properties(property);
+CREATE INDEX IF NOT EXISTS idx_armor_rule ON armor(rule_id);
+CREATE INDEX IF NOT EXISTS idx_gear_rule ON gear(rule_id);
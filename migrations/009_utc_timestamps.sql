@@ -0,0 +1,34 @@
+-- Normalize rule timestamps to RFC 3339 UTC ("2025-01-15T12:00:00+00:00"),
+-- the format sqlx writes for chrono::DateTime<Utc>.
+--
+-- Rows were previously written as epoch seconds (Rule::new), RFC 3339 with
+-- fractional seconds (POST /api/rules) or SQLite datetime('now')
+-- ("2025-01-15 12:00:00"). Epoch values are converted with 'unixepoch';
+-- everything else goes through strftime, which understands the other two and
+-- converts offsets to UTC. Unreadable values fall back to the current time.
+
+UPDATE rules SET
+    created_at = COALESCE(
+        CASE
+            WHEN created_at != '' AND created_at NOT GLOB '*[^0-9]*'
+                THEN strftime('%Y-%m-%dT%H:%M:%S+00:00', CAST(created_at AS INTEGER), 'unixepoch')
+            ELSE strftime('%Y-%m-%dT%H:%M:%S+00:00', created_at)
+        END,
+        strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')
+    ),
+    updated_at = COALESCE(
+        CASE
+            WHEN updated_at != '' AND updated_at NOT GLOB '*[^0-9]*'
+                THEN strftime('%Y-%m-%dT%H:%M:%S+00:00', CAST(updated_at AS INTEGER), 'unixepoch')
+            ELSE strftime('%Y-%m-%dT%H:%M:%S+00:00', updated_at)
+        END,
+        strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')
+    );
+
+UPDATE rule_revisions SET
+    created_at = COALESCE(
+        strftime('%Y-%m-%dT%H:%M:%S+00:00', created_at),
+        strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')
+    );
+
+CREATE INDEX IF NOT EXISTS idx_rules_updated_at ON rules(updated_at);
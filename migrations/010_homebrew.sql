@@ -0,0 +1,16 @@
+-- Homebrew collections: house rules kept apart from official content.
+-- Rules with a collection_id are homebrew; official rules leave it NULL.
+-- Private collections are only shown to admins.
+
+CREATE TABLE IF NOT EXISTS homebrew_collections (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    visibility TEXT NOT NULL DEFAULT 'public' CHECK (visibility IN ('public', 'private')),
+    description TEXT,
+    created_at TEXT NOT NULL
+);
+
+ALTER TABLE rules ADD COLUMN collection_id TEXT REFERENCES homebrew_collections(id);
+
+CREATE INDEX IF NOT EXISTS idx_rules_collection ON rules(collection_id);
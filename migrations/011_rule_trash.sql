@@ -0,0 +1,8 @@
+-- Soft delete: deleting a rule in the admin dashboard moves it to the trash
+-- by setting deleted_at. Trashed rules are hidden from listings, search and
+-- the Oracle until restored, and purged for good after the retention period
+-- (TRASH_RETENTION_DAYS).
+
+ALTER TABLE rules ADD COLUMN deleted_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_rules_deleted_at ON rules(deleted_at);
@@ -0,0 +1,17 @@
+-- Glossary of game terms shown as tooltips in rendered rules and Oracle
+-- answers and listed at /glossary. Imported by import_rules from
+-- data/glossary.yaml; aliases (abbreviations, alternate names) live in
+-- glossary_aliases.
+
+CREATE TABLE IF NOT EXISTS glossary_terms (
+    id TEXT PRIMARY KEY,
+    term TEXT NOT NULL,
+    definition TEXT NOT NULL,
+    rule_id TEXT
+);
+
+CREATE TABLE IF NOT EXISTS glossary_aliases (
+    term_id TEXT NOT NULL,
+    alias TEXT NOT NULL,
+    PRIMARY KEY (term_id, alias)
+);
@@ -0,0 +1,11 @@
+-- Search synonyms and abbreviations, e.g. "ac" -> "armor class".
+-- A query containing `term` is also searched for `expansion`; hits found
+-- only through a synonym rank just below direct hits. Terms are stored
+-- normalized (lowercase words separated by single spaces). Seeded by
+-- import_rules from data/synonyms.yaml and managed at /admin/synonyms.
+
+CREATE TABLE IF NOT EXISTS search_synonyms (
+    term TEXT NOT NULL,
+    expansion TEXT NOT NULL,
+    PRIMARY KEY (term, expansion)
+);
@@ -0,0 +1,11 @@
+-- Former rule IDs. Renaming a rule in the admin keeps its old ID here so
+-- bookmarks and links to /rules/<old-id> redirect to the canonical ID.
+-- An alias never names a live rule: reusing it as a rule ID removes it.
+
+CREATE TABLE IF NOT EXISTS rule_aliases (
+    alias TEXT PRIMARY KEY,
+    rule_id TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_rule_aliases_rule ON rule_aliases(rule_id);
@@ -0,0 +1,38 @@
+-- Rebuild the full-text index with subcategory and source alongside title,
+-- category and content. Searches rank with bm25() column weights (see
+-- FTS_COLUMN_WEIGHTS in src/db/sqlite.rs), so the column order matters:
+-- title, category, subcategory, source, content.
+
+DROP TRIGGER IF EXISTS rules_ai;
+DROP TRIGGER IF EXISTS rules_ad;
+DROP TRIGGER IF EXISTS rules_au;
+DROP TABLE IF EXISTS rules_fts;
+
+CREATE VIRTUAL TABLE rules_fts USING fts5(
+    title,
+    category,
+    subcategory,
+    source,
+    content,
+    content='rules',
+    content_rowid='rowid'
+);
+
+CREATE TRIGGER rules_ai AFTER INSERT ON rules BEGIN
+    INSERT INTO rules_fts(rowid, title, category, subcategory, source, content)
+    VALUES (NEW.rowid, NEW.title, NEW.category, NEW.subcategory, NEW.source, NEW.content);
+END;
+
+CREATE TRIGGER rules_ad AFTER DELETE ON rules BEGIN
+    INSERT INTO rules_fts(rules_fts, rowid, title, category, subcategory, source, content)
+    VALUES ('delete', OLD.rowid, OLD.title, OLD.category, OLD.subcategory, OLD.source, OLD.content);
+END;
+
+CREATE TRIGGER rules_au AFTER UPDATE ON rules BEGIN
+    INSERT INTO rules_fts(rules_fts, rowid, title, category, subcategory, source, content)
+    VALUES ('delete', OLD.rowid, OLD.title, OLD.category, OLD.subcategory, OLD.source, OLD.content);
+    INSERT INTO rules_fts(rowid, title, category, subcategory, source, content)
+    VALUES (NEW.rowid, NEW.title, NEW.category, NEW.subcategory, NEW.source, NEW.content);
+END;
+
+INSERT INTO rules_fts(rules_fts) VALUES ('rebuild');
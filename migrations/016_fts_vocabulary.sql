@@ -0,0 +1,5 @@
+-- Every term in the full-text index with the number of rules containing it.
+-- Search corrects misspelled query words against this vocabulary when
+-- nothing matches ("grapled" -> "grappled").
+
+CREATE VIRTUAL TABLE IF NOT EXISTS rules_vocab USING fts5vocab(rules_fts, 'row');
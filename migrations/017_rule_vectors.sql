@@ -0,0 +1,11 @@
+-- Rule embeddings for the embedded vector index (VECTOR_INDEX=sqlite), one
+-- row per rule, so small deployments need no Qdrant. Vectors are
+-- little-endian f32 blobs compared in-process by cosine similarity; rows of
+-- another dimension (from a different embedding provider) are ignored.
+
+CREATE TABLE IF NOT EXISTS rule_vectors (
+    rule_id TEXT PRIMARY KEY,
+    dimension INTEGER NOT NULL,
+    vector BLOB NOT NULL,
+    updated_at TEXT NOT NULL
+);
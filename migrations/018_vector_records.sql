@@ -0,0 +1,12 @@
+-- What the vector index holds for each rule: a hash of the text that was
+-- embedded, the embedding model and the index it went to. index_vectors only
+-- re-embeds rules whose record no longer matches, and deletes the vectors of
+-- recorded rules that are gone.
+
+CREATE TABLE IF NOT EXISTS vector_records (
+    rule_id TEXT PRIMARY KEY,
+    content_hash TEXT NOT NULL,
+    embedding_model TEXT NOT NULL,
+    vector_index TEXT NOT NULL,
+    indexed_at TEXT NOT NULL
+);
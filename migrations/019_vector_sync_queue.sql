@@ -0,0 +1,11 @@
+-- Rules whose vector could not be brought in line with the database after an
+-- admin write (embedding API down, Qdrant unreachable). The server retries
+-- them periodically and drops an entry once its rule is in sync again.
+
+CREATE TABLE IF NOT EXISTS vector_sync_queue (
+    rule_id TEXT PRIMARY KEY,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    last_error TEXT NOT NULL,
+    queued_at TEXT NOT NULL,
+    last_attempt_at TEXT NOT NULL
+);
@@ -0,0 +1,10 @@
+-- Normalize errata timestamps to RFC 3339 UTC, as 009 did for rules and
+-- revisions. Errata were written with SQLite datetime('now')
+-- ("2025-01-15 12:00:00"); strftime reads that as UTC. Unreadable values fall
+-- back to the current time.
+
+UPDATE rule_errata SET
+    created_at = COALESCE(
+        strftime('%Y-%m-%dT%H:%M:%S+00:00', created_at),
+        strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')
+    );
@@ -0,0 +1,8 @@
+-- One row per term occurrence in the full-text index, keyed by the rule's
+-- rowid. Spelling corrections count only the rules the reader may see, so
+-- the per-term totals of rules_vocab, which include trashed and private
+-- homebrew rules, are replaced by this view joined to the visible rules.
+
+DROP TABLE IF EXISTS rules_vocab;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS rules_vocab_instances USING fts5vocab(rules_fts, 'instance');
@@ -1,6 +1,6 @@
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use crate::models::Rule;
+use crate::models::{apply_errata, errata_today, rule_sections, Erratum, Rule};
 
 const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
 
@@ -28,30 +28,18 @@ struct ContentBlock {
     text: String,
 }
 
-/// Get a ruling from Claude based on a scenario question and relevant rules
+/// Get a ruling from Claude based on a scenario question and relevant rules.
+/// Any effective `errata` for those rules are applied before they are sent.
 pub async fn get_ruling(
     api_key: &str,
     model: &str,
     question: &str,
     relevant_rules: &[Rule],
+    errata: &[Erratum],
 ) -> Result<String, ClaudeError> {
     let client = Client::new();
 
-    // Build context from relevant rules
-    let rules_context = if relevant_rules.is_empty() {
-        "No specific rules found for context.".to_string()
-    } else {
-        relevant_rules
-            .iter()
-            .map(|r| format!("## {}\n{}\n(Source: {}, Page {})\n",
-                r.title,
-                r.content,
-                r.source,
-                r.page.map(|p| p.to_string()).unwrap_or_else(|| "N/A".to_string())
-            ))
-            .collect::<Vec<_>>()
-            .join("\n")
-    };
+    let rules_context = build_rules_context(relevant_rules, errata, &errata_today());
 
     let system_prompt = format!(
         r#"You are a D&D 2024 rules expert. Your role is to provide accurate rulings based on the official 2024 Player's Handbook and Dungeon Master's Guide.
@@ -62,6 +50,8 @@ IMPORTANT GUIDELINES:
 3. Distinguish between RAW (Rules as Written) and RAI (Rules as Intended)
 4. If homebrew or DM discretion is needed, say so clearly
 5. Cite specific page numbers when possible
+6. Rules marked as updated by errata already contain the corrected text; treat it as authoritative and mention that errata applies
+7. Rules marked HOMEBREW are this table's house rules, not official content; they take precedence over official rules they change, and when you rely on one say so (e.g. "per your house rule...")
 
 RELEVANT RULES FOR CONTEXT:
 {rules_context}
@@ -104,7 +94,69 @@ Provide clear, concise rulings that a DM can use at the table."#
         .content
         .first()
         .map(|c| c.text.clone())
-        .ok_or_else(|| ClaudeError::EmptyResponse)
+        .ok_or(ClaudeError::EmptyResponse)
+}
+
+/// Build the rules section of the system prompt, using post-errata text
+fn build_rules_context(rules: &[Rule], errata: &[Erratum], today: &str) -> String {
+    if rules.is_empty() {
+        return "No specific rules found for context.".to_string();
+    }
+
+    rules
+        .iter()
+        .map(|r| {
+            let rule_errata: Vec<Erratum> = errata
+                .iter()
+                .filter(|e| e.rule_id == r.id && e.is_effective(today))
+                .cloned()
+                .collect();
+            let errata_note = rule_errata
+                .last()
+                .map(|latest| format!("(Updated by errata published {})\n", latest.published_on))
+                .unwrap_or_default();
+
+            let homebrew_note = if r.is_homebrew() {
+                let name = r.collection.as_ref().map(|c| format!("{} (owner: {})", c.name, c.owner));
+                format!("[HOMEBREW - house rule from {}]\n", name.unwrap_or_else(|| "a homebrew collection".to_string()))
+            } else {
+                String::new()
+            };
+
+            format!("## {}\n{}{}{}\n(Source: {}, Page {})\n",
+                r.title,
+                homebrew_note,
+                errata_note,
+                rule_context_text(r, &apply_errata(&r.content, &rule_errata, today)),
+                r.source,
+                r.page.map(|p| p.to_string()).unwrap_or_else(|| "N/A".to_string())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The rule text to quote: the opening text and the matched section when
+/// retrieval picked one, otherwise all of it
+fn rule_context_text(rule: &Rule, content: &str) -> String {
+    let Some(anchor) = rule.section.as_ref().and_then(|section| section.anchor.as_deref()) else {
+        return content.to_string();
+    };
+    let sections = rule_sections(content);
+    let Some(matched) = sections.iter().find(|section| section.anchor.as_deref() == Some(anchor)) else {
+        return content.to_string();
+    };
+
+    let mut parts: Vec<&str> = sections
+        .iter()
+        .filter(|section| section.anchor.is_none())
+        .map(|section| section.content.as_str())
+        .collect();
+    parts.push(&matched.content);
+    if sections.len() > parts.len() {
+        parts.push("(Other sections of this rule omitted.)");
+    }
+    parts.join("\n\n")
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -121,3 +173,85 @@ pub enum ClaudeError {
     #[error("Empty response from Claude")]
     EmptyResponse,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::HomebrewCollection;
+
+    fn rule(id: &str, content: &str) -> Rule {
+        Rule {
+            category: "Conditions".to_string(),
+            ..Rule::test(id, "Grappled", content)
+        }
+    }
+
+    fn erratum(rule_id: &str, published_on: &str) -> Erratum {
+        Erratum {
+            id: 1,
+            rule_id: rule_id.to_string(),
+            published_on: published_on.to_string(),
+            original_text: "Speed is 5".to_string(),
+            corrected_text: "Speed is 0".to_string(),
+            source_note: None,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn context_uses_post_errata_text_and_mentions_errata() {
+        let context = build_rules_context(
+            &[rule("grappled", "Your Speed is 5."), rule("prone", "Your Speed is 5.")],
+            &[erratum("grappled", "2025-01-15")],
+            "2025-06-01",
+        );
+
+        assert!(context.contains("(Updated by errata published 2025-01-15)\nYour Speed is 0."));
+        assert_eq!(context.matches("Speed is 5").count(), 1);
+    }
+
+    #[test]
+    fn context_ignores_errata_not_yet_published() {
+        let context = build_rules_context(
+            &[rule("grappled", "Your Speed is 5.")],
+            &[erratum("grappled", "2030-01-01")],
+            "2025-06-01",
+        );
+
+        assert!(context.contains("Your Speed is 5."));
+        assert!(!context.contains("errata"));
+    }
+
+    #[test]
+    fn context_quotes_only_the_matched_section() {
+        let mut exhaustion = rule(
+            "exhaustion",
+            "Measured in levels.\n\n**Level Effects:** Speed is 5.\n\n**Recovery:** A long rest removes a level.",
+        );
+        exhaustion.section = crate::models::matching_section(&exhaustion.content, "exhaustion recovery");
+
+        let context = build_rules_context(&[exhaustion], &[erratum("exhaustion", "2025-01-15")], "2025-06-01");
+
+        assert!(context.contains("Measured in levels.\n\n**Recovery:** A long rest removes a level.\n\n(Other sections"));
+        assert!(!context.contains("Level Effects"));
+    }
+
+    #[test]
+    fn context_labels_homebrew_rules() {
+        let mut house_rule = rule("flanking", "Flanking grants advantage.");
+        house_rule.collection_id = Some("table-rules".to_string());
+        house_rule.collection = Some(HomebrewCollection {
+            id: "table-rules".to_string(),
+            name: "Table Rules".to_string(),
+            owner: "Sam".to_string(),
+            visibility: "public".to_string(),
+            description: None,
+            created_at: crate::models::now_utc(),
+        });
+
+        let context = build_rules_context(&[house_rule, rule("grappled", "Your Speed is 0.")], &[], "2025-06-01");
+
+        assert!(context.contains("[HOMEBREW - house rule from Table Rules (owner: Sam)]\nFlanking grants advantage."));
+        assert_eq!(context.matches("HOMEBREW").count(), 1);
+    }
+}
@@ -3,6 +3,12 @@
 //! Usage:
 //!   cargo run --bin import_rules           # Import all rules
 //!   cargo run --bin import_rules -- --dry-run  # Preview without changes
+//!
+//! A file may declare a `homebrew:` block (name, owner, visibility); its rules
+//! are then imported into that homebrew collection.
+//!
+//! Glossary terms are imported from `data/glossary.yaml` and search synonyms
+//! from `data/synonyms.yaml` when they exist.
 
 use clap::Parser;
 use glob::glob;
@@ -22,6 +28,14 @@ struct Args {
     #[arg(short, long, default_value = "data/rules")]
     rules_dir: PathBuf,
 
+    /// Path to the glossary file (default: data/glossary.yaml)
+    #[arg(short, long, default_value = "data/glossary.yaml")]
+    glossary: PathBuf,
+
+    /// Path to the search synonyms file (default: data/synonyms.yaml)
+    #[arg(short, long, default_value = "data/synonyms.yaml")]
+    synonyms: PathBuf,
+
     /// Database URL (default: from DATABASE_URL env or sqlite:./rulecraft.db)
     #[arg(short, long)]
     database_url: Option<String>,
@@ -37,6 +51,62 @@ struct YamlRule {
     #[serde(default)]
     page: Option<i32>,
     content: String,
+    /// Tag names; when present they replace the rule's tags, when absent
+    /// tags added through the admin dashboard are kept
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+    #[serde(default)]
+    errata: Vec<YamlErratum>,
+}
+
+/// A published correction listed under a rule in YAML
+#[derive(Debug, Deserialize)]
+struct YamlErratum {
+    /// Publication date, YYYY-MM-DD
+    date: String,
+    original: String,
+    corrected: String,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+/// Homebrew collection declared at the top of a YAML file; every rule in
+/// the file joins it
+#[derive(Debug, Deserialize)]
+struct YamlHomebrew {
+    /// Defaults to the slug of `name`
+    #[serde(default)]
+    id: Option<String>,
+    name: String,
+    owner: String,
+    /// "public" (default) or "private"
+    #[serde(default)]
+    visibility: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// A glossary entry in YAML
+#[derive(Debug, Deserialize)]
+struct YamlGlossaryTerm {
+    term: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    definition: String,
+    #[serde(default)]
+    rule_id: Option<String>,
+}
+
+/// Structure for the glossary file
+#[derive(Debug, Deserialize)]
+struct GlossaryFile {
+    terms: Vec<YamlGlossaryTerm>,
+}
+
+/// Structure for the search synonyms file: each term maps to its expansions
+#[derive(Debug, Deserialize)]
+struct SynonymsFile {
+    synonyms: std::collections::BTreeMap<String, Vec<String>>,
 }
 
 /// Structure for a YAML rules file
@@ -44,6 +114,8 @@ struct YamlRule {
 struct RulesFile {
     category: String,
     source: String,
+    #[serde(default)]
+    homebrew: Option<YamlHomebrew>,
     rules: Vec<YamlRule>,
 }
 
@@ -56,6 +128,9 @@ struct Rule {
     content: String,
     source: String,
     page: Option<i32>,
+    collection_id: Option<String>,
+    tags: Option<Vec<String>>,
+    errata: Vec<YamlErratum>,
 }
 
 #[tokio::main]
@@ -105,6 +180,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Parse all YAML files
     let mut all_rules: Vec<Rule> = Vec::new();
+    let mut collections: Vec<rulecraft::models::HomebrewCollection> = Vec::new();
     let mut parse_errors = 0;
 
     for yaml_path in &yaml_files {
@@ -119,7 +195,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     rules_file.category
                 );
 
+                let collection_id = match rules_file.homebrew {
+                    Some(homebrew) => match homebrew_collection(homebrew) {
+                        Ok(collection) => {
+                            println!("  Homebrew collection '{}' ({})", collection.name, collection.visibility);
+                            let id = collection.id.clone();
+                            collections.push(collection);
+                            Some(id)
+                        }
+                        Err(e) => {
+                            eprintln!("Error in homebrew block of {}: {}", yaml_path.display(), e);
+                            parse_errors += 1;
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+
                 for yaml_rule in rules_file.rules {
+                    if rulecraft::models::slugify(&yaml_rule.id) != yaml_rule.id {
+                        eprintln!(
+                            "Rule ID '{}' in {} is not a slug (lowercase letters, digits and hyphens)",
+                            yaml_rule.id,
+                            yaml_path.display()
+                        );
+                    }
                     all_rules.push(Rule {
                         id: yaml_rule.id,
                         title: yaml_rule.title,
@@ -128,6 +228,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         content: yaml_rule.content,
                         source: rules_file.source.clone(),
                         page: yaml_rule.page,
+                        collection_id: collection_id.clone(),
+                        tags: yaml_rule.tags,
+                        errata: yaml_rule.errata,
                     });
                 }
             }
@@ -141,6 +244,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!();
     println!("Total rules parsed: {}", all_rules.len());
 
+    let mut glossary = if args.glossary.exists() {
+        match load_glossary(&args.glossary) {
+            Ok(terms) => {
+                println!("Parsed {}: {} glossary terms", args.glossary.display(), terms.len());
+                terms
+            }
+            Err(e) => {
+                eprintln!("Error parsing {}: {}", args.glossary.display(), e);
+                parse_errors += 1;
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    let synonyms = if args.synonyms.exists() {
+        match load_synonyms(&args.synonyms) {
+            Ok(synonyms) => {
+                println!("Parsed {}: {} search synonyms", args.synonyms.display(), synonyms.len());
+                synonyms
+            }
+            Err(e) => {
+                eprintln!("Error parsing {}: {}", args.synonyms.display(), e);
+                parse_errors += 1;
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
     if parse_errors > 0 {
         eprintln!("Warning: {} file(s) had parse errors", parse_errors);
     }
@@ -171,9 +306,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Run migrations to ensure schema exists
     rulecraft::db::run_migrations(&pool).await?;
 
+    for collection in &collections {
+        rulecraft::db::upsert_homebrew_collection(&pool, collection).await?;
+    }
+
+    // Rules renamed in the admin keep their old ID in the YAML; update them
+    // under the new one, and point links at it, instead of bringing the old
+    // ID back
+    let renamed = rulecraft::db::get_rule_alias_map(&pool).await?;
+    for rule in &mut all_rules {
+        if let Some(current) = renamed.get(&rule.id) {
+            println!("  {} was renamed to {}", rule.id, current);
+            rule.id = current.clone();
+        }
+        rule.content = rulecraft::search::links::retarget_explicit_links(&rule.content, &renamed);
+    }
+
     // Import rules
     let mut inserted = 0;
     let mut updated = 0;
+    let mut tagged = 0;
+    let mut spell_count = 0;
+    let mut item_count = 0;
+    let mut errata_count = 0;
 
     for rule in &all_rules {
         // Convert to the crate's Rule type
@@ -184,12 +339,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             subcategory: rule.subcategory.clone(),
             content: rule.content.clone(),
             source: rule.source.clone(),
+            source_id: None, // Resolved from the source name by upsert
             page: rule.page,
-            created_at: String::new(), // Will be set by upsert
-            updated_at: String::new(), // Will be set by upsert
+            created_at: rulecraft::models::now_utc(), // Set by upsert
+            updated_at: rulecraft::models::now_utc(), // Set by upsert
+            collection_id: rule.collection_id.clone(),
+            tags: Vec::new(),
+            equipment: rulecraft::models::EquipmentSet::default(),
+            collection: None,
+            section: None,
+            highlight: None,
         };
 
-        match rulecraft::db::upsert_rule(&pool, &db_rule).await {
+        match rulecraft::db::upsert_rule(&pool, &db_rule, "import_rules").await {
             Ok(is_new) => {
                 if is_new {
                     inserted += 1;
@@ -199,15 +361,170 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
             Err(e) => {
                 eprintln!("Error upserting rule '{}': {}", rule.id, e);
+                continue;
+            }
+        }
+
+        if db_rule.is_spell() {
+            match rulecraft::models::Spell::from_rule(&db_rule) {
+                Ok(spell) => match rulecraft::db::upsert_spell(&pool, &spell).await {
+                    Ok(_) => spell_count += 1,
+                    Err(e) => eprintln!("Error storing spell '{}': {}", rule.id, e),
+                },
+                Err(e) => eprintln!("Could not parse spell '{}': {}", rule.id, e),
+            }
+        }
+
+        if db_rule.is_equipment() {
+            match rulecraft::models::EquipmentSet::from_rule(&db_rule) {
+                Ok(equipment) => match rulecraft::db::set_rule_equipment(&pool, &rule.id, &equipment).await {
+                    Ok(_) => item_count += equipment.weapons.len() + equipment.armor.len() + equipment.gear.len(),
+                    Err(e) => eprintln!("Error storing equipment of '{}': {}", rule.id, e),
+                },
+                Err(e) => eprintln!("Could not parse equipment in '{}': {}", rule.id, e),
+            }
+        }
+
+        if let Some(tag_names) = &rule.tags {
+            let tags = rulecraft::models::parse_tag_list(&tag_names.join(","));
+            match rulecraft::db::set_rule_tags(&pool, &rule.id, &tags).await {
+                Ok(_) => tagged += 1,
+                Err(e) => eprintln!("Error setting tags for '{}': {}", rule.id, e),
+            }
+        }
+
+        for yaml_erratum in &rule.errata {
+            if !rulecraft::models::is_valid_errata_date(&yaml_erratum.date) {
+                eprintln!(
+                    "Skipping erratum for '{}': date '{}' is not YYYY-MM-DD",
+                    rule.id, yaml_erratum.date
+                );
+                continue;
+            }
+
+            let erratum = rulecraft::models::Erratum {
+                id: 0,
+                rule_id: rule.id.clone(),
+                published_on: yaml_erratum.date.clone(),
+                original_text: yaml_erratum.original.clone(),
+                corrected_text: yaml_erratum.corrected.clone(),
+                source_note: yaml_erratum.note.clone(),
+                created_at: String::new(),
+            };
+
+            match rulecraft::db::upsert_erratum(&pool, &erratum).await {
+                Ok(_) => errata_count += 1,
+                Err(e) => eprintln!("Error importing erratum for '{}': {}", rule.id, e),
             }
         }
     }
 
+    let mut glossary_count = 0;
+    for term in &mut glossary {
+        if let Some(current) = term.rule_id.as_ref().and_then(|rule_id| renamed.get(rule_id)) {
+            term.rule_id = Some(current.clone());
+        }
+        if let Some(rule_id) = &term.rule_id {
+            if rulecraft::db::get_rule_by_id(&pool, rule_id).await?.is_none() {
+                eprintln!("Glossary term '{}' links to missing rule '{}'", term.term, rule_id);
+            }
+        }
+        match rulecraft::db::upsert_glossary_term(&pool, term).await {
+            Ok(_) => glossary_count += 1,
+            Err(e) => eprintln!("Error importing glossary term '{}': {}", term.term, e),
+        }
+    }
+
+    let mut synonym_count = 0;
+    for synonym in &synonyms {
+        match rulecraft::db::add_synonym(&pool, synonym).await {
+            Ok(true) => synonym_count += 1,
+            Ok(false) => {}
+            Err(e) => eprintln!("Error importing synonym '{}': {}", synonym.term, e),
+        }
+    }
+
+    let link_report = rulecraft::search::links::index_rule_links(&pool).await?;
+
     println!();
     println!("Import complete!");
     println!("  Inserted: {} new rules", inserted);
     println!("  Updated:  {} existing rules", updated);
     println!("  Total:    {} rules in database", inserted + updated);
+    println!("  Tagged:   {} rules", tagged);
+    println!("  Spells:   {} parsed", spell_count);
+    println!("  Items:    {} weapons, armor and gear", item_count);
+    println!("  Errata:   {} entries", errata_count);
+    println!("  Homebrew: {} collection(s)", collections.len());
+    println!("  Glossary: {} terms", glossary_count);
+    println!("  Synonyms: {} added", synonym_count);
+    println!("  Links:    {} cross-references", link_report.links);
+
+    if !link_report.broken.is_empty() {
+        println!();
+        println!("Broken rule links:");
+        for broken in &link_report.broken {
+            println!("  {} -> [[rule:{}]] (no such rule)", broken.rule_id, broken.target_id);
+        }
+    }
 
     Ok(())
 }
+
+fn load_glossary(path: &std::path::Path) -> Result<Vec<rulecraft::models::GlossaryTerm>, Box<dyn std::error::Error>> {
+    let file: GlossaryFile = serde_yaml::from_str(&std::fs::read_to_string(path)?)?;
+    let mut terms = Vec::new();
+
+    for entry in file.terms {
+        let id = rulecraft::models::slugify(&entry.term);
+        if id.is_empty() {
+            return Err(format!("glossary term '{}' needs letters or digits", entry.term).into());
+        }
+        terms.push(rulecraft::models::GlossaryTerm {
+            id,
+            term: entry.term.trim().to_string(),
+            definition: entry.definition.trim().to_string(),
+            rule_id: entry.rule_id,
+            aliases: entry.aliases.iter().map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect(),
+        });
+    }
+
+    Ok(terms)
+}
+
+fn load_synonyms(path: &std::path::Path) -> Result<Vec<rulecraft::models::Synonym>, Box<dyn std::error::Error>> {
+    let file: SynonymsFile = serde_yaml::from_str(&std::fs::read_to_string(path)?)?;
+    let mut synonyms = Vec::new();
+
+    for (term, expansions) in file.synonyms {
+        for expansion in expansions {
+            match rulecraft::models::Synonym::new(&term, &expansion) {
+                Some(synonym) => synonyms.push(synonym),
+                None => eprintln!("Skipping synonym '{}' -> '{}': blank or identical", term, expansion),
+            }
+        }
+    }
+
+    Ok(synonyms)
+}
+
+fn homebrew_collection(homebrew: YamlHomebrew) -> Result<rulecraft::models::HomebrewCollection, String> {
+    let visibility = match homebrew.visibility.as_deref() {
+        Some(value) => rulecraft::models::Visibility::from_param(value)
+            .ok_or_else(|| format!("visibility '{}' is not public or private", value))?,
+        None => rulecraft::models::Visibility::Public,
+    };
+    let id = rulecraft::models::slugify(homebrew.id.as_deref().unwrap_or(&homebrew.name));
+    if id.is_empty() {
+        return Err("collection needs a name".to_string());
+    }
+
+    Ok(rulecraft::models::HomebrewCollection {
+        id,
+        name: homebrew.name,
+        owner: homebrew.owner,
+        visibility: visibility.as_str().to_string(),
+        description: homebrew.description,
+        created_at: rulecraft::models::now_utc(),
+    })
+}
@@ -1,25 +1,28 @@
-//! CLI tool for indexing rules into Qdrant vector search.
+//! CLI tool for indexing rules for vector search, into Qdrant or the SQLite
+//! `rule_vectors` table as `VECTOR_INDEX` selects, with OpenAI or local
+//! embeddings as `EMBEDDING_PROVIDER` selects. Only new and changed rules
+//! are embedded unless `--full` is given.
 //!
 //! Usage:
 //!   cargo run --bin index_vectors
 //!   cargo run --bin index_vectors -- --dry-run
+//!   cargo run --bin index_vectors -- --full
 
 use clap::Parser;
 use rulecraft::{
-    config::VectorSearchConfig,
+    config::{VectorBackend, VectorSearchConfig},
     search::{
-        indexer::index_rules,
-        openai_embeddings::OpenAiEmbeddingClient,
-        qdrant::QdrantVectorIndex,
+        hybrid::{ConfiguredEmbeddingClient, ConfiguredVectorIndex},
+        indexer::{index_rules, plan_index, IndexOptions},
     },
     Config,
 };
 
 #[derive(Parser, Debug)]
 #[command(name = "index_vectors")]
-#[command(about = "Index Rulecraft rules into Qdrant using OpenAI embeddings")]
+#[command(about = "Index Rulecraft rules for vector search using OpenAI or local embeddings")]
 struct Args {
-    /// Preview rules that would be indexed without calling OpenAI or Qdrant
+    /// Preview rules that would be indexed without embedding or storing them
     #[arg(long)]
     dry_run: bool,
 
@@ -27,6 +30,10 @@ struct Args {
     #[arg(long)]
     fail_fast: bool,
 
+    /// Clear the index and embed every rule, not just new and changed ones
+    #[arg(long)]
+    full: bool,
+
     /// Database URL (default: DATABASE_URL env or sqlite:./rulecraft.db)
     #[arg(short, long)]
     database_url: Option<String>,
@@ -35,7 +42,7 @@ struct Args {
     #[arg(long)]
     qdrant_url: Option<String>,
 
-    /// Qdrant collection (default: QDRANT_COLLECTION env or rulecraft_rules_openai_small_v1)
+    /// Qdrant collection (default: QDRANT_COLLECTION env, else one named after the embedding provider)
     #[arg(long)]
     qdrant_collection: Option<String>,
 }
@@ -56,48 +63,59 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Rulecraft Vector Indexer");
     println!("========================");
     println!("Database: {}", database_url);
-    println!("Qdrant: {}", vector_config.qdrant_url);
-    println!("Collection: {}", vector_config.qdrant_collection);
-    println!("Embedding model: {}", vector_config.openai_embedding_model);
+    match vector_config.backend {
+        VectorBackend::Qdrant => {
+            println!("Qdrant: {}", vector_config.qdrant_url);
+            println!("Collection: {}", vector_config.qdrant_collection);
+        }
+        VectorBackend::Sqlite => println!("Vector index: SQLite (rule_vectors)"),
+    }
+    println!("Embedding model: {}", vector_config.embedding_model());
     println!();
 
     let pool = rulecraft::db::init_pool(&database_url).await?;
     rulecraft::db::run_migrations(&pool).await?;
     let rules = rulecraft::db::get_all_rules(&pool).await?;
 
+    let options = IndexOptions {
+        full: args.full,
+        fail_fast: args.fail_fast,
+        ..IndexOptions::from_config(&vector_config)
+    };
+
     if args.dry_run {
+        let records = rulecraft::db::get_vector_records(&pool).await?;
+        let plan = plan_index(&rules, &records, &options);
         println!("DRY RUN - no embeddings generated and no vectors upserted");
-        println!("Rules that would be indexed: {}", rules.len());
-        for rule in rules.iter().take(20) {
-            println!("  - [{}] {} ({})", rule.category, rule.title, rule.id);
+        if args.full || records.is_empty() {
+            println!("The index would be cleared first");
         }
-        if rules.len() > 20 {
-            println!("  ... and {} more", rules.len() - 20);
+        println!("Rules that would be embedded: {}", plan.changed.len());
+        for rule_id in plan.changed.iter().take(20) {
+            println!("  - {}", rule_id);
+        }
+        if plan.changed.len() > 20 {
+            println!("  ... and {} more", plan.changed.len() - 20);
+        }
+        println!("Unchanged rules: {}", plan.unchanged);
+        println!("Vectors that would be removed: {}", plan.removed.len());
+        for rule_id in &plan.removed {
+            println!("  - {}", rule_id);
         }
         return Ok(());
     }
 
-    let api_key = vector_config
-        .openai_api_key
-        .clone()
-        .ok_or("OPENAI_API_KEY must be set to index vectors")?;
-
-    let embedding_client = OpenAiEmbeddingClient::new(
-        api_key,
-        vector_config.openai_embedding_model.clone(),
-        vector_config.openai_embedding_dimension,
-    );
-    let vector_index = QdrantVectorIndex::new(
-        vector_config.qdrant_url.clone(),
-        vector_config.qdrant_collection.clone(),
-        vector_config.openai_embedding_dimension,
-    );
+    let embedding_client = ConfiguredEmbeddingClient::from_config(&vector_config)
+        .map_err(|_| "OPENAI_API_KEY must be set to index vectors with OpenAI embeddings (or set EMBEDDING_PROVIDER=local)")?;
+    let vector_index = ConfiguredVectorIndex::from_config(&vector_config, &pool);
 
-    let report = index_rules(&rules, &embedding_client, &vector_index, args.fail_fast).await?;
+    let report = index_rules(&pool, &rules, &embedding_client, &vector_index, &options).await?;
 
     println!("Index complete");
-    println!("  Indexed: {}", report.indexed);
-    println!("  Failed:  {}", report.failed.len());
+    println!("  Changed:   {}", report.changed);
+    println!("  Unchanged: {}", report.unchanged);
+    println!("  Removed:   {}", report.removed);
+    println!("  Failed:    {}", report.failed.len());
     for failure in report.failed {
         println!("  - {}: {}", failure.rule_id, failure.error);
     }
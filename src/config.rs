@@ -1,28 +1,64 @@
 use std::env;
 
+use crate::search::{
+    hybrid::Fusion,
+    local_embeddings::{DEFAULT_LOCAL_DIMENSION, DEFAULT_LOCAL_SCORE_THRESHOLD},
+};
+
 #[derive(Clone)]
 pub struct Config {
     pub database_url: String,
     pub claude_api_key: Option<String>,
     pub claude_model: String,
     pub port: u16,
+    /// Base URL used for absolute links in `/feed.atom`, e.g. https://rules.example.com
+    pub public_url: Option<String>,
     pub admin_api_key: Option<String>,
     pub ai_rate_limit_per_hour: u32,
     pub search_rate_limit_per_minute: u32,
+    /// Days a deleted rule stays in the trash before it is purged; 0 keeps it forever
+    pub trash_retention_days: u32,
     pub vector: VectorSearchConfig,
 }
 
+/// Where rule and query embeddings come from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmbeddingProvider {
+    /// The OpenAI embeddings API
+    OpenAi,
+    /// The built-in hashing vectorizer, which needs no network access
+    Local,
+}
+
+/// Where rule embeddings are stored and searched
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VectorBackend {
+    /// A Qdrant server at `qdrant_url`
+    Qdrant,
+    /// The `rule_vectors` table in the rules database
+    Sqlite,
+}
+
 #[derive(Clone, Debug)]
 pub struct VectorSearchConfig {
     pub enabled: bool,
+    pub backend: VectorBackend,
+    pub embedding_provider: EmbeddingProvider,
     pub openai_api_key: Option<String>,
     pub openai_embedding_model: String,
     pub openai_embedding_dimension: usize,
+    pub local_embedding_dimension: usize,
     pub qdrant_url: String,
     pub qdrant_collection: String,
     pub top_k: usize,
     pub score_threshold: f32,
     pub oracle_max_context_rules: usize,
+    /// Quote only the opening text and the matching section of long rules
+    /// in the Oracle prompt
+    pub oracle_section_context: bool,
+    /// How full-text and vector rankings are combined for the Oracle and
+    /// hybrid search
+    pub fusion: Fusion,
 }
 
 impl Config {
@@ -37,6 +73,10 @@ impl Config {
                 .ok()
                 .and_then(|p| p.parse().ok())
                 .unwrap_or(3000),
+            public_url: env::var("PUBLIC_URL")
+                .ok()
+                .map(|url| url.trim_end_matches('/').to_string())
+                .filter(|url| !url.is_empty()),
             admin_api_key: env::var("ADMIN_API_KEY").ok(),
             ai_rate_limit_per_hour: env::var("AI_RATE_LIMIT_PER_HOUR")
                 .ok()
@@ -46,6 +86,10 @@ impl Config {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(30),
+            trash_retention_days: env::var("TRASH_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
             vector: VectorSearchConfig::from_env(),
         }
     }
@@ -53,8 +97,24 @@ impl Config {
 
 impl VectorSearchConfig {
     pub fn from_env() -> Self {
+        let embedding_provider = match env::var("EMBEDDING_PROVIDER").unwrap_or_default().trim().to_lowercase().as_str() {
+            "local" => EmbeddingProvider::Local,
+            _ => EmbeddingProvider::OpenAi,
+        };
+        let (default_collection, default_threshold) = match embedding_provider {
+            EmbeddingProvider::OpenAi => ("rulecraft_rules_openai_small_v1", 0.35),
+            EmbeddingProvider::Local => ("rulecraft_rules_local_hashing_v1", DEFAULT_LOCAL_SCORE_THRESHOLD),
+        };
+
+        let backend = match env::var("VECTOR_INDEX").unwrap_or_default().trim().to_lowercase().as_str() {
+            "sqlite" => VectorBackend::Sqlite,
+            _ => VectorBackend::Qdrant,
+        };
+
         Self {
             enabled: env_bool("VECTOR_SEARCH_ENABLED", false),
+            backend,
+            embedding_provider,
             openai_api_key: env::var("OPENAI_API_KEY").ok(),
             openai_embedding_model: env::var("OPENAI_EMBEDDING_MODEL")
                 .unwrap_or_else(|_| "text-embedding-3-small".to_string()),
@@ -62,10 +122,14 @@ impl VectorSearchConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(1536),
+            local_embedding_dimension: env::var("LOCAL_EMBEDDING_DIMENSION")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_LOCAL_DIMENSION),
             qdrant_url: env::var("QDRANT_URL")
                 .unwrap_or_else(|_| "http://localhost:6333".to_string()),
             qdrant_collection: env::var("QDRANT_COLLECTION")
-                .unwrap_or_else(|_| "rulecraft_rules_openai_small_v1".to_string()),
+                .unwrap_or_else(|_| default_collection.to_string()),
             top_k: env::var("VECTOR_TOP_K")
                 .ok()
                 .and_then(|v| v.parse().ok())
@@ -73,13 +137,53 @@ impl VectorSearchConfig {
             score_threshold: env::var("VECTOR_SCORE_THRESHOLD")
                 .ok()
                 .and_then(|v| v.parse().ok())
-                .unwrap_or(0.35),
+                .unwrap_or(default_threshold),
             oracle_max_context_rules: env::var("ORACLE_MAX_CONTEXT_RULES")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(10),
+            oracle_section_context: env_bool("ORACLE_SECTION_CONTEXT", true),
+            fusion: fusion_from_env(),
+        }
+    }
+
+    /// Length of the vectors the configured provider produces
+    pub fn embedding_dimension(&self) -> usize {
+        match self.embedding_provider {
+            EmbeddingProvider::OpenAi => self.openai_embedding_dimension,
+            EmbeddingProvider::Local => self.local_embedding_dimension,
         }
     }
+
+    /// The embedding model, for logs and CLI output
+    pub fn embedding_model(&self) -> String {
+        match self.embedding_provider {
+            EmbeddingProvider::OpenAi => self.openai_embedding_model.clone(),
+            EmbeddingProvider::Local => format!("local hashing vectorizer ({} dimensions)", self.local_embedding_dimension),
+        }
+    }
+}
+
+/// `SEARCH_FUSION=weighted` weighs the sources by `SEARCH_FTS_WEIGHT` and
+/// `SEARCH_VECTOR_WEIGHT`; anything else is Reciprocal Rank Fusion with
+/// constant `SEARCH_RRF_K`
+fn fusion_from_env() -> Fusion {
+    let number = |key: &str, default: f32| {
+        env::var(key)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+
+    match env::var("SEARCH_FUSION").unwrap_or_default().trim().to_lowercase().as_str() {
+        "weighted" => Fusion::Weighted {
+            fts_weight: number("SEARCH_FTS_WEIGHT", 0.5),
+            vector_weight: number("SEARCH_VECTOR_WEIGHT", 0.5),
+        },
+        _ => Fusion::Rrf {
+            k: number("SEARCH_RRF_K", 60.0),
+        },
+    }
 }
 
 fn env_bool(key: &str, default: bool) -> bool {
@@ -0,0 +1,294 @@
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+/// Default directory holding numbered `NNN_name.sql` migration files
+pub const MIGRATIONS_DIR: &str = "migrations";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    pub version: i64,
+    pub name: String,
+    pub sql: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("failed to read migrations from {path}: {message}")]
+    Io { path: PathBuf, message: String },
+
+    #[error("migration file name '{0}' must start with a numeric version, e.g. 002_add_tags.sql")]
+    InvalidFileName(String),
+
+    #[error("duplicate migration version {0}")]
+    DuplicateVersion(i64),
+
+    #[error("database schema version {database} is newer than this binary supports ({supported}); upgrade Rulecraft before starting it against this database")]
+    DatabaseTooNew { database: i64, supported: i64 },
+
+    #[error("migration {version} ({name}) failed: {source}")]
+    Failed {
+        version: i64,
+        name: String,
+        #[source]
+        source: sqlx::Error,
+    },
+
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Load every `*.sql` file in `dir`, ordered by its numeric version prefix
+pub fn load_migrations(dir: &Path) -> Result<Vec<Migration>, MigrationError> {
+    let io_error = |e: std::io::Error| MigrationError::Io {
+        path: dir.to_path_buf(),
+        message: e.to_string(),
+    };
+
+    let mut migrations = Vec::new();
+
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.extension().map(|ext| ext != "sql").unwrap_or(true) {
+            continue;
+        }
+
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (version, name) = parse_file_name(&file_name)?;
+        let sql = std::fs::read_to_string(&path).map_err(|e| MigrationError::Io {
+            path: path.clone(),
+            message: e.to_string(),
+        })?;
+
+        migrations.push(Migration { version, name, sql });
+    }
+
+    migrations.sort_by_key(|migration| migration.version);
+
+    for pair in migrations.windows(2) {
+        if pair[0].version == pair[1].version {
+            return Err(MigrationError::DuplicateVersion(pair[0].version));
+        }
+    }
+
+    Ok(migrations)
+}
+
+fn parse_file_name(file_name: &str) -> Result<(i64, String), MigrationError> {
+    let stem = file_name.trim_end_matches(".sql");
+    let digits: String = stem.chars().take_while(|c| c.is_ascii_digit()).collect();
+
+    let version = digits
+        .parse::<i64>()
+        .map_err(|_| MigrationError::InvalidFileName(file_name.to_string()))?;
+    let name = stem[digits.len()..].trim_start_matches('_').to_string();
+
+    Ok((version, name))
+}
+
+/// Current schema version recorded in the database, or 0 for a fresh database
+pub async fn current_version(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    ensure_version_table(pool).await?;
+
+    let version: (Option<i64>,) = sqlx::query_as("SELECT MAX(version) FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(version.0.unwrap_or(0))
+}
+
+/// Apply every migration newer than the recorded schema version.
+///
+/// Each migration runs in its own transaction together with its
+/// `schema_version` row, so a failing step leaves the database at the
+/// previous version. Returns the versions that were applied.
+pub async fn apply_migrations(
+    pool: &SqlitePool,
+    migrations: &[Migration],
+) -> Result<Vec<i64>, MigrationError> {
+    let database_version = current_version(pool).await?;
+    let supported_version = migrations.last().map(|m| m.version).unwrap_or(0);
+
+    if database_version > supported_version {
+        return Err(MigrationError::DatabaseTooNew {
+            database: database_version,
+            supported: supported_version,
+        });
+    }
+
+    let mut applied = Vec::new();
+
+    for migration in migrations.iter().filter(|m| m.version > database_version) {
+        let failed = |source: sqlx::Error| MigrationError::Failed {
+            version: migration.version,
+            name: migration.name.clone(),
+            source,
+        };
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(&migration.sql)
+            .execute(&mut *tx)
+            .await
+            .map_err(failed)?;
+
+        sqlx::query("INSERT INTO schema_version (version, name, applied_at) VALUES (?, ?, datetime('now'))")
+            .bind(migration.version)
+            .bind(&migration.name)
+            .execute(&mut *tx)
+            .await
+            .map_err(failed)?;
+
+        tx.commit().await.map_err(failed)?;
+
+        tracing::info!("Applied migration {} ({})", migration.version, migration.name);
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+async fn ensure_version_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn memory_pool() -> SqlitePool {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    fn migration(version: i64, sql: &str) -> Migration {
+        Migration {
+            version,
+            name: format!("step_{version}"),
+            sql: sql.to_string(),
+        }
+    }
+
+    #[test]
+    fn file_names_parse_into_version_and_name() {
+        assert_eq!(
+            parse_file_name("001_initial.sql").unwrap(),
+            (1, "initial".to_string())
+        );
+        assert!(matches!(
+            parse_file_name("initial.sql"),
+            Err(MigrationError::InvalidFileName(_))
+        ));
+    }
+
+    #[test]
+    fn repository_migrations_are_loadable() {
+        let migrations = load_migrations(Path::new(MIGRATIONS_DIR)).unwrap();
+
+        assert_eq!(migrations[0].version, 1);
+        assert!(migrations.windows(2).all(|pair| pair[0].version < pair[1].version));
+    }
+
+    #[tokio::test]
+    async fn applies_pending_migrations_once() {
+        let pool = memory_pool().await;
+        let migrations = vec![
+            migration(1, "CREATE TABLE a (id INTEGER);"),
+            migration(2, "CREATE TABLE b (id INTEGER);"),
+        ];
+
+        assert_eq!(apply_migrations(&pool, &migrations[..1]).await.unwrap(), vec![1]);
+        assert_eq!(apply_migrations(&pool, &migrations).await.unwrap(), vec![2]);
+        assert!(apply_migrations(&pool, &migrations).await.unwrap().is_empty());
+        assert_eq!(current_version(&pool).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_migration_rolls_back_and_keeps_version() {
+        let pool = memory_pool().await;
+        let migrations = vec![
+            migration(1, "CREATE TABLE a (id INTEGER);"),
+            migration(2, "CREATE TABLE b (id INTEGER); INSERT INTO missing VALUES (1);"),
+        ];
+
+        let err = apply_migrations(&pool, &migrations).await.unwrap_err();
+
+        assert!(matches!(err, MigrationError::Failed { version: 2, .. }));
+        assert_eq!(current_version(&pool).await.unwrap(), 1);
+        let b_exists: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'b'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(b_exists.0, 0);
+    }
+
+    #[tokio::test]
+    async fn refuses_database_newer_than_binary() {
+        let pool = memory_pool().await;
+        let migrations = vec![
+            migration(1, "CREATE TABLE a (id INTEGER);"),
+            migration(2, "CREATE TABLE b (id INTEGER);"),
+        ];
+        apply_migrations(&pool, &migrations).await.unwrap();
+
+        let err = apply_migrations(&pool, &migrations[..1]).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            MigrationError::DatabaseTooNew {
+                database: 2,
+                supported: 1
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn utc_timestamp_migration_normalizes_legacy_formats() {
+        let pool = memory_pool().await;
+        let migrations = load_migrations(Path::new(MIGRATIONS_DIR)).unwrap();
+        let (before, after): (Vec<_>, Vec<_>) = migrations.into_iter().partition(|m| m.version < 9);
+        apply_migrations(&pool, &before).await.unwrap();
+
+        for (id, created_at) in [
+            ("epoch", "1736942400"),
+            ("sqlite", "2025-01-15 12:00:00"),
+            ("rfc3339", "2025-01-15T14:00:00.123+02:00"),
+        ] {
+            sqlx::query(
+                "INSERT INTO rules (id, title, category, content, source, created_at, updated_at) VALUES (?, 't', 'c', 'x', 's', ?, ?)",
+            )
+            .bind(id)
+            .bind(created_at)
+            .bind(created_at)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        apply_migrations(&pool, &after).await.unwrap();
+
+        let rows: Vec<(String, chrono::DateTime<chrono::Utc>)> =
+            sqlx::query_as("SELECT id, updated_at FROM rules ORDER BY id").fetch_all(&pool).await.unwrap();
+        let expected: chrono::DateTime<chrono::Utc> = "2025-01-15T12:00:00Z".parse().unwrap();
+        assert!(rows.iter().all(|(_, updated_at)| *updated_at == expected), "{:?}", rows);
+    }
+}
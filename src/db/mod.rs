@@ -1,3 +1,9 @@
+pub mod migrations;
+mod repository;
 mod sqlite;
 
+pub use migrations::MigrationError;
+pub use repository::{
+    purge_expired_trash, InMemoryRuleRepository, RepositoryFuture, RuleRepository, SqliteRuleRepository,
+};
 pub use sqlite::*;
@@ -0,0 +1,697 @@
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::RwLock;
+
+use super::sqlite;
+use crate::models::{now_utc, HomebrewCollection, Rule, RuleFilter, Source, TrashedRule};
+
+/// Boxed future returned by [`RuleRepository`] methods, so the trait can be
+/// used as `dyn RuleRepository`
+pub type RepositoryFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, sqlx::Error>> + Send + 'a>>;
+
+/// Storage for the rules themselves.
+///
+/// Side tables (tags, errata, spells, equipment, revisions, links) stay on the
+/// SQLite pool; this covers the rule reads and writes the routes depend on, so
+/// the router can run against [`InMemoryRuleRepository`] in tests.
+///
+/// Deleting moves a rule to the trash. Trashed rules are invisible to every
+/// read method until restored; `upsert` still updates them in place.
+pub trait RuleRepository: Send + Sync {
+    fn get<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, Option<Rule>>;
+
+    /// Rules with the given IDs, in the order given; unknown IDs are skipped
+    fn get_many<'a>(&'a self, ids: &'a [String]) -> RepositoryFuture<'a, Vec<Rule>>;
+
+    /// Every rule passing `filter`, ordered by category and title
+    fn list<'a>(&'a self, filter: &'a RuleFilter) -> RepositoryFuture<'a, Vec<Rule>>;
+
+    /// Full-text search with an FTS5 match expression such as
+    /// `grapple OR escape` or `"armor class" OR ac`, best match first
+    fn search<'a>(&'a self, query: &'a str, limit: i32, filter: &'a RuleFilter) -> RepositoryFuture<'a, Vec<Rule>>;
+
+    /// Substring search over title, content and category, title matches first
+    fn fuzzy_search<'a>(
+        &'a self,
+        query: &'a str,
+        limit: i32,
+        filter: &'a RuleFilter,
+    ) -> RepositoryFuture<'a, Vec<Rule>>;
+
+    /// Every term in the full-text index with the number of rules passing
+    /// `filter` that contain it, for spelling corrections
+    fn vocabulary<'a>(&'a self, filter: &'a RuleFilter) -> RepositoryFuture<'a, HashMap<String, i64>>;
+
+    /// Insert or update a rule; returns true when the rule was new
+    fn upsert<'a>(&'a self, rule: &'a Rule, changed_by: &'a str) -> RepositoryFuture<'a, bool>;
+
+    /// Move a rule to the trash; returns false if there was nothing to trash
+    fn delete<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, bool>;
+
+    /// Take a rule back out of the trash; returns false if it was not trashed
+    fn restore<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, bool>;
+
+    /// Permanently remove a rule, trashed or not
+    fn purge<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, ()>;
+
+    /// Rules in the trash, most recently deleted first
+    fn trash(&self) -> RepositoryFuture<'_, Vec<TrashedRule>>;
+}
+
+/// Purge rules that have been in the trash longer than `retention_days`
+/// (0 keeps them forever). Returns the IDs of the purged rules.
+pub async fn purge_expired_trash(rules: &dyn RuleRepository, retention_days: u32) -> Result<Vec<String>, sqlx::Error> {
+    let now = now_utc();
+    let mut purged = Vec::new();
+
+    for trashed in rules.trash().await? {
+        if trashed.purge_due(retention_days).is_some_and(|due| due <= now) {
+            rules.purge(&trashed.id).await?;
+            purged.push(trashed.id);
+        }
+    }
+
+    Ok(purged)
+}
+
+/// The production repository, backed by the `db::sqlite` functions
+pub struct SqliteRuleRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteRuleRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+impl RuleRepository for SqliteRuleRepository {
+    fn get<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, Option<Rule>> {
+        Box::pin(sqlite::get_rule_by_id(&self.pool, id))
+    }
+
+    fn get_many<'a>(&'a self, ids: &'a [String]) -> RepositoryFuture<'a, Vec<Rule>> {
+        Box::pin(sqlite::get_rules_by_ids(&self.pool, ids))
+    }
+
+    fn list<'a>(&'a self, filter: &'a RuleFilter) -> RepositoryFuture<'a, Vec<Rule>> {
+        Box::pin(sqlite::get_rules_filtered(&self.pool, filter))
+    }
+
+    fn search<'a>(&'a self, query: &'a str, limit: i32, filter: &'a RuleFilter) -> RepositoryFuture<'a, Vec<Rule>> {
+        Box::pin(sqlite::search_rules_fts(&self.pool, query, limit, filter))
+    }
+
+    fn fuzzy_search<'a>(
+        &'a self,
+        query: &'a str,
+        limit: i32,
+        filter: &'a RuleFilter,
+    ) -> RepositoryFuture<'a, Vec<Rule>> {
+        Box::pin(sqlite::fuzzy_search(&self.pool, query, limit, filter))
+    }
+
+    fn vocabulary<'a>(&'a self, filter: &'a RuleFilter) -> RepositoryFuture<'a, HashMap<String, i64>> {
+        Box::pin(sqlite::get_search_vocabulary(&self.pool, filter))
+    }
+
+    fn upsert<'a>(&'a self, rule: &'a Rule, changed_by: &'a str) -> RepositoryFuture<'a, bool> {
+        Box::pin(sqlite::upsert_rule(&self.pool, rule, changed_by))
+    }
+
+    fn delete<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, bool> {
+        Box::pin(sqlite::trash_rule(&self.pool, id))
+    }
+
+    fn restore<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, bool> {
+        Box::pin(sqlite::restore_rule(&self.pool, id))
+    }
+
+    fn purge<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, ()> {
+        Box::pin(sqlite::purge_rule(&self.pool, id))
+    }
+
+    fn trash(&self) -> RepositoryFuture<'_, Vec<TrashedRule>> {
+        Box::pin(sqlite::get_trashed_rules(&self.pool))
+    }
+}
+
+/// A repository holding rules in memory, for tests.
+///
+/// Rules keep the tags they were upserted with, which `RuleFilter::tags` is
+/// checked against. No revision history is kept, and search approximates
+/// FTS5: terms are matched as whole words (or prefixes with a trailing `*`),
+/// and rules matching more of the terms rank first, then those matching more
+/// often, counting title hits higher.
+#[derive(Default)]
+pub struct InMemoryRuleRepository {
+    rules: RwLock<HashMap<String, Rule>>,
+    /// When each trashed rule was deleted
+    trashed: RwLock<HashMap<String, DateTime<Utc>>>,
+    sources: Vec<Source>,
+    collections: Vec<HomebrewCollection>,
+}
+
+impl InMemoryRuleRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sources used to link rules by their `source` text and to apply
+    /// source and edition filters
+    pub fn with_sources(mut self, sources: Vec<Source>) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Homebrew collections whose visibility the homebrew filters check
+    pub fn with_collections(mut self, collections: Vec<HomebrewCollection>) -> Self {
+        self.collections = collections;
+        self
+    }
+
+    pub fn with_rules(self, rules: impl IntoIterator<Item = Rule>) -> Self {
+        {
+            let mut stored = self.rules.write().unwrap();
+            for rule in rules {
+                let rule = self.link_source(rule);
+                stored.insert(rule.id.clone(), rule);
+            }
+        }
+        self
+    }
+
+    fn link_source(&self, mut rule: Rule) -> Rule {
+        if rule.source_id.is_none() {
+            let text = rule.source.trim();
+            rule.source_id = self
+                .sources
+                .iter()
+                .find(|s| s.name.eq_ignore_ascii_case(text) || s.abbreviation.eq_ignore_ascii_case(text))
+                .map(|s| s.abbreviation.clone());
+        }
+        rule
+    }
+
+    fn matches(&self, rule: &Rule, filter: &RuleFilter) -> bool {
+        let source = rule
+            .source_id
+            .as_deref()
+            .and_then(|id| self.sources.iter().find(|s| s.abbreviation == id));
+
+        filter.matches_source(source)
+            && filter.matches_collection(rule.collection_id.as_deref(), &self.collections)
+            && filter.matches_placement(&rule.category, rule.subcategory.as_deref(), rule.page)
+            && filter
+                .tags
+                .iter()
+                .all(|slug| rule.tags.iter().any(|tag| &tag.slug == slug))
+            && !self.contains_excluded_term(rule, filter)
+    }
+
+    fn contains_excluded_term(&self, rule: &Rule, filter: &RuleFilter) -> bool {
+        if filter.excluded_terms.is_empty() {
+            return false;
+        }
+        let title = words(&rule.title);
+        let body: Vec<String> = words(&rule.content).into_iter().chain(words(&rule.category)).collect();
+        filter
+            .excluded_terms
+            .iter()
+            .flat_map(|term| fts_clauses(term).into_iter().flatten())
+            .any(|term| term.hits(&title, &body) > 0)
+    }
+
+    fn is_trashed(&self, id: &str) -> bool {
+        self.trashed.read().unwrap().contains_key(id)
+    }
+
+    fn filtered(&self, filter: &RuleFilter) -> Vec<Rule> {
+        let mut rules: Vec<Rule> = self
+            .rules
+            .read()
+            .unwrap()
+            .values()
+            .filter(|rule| !self.is_trashed(&rule.id) && self.matches(rule, filter))
+            .cloned()
+            .collect();
+        rules.sort_by(|a, b| a.category.cmp(&b.category).then_with(|| a.title.cmp(&b.title)));
+        rules
+    }
+
+    fn search_sync(&self, query: &str, limit: i32, filter: &RuleFilter) -> Vec<Rule> {
+        let clauses = fts_clauses(query);
+
+        // Rank by how many distinct terms match, then by weighted hit count
+        let mut scored: Vec<((usize, usize), Rule)> = self
+            .filtered(filter)
+            .into_iter()
+            .filter_map(|rule| {
+                let title = words(&rule.title);
+                let body: Vec<String> = words(&rule.content).into_iter().chain(words(&rule.category)).collect();
+                let hits: Vec<Vec<usize>> = clauses
+                    .iter()
+                    .map(|clause| clause.iter().map(|term| term.hits(&title, &body)).collect())
+                    .collect();
+                if hits.is_empty() || !hits.iter().all(|clause| clause.iter().any(|hits| *hits > 0)) {
+                    return None;
+                }
+                let matched = hits.iter().flatten().filter(|hits| **hits > 0).count();
+                Some(((matched, hits.iter().flatten().sum()), rule))
+            })
+            .collect();
+
+        scored.sort_by(|(a_score, a), (b_score, b)| b_score.cmp(a_score).then_with(|| a.title.cmp(&b.title)));
+        scored
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|(_, rule)| rule)
+            .collect()
+    }
+
+    fn fuzzy_search_sync(&self, query: &str, limit: i32, filter: &RuleFilter) -> Vec<Rule> {
+        let needle = query.to_lowercase();
+
+        let mut ranked: Vec<(u8, Rule)> = self
+            .filtered(filter)
+            .into_iter()
+            .filter_map(|rule| {
+                let title = rule.title.to_lowercase();
+                let category = rule.category.to_lowercase();
+                let rank = if title.starts_with(&needle) {
+                    1
+                } else if title.contains(&needle) {
+                    2
+                } else if category.contains(&needle) {
+                    3
+                } else if rule.content.to_lowercase().contains(&needle) {
+                    4
+                } else {
+                    return None;
+                };
+                Some((rank, rule))
+            })
+            .collect();
+
+        ranked.sort_by(|(a_rank, a), (b_rank, b)| a_rank.cmp(b_rank).then_with(|| a.title.cmp(&b.title)));
+        ranked
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|(_, rule)| rule)
+            .collect()
+    }
+
+    fn upsert_sync(&self, rule: &Rule) -> bool {
+        let rule = self.link_source(rule.clone());
+        let mut rules = self.rules.write().unwrap();
+        let now = now_utc();
+
+        match rules.get_mut(&rule.id) {
+            Some(existing) => {
+                if !sqlite::same_rule_fields(existing, &rule) || existing.tags != rule.tags {
+                    *existing = Rule {
+                        created_at: existing.created_at,
+                        updated_at: now,
+                        ..rule
+                    };
+                }
+                false
+            }
+            None => {
+                rules.insert(
+                    rule.id.clone(),
+                    Rule {
+                        created_at: now,
+                        updated_at: now,
+                        ..rule
+                    },
+                );
+                true
+            }
+        }
+    }
+}
+
+impl RuleRepository for InMemoryRuleRepository {
+    fn get<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, Option<Rule>> {
+        let rule = self.rules.read().unwrap().get(id).filter(|_| !self.is_trashed(id)).cloned();
+        Box::pin(async move { Ok(rule) })
+    }
+
+    fn get_many<'a>(&'a self, ids: &'a [String]) -> RepositoryFuture<'a, Vec<Rule>> {
+        let rules = {
+            let stored = self.rules.read().unwrap();
+            ids.iter()
+                .filter(|id| !self.is_trashed(id))
+                .filter_map(|id| stored.get(id).cloned())
+                .collect()
+        };
+        Box::pin(async move { Ok(rules) })
+    }
+
+    fn list<'a>(&'a self, filter: &'a RuleFilter) -> RepositoryFuture<'a, Vec<Rule>> {
+        let rules = self.filtered(filter);
+        Box::pin(async move { Ok(rules) })
+    }
+
+    fn search<'a>(&'a self, query: &'a str, limit: i32, filter: &'a RuleFilter) -> RepositoryFuture<'a, Vec<Rule>> {
+        let rules = self.search_sync(query, limit, filter);
+        Box::pin(async move { Ok(rules) })
+    }
+
+    fn fuzzy_search<'a>(
+        &'a self,
+        query: &'a str,
+        limit: i32,
+        filter: &'a RuleFilter,
+    ) -> RepositoryFuture<'a, Vec<Rule>> {
+        let rules = self.fuzzy_search_sync(query, limit, filter);
+        Box::pin(async move { Ok(rules) })
+    }
+
+    fn vocabulary<'a>(&'a self, filter: &'a RuleFilter) -> RepositoryFuture<'a, HashMap<String, i64>> {
+        let mut vocabulary: HashMap<String, i64> = HashMap::new();
+        for rule in self.filtered(filter) {
+            let fields = [&rule.title, &rule.category, &rule.source, &rule.content];
+            let mut terms: Vec<String> = fields
+                .into_iter()
+                .chain(rule.subcategory.as_ref())
+                .flat_map(|text| words(text))
+                .collect();
+            terms.sort();
+            terms.dedup();
+            for term in terms {
+                *vocabulary.entry(term).or_insert(0) += 1;
+            }
+        }
+        Box::pin(async move { Ok(vocabulary) })
+    }
+
+    fn upsert<'a>(&'a self, rule: &'a Rule, _changed_by: &'a str) -> RepositoryFuture<'a, bool> {
+        let is_new = self.upsert_sync(rule);
+        Box::pin(async move { Ok(is_new) })
+    }
+
+    fn delete<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, bool> {
+        let trashed = self.rules.read().unwrap().contains_key(id) && {
+            let mut trashed = self.trashed.write().unwrap();
+            !trashed.contains_key(id) && trashed.insert(id.to_string(), now_utc()).is_none()
+        };
+        Box::pin(async move { Ok(trashed) })
+    }
+
+    fn restore<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, bool> {
+        let restored = self.trashed.write().unwrap().remove(id).is_some();
+        Box::pin(async move { Ok(restored) })
+    }
+
+    fn purge<'a>(&'a self, id: &'a str) -> RepositoryFuture<'a, ()> {
+        self.rules.write().unwrap().remove(id);
+        self.trashed.write().unwrap().remove(id);
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn trash(&self) -> RepositoryFuture<'_, Vec<TrashedRule>> {
+        let mut trash: Vec<TrashedRule> = {
+            let rules = self.rules.read().unwrap();
+            self.trashed
+                .read()
+                .unwrap()
+                .iter()
+                .filter_map(|(id, deleted_at)| {
+                    rules.get(id).map(|rule| TrashedRule {
+                        id: rule.id.clone(),
+                        title: rule.title.clone(),
+                        category: rule.category.clone(),
+                        source: rule.source.clone(),
+                        deleted_at: *deleted_at,
+                    })
+                })
+                .collect()
+        };
+        trash.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then_with(|| a.title.cmp(&b.title)));
+        Box::pin(async move { Ok(trash) })
+    }
+}
+
+/// Lowercased words the way the FTS5 unicode61 tokenizer splits them
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// A term of a match expression: a word or quoted phrase, the last word
+/// ending in `*` for a prefix, optionally restricted to the title
+struct FtsTerm {
+    words: Vec<String>,
+    title_only: bool,
+}
+
+impl FtsTerm {
+    fn hits(&self, title: &[String], body: &[String]) -> usize {
+        let in_title = count_matches(title, &self.words);
+        if self.title_only {
+            in_title
+        } else {
+            10 * in_title + count_matches(body, &self.words)
+        }
+    }
+}
+
+/// The clauses of a match expression such as
+/// `"bonus action" AND title:"grapple" AND ("grap"* OR escape)`: every
+/// clause must match, and a clause matches when any of its terms does
+fn fts_clauses(query: &str) -> Vec<Vec<FtsTerm>> {
+    let mut clauses = Vec::new();
+    let mut clause = Vec::new();
+    let mut title_only = false;
+    let mut rest = query;
+
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace() && c != '(' && c != ')') {
+        rest = &rest[start..];
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let mut phrase = words(&quoted[..end]);
+            rest = quoted.get(end + 1..).unwrap_or_default();
+            if let Some(after) = rest.strip_prefix('*') {
+                if let Some(last) = phrase.last_mut() {
+                    last.push('*');
+                }
+                rest = after;
+            }
+            if !phrase.is_empty() {
+                clause.push(FtsTerm { words: phrase, title_only });
+            }
+            title_only = false;
+            continue;
+        }
+
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == '"')
+            .unwrap_or(rest.len());
+        let token = &rest[..end];
+        rest = &rest[end..];
+        match token {
+            "OR" => {}
+            "AND" => clauses.push(std::mem::take(&mut clause)),
+            "title:" => title_only = true,
+            word => {
+                clause.push(FtsTerm {
+                    words: vec![word.to_lowercase()],
+                    title_only,
+                });
+                title_only = false;
+            }
+        }
+    }
+
+    clauses.push(clause);
+    clauses.retain(|clause| !clause.is_empty());
+    clauses
+}
+
+fn count_matches(words: &[String], phrase: &[String]) -> usize {
+    words
+        .windows(phrase.len())
+        .filter(|window| {
+            window.iter().zip(phrase).all(|(word, term)| match term.strip_suffix('*') {
+                Some(prefix) => word.starts_with(prefix),
+                None => word == term,
+            })
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Tag;
+
+    fn rule(id: &str, title: &str, content: &str, source: &str) -> Rule {
+        Rule {
+            source: source.to_string(),
+            ..Rule::test(id, title, content)
+        }
+    }
+
+    fn source(abbreviation: &str, name: &str, edition: &str) -> Source {
+        Source {
+            abbreviation: abbreviation.to_string(),
+            name: name.to_string(),
+            edition: edition.to_string(),
+            published_year: None,
+            display_order: 0,
+        }
+    }
+
+    fn fixtures() -> Vec<Rule> {
+        vec![
+            rule("grappled", "Grappled", "Your speed is 0 while grappled.", "Player's Handbook 2024"),
+            rule("escape", "Escaping a Grapple", "Use an action to escape.", "Player's Handbook 2024"),
+            rule("chases", "Chases", "A grappled quarry can't flee.", "Dungeon Master's Guide 2024"),
+        ]
+    }
+
+    /// Run the same checks against both implementations
+    async fn check_repository(repository: &dyn RuleRepository) {
+        assert_eq!(repository.get("escape").await.unwrap().unwrap().source_id.as_deref(), Some("PHB"));
+        assert!(repository.get("missing").await.unwrap().is_none());
+
+        let ids = vec!["chases".to_string(), "missing".to_string(), "grappled".to_string()];
+        let found: Vec<_> = repository.get_many(&ids).await.unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(found, vec!["chases", "grappled"]);
+
+        let phb_only = RuleFilter::from_params(None, Some("DMG"), None);
+        let listed: Vec<_> = repository.list(&phb_only).await.unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(listed, vec!["escape", "grappled"]);
+
+        let hits: Vec<_> = repository
+            .search("grappled OR flee", 20, &RuleFilter::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(hits, vec!["chases", "grappled"]);
+
+        let prefix: Vec<_> = repository
+            .search("grappl*", 20, &phb_only)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(prefix.len(), 2);
+
+        let required: Vec<_> = repository
+            .search(r#""speed" AND title:"grappled" AND ("flee" OR "grap"*)"#, 20, &RuleFilter::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(required, vec!["grappled"]);
+
+        let without_flee = RuleFilter {
+            excluded_terms: vec![r#""flee""#.to_string()],
+            ..RuleFilter::default()
+        };
+        assert_eq!(repository.search("grappl*", 20, &without_flee).await.unwrap().len(), 2);
+        assert!(repository.list(&without_flee).await.unwrap().iter().all(|r| r.id != "chases"));
+
+        let fuzzy: Vec<_> = repository
+            .fuzzy_search("grap", 10, &RuleFilter::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(fuzzy, vec!["grappled", "escape", "chases"]);
+
+        let mut changed = repository.get("escape").await.unwrap().unwrap();
+        changed.content = "Use an action to escape the grapple.".to_string();
+        assert!(!repository.upsert(&changed, "dm").await.unwrap());
+        assert!(repository.get("escape").await.unwrap().unwrap().content.ends_with("the grapple."));
+
+        assert!(repository.delete("chases").await.unwrap());
+        assert!(!repository.delete("chases").await.unwrap());
+        assert!(repository.get("chases").await.unwrap().is_none());
+        assert!(repository.search("flee", 20, &RuleFilter::default()).await.unwrap().is_empty());
+        let trash: Vec<_> = repository.trash().await.unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(trash, vec!["chases"]);
+
+        assert!(repository.restore("chases").await.unwrap());
+        assert!(repository.get("chases").await.unwrap().is_some());
+
+        repository.delete("chases").await.unwrap();
+        assert!(purge_expired_trash(repository, 30).await.unwrap().is_empty());
+        repository.purge("chases").await.unwrap();
+        assert!(repository.trash().await.unwrap().is_empty());
+        assert!(!repository.restore("chases").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn sqlite_repository_reads_and_writes_rules() {
+        let pool = sqlite::test_pool().await;
+        let repository = SqliteRuleRepository::new(pool);
+        for rule in fixtures() {
+            assert!(repository.upsert(&rule, "test").await.unwrap());
+        }
+
+        check_repository(&repository).await;
+    }
+
+    #[tokio::test]
+    async fn in_memory_repository_matches_sqlite_behaviour() {
+        let repository = InMemoryRuleRepository::new().with_sources(vec![
+            source("PHB", "Player's Handbook 2024", "2024"),
+            source("DMG", "Dungeon Master's Guide 2024", "2024"),
+        ]);
+        for rule in fixtures() {
+            assert!(repository.upsert(&rule, "test").await.unwrap());
+        }
+
+        check_repository(&repository).await;
+    }
+
+    #[tokio::test]
+    async fn purge_expired_trash_removes_only_old_trashed_rules() {
+        let pool = sqlite::test_pool().await;
+        let repository = SqliteRuleRepository::new(pool.clone());
+        for rule in fixtures() {
+            repository.upsert(&rule, "test").await.unwrap();
+        }
+        repository.delete("chases").await.unwrap();
+        repository.delete("grappled").await.unwrap();
+        sqlx::query("UPDATE rules SET deleted_at = '2020-01-01T00:00:00+00:00' WHERE id = 'chases'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(purge_expired_trash(&repository, 0).await.unwrap().is_empty());
+        assert_eq!(purge_expired_trash(&repository, 30).await.unwrap(), vec!["chases"]);
+        let trash: Vec<_> = repository.trash().await.unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(trash, vec!["grappled"]);
+        assert!(!repository.restore("chases").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn in_memory_repository_filters_by_tag() {
+        let mut tagged = rule("grappled", "Grappled", "Speed 0.", "PHB");
+        tagged.tags = vec![Tag {
+            slug: "condition".to_string(),
+            name: "Condition".to_string(),
+        }];
+        let repository = InMemoryRuleRepository::new().with_rules([tagged, rule("cover", "Cover", "AC +2.", "PHB")]);
+
+        let mut filter = RuleFilter::default();
+        filter.tags.push("condition".to_string());
+        let ids: Vec<_> = repository.list(&filter).await.unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec!["grappled"]);
+    }
+}
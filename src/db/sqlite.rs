@@ -1,7 +1,14 @@
-use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
-use crate::models::Rule;
+use sqlx::{sqlite::SqlitePoolOptions, QueryBuilder, Sqlite, SqlitePool};
+use crate::models::{
+    now_utc, slugify, weapon_property_names, Armor, EquipmentFacets, EquipmentFilter, EquipmentKind, EquipmentSet, EquipmentSort, Erratum, Gear,
+    GlossaryTerm, HomebrewCollection, Rule, RuleFilter, RuleLink, RuleRevision, SearchHighlight, Source, Spell, SpellFilter,
+    QueuedVectorSync, Synonym, Tag, TagSummary, TrashedRule, VectorRecord, Weapon, MATCH_END, MATCH_START, SNIPPET_WORDS,
+};
+use std::collections::HashMap;
 use std::path::Path;
 
+use super::migrations::{apply_migrations, load_migrations, MigrationError, MIGRATIONS_DIR};
+
 pub async fn init_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
     // Create database file if it doesn't exist
     if database_url.starts_with("sqlite:") {
@@ -18,50 +25,11 @@ pub async fn init_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
         .await
 }
 
-pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    // Create schema
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS rules (
-            id TEXT PRIMARY KEY,
-            title TEXT NOT NULL,
-            category TEXT NOT NULL,
-            subcategory TEXT,
-            content TEXT NOT NULL,
-            source TEXT NOT NULL,
-            page INTEGER,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
-        );
-
-        CREATE VIRTUAL TABLE IF NOT EXISTS rules_fts USING fts5(
-            title,
-            content,
-            category,
-            content='rules',
-            content_rowid='rowid'
-        );
-
-        CREATE TRIGGER IF NOT EXISTS rules_ai AFTER INSERT ON rules BEGIN
-            INSERT INTO rules_fts(rowid, title, content, category)
-            VALUES (NEW.rowid, NEW.title, NEW.content, NEW.category);
-        END;
-
-        CREATE TRIGGER IF NOT EXISTS rules_ad AFTER DELETE ON rules BEGIN
-            INSERT INTO rules_fts(rules_fts, rowid, title, content, category)
-            VALUES ('delete', OLD.rowid, OLD.title, OLD.content, OLD.category);
-        END;
-
-        CREATE TRIGGER IF NOT EXISTS rules_au AFTER UPDATE ON rules BEGIN
-            INSERT INTO rules_fts(rules_fts, rowid, title, content, category)
-            VALUES ('delete', OLD.rowid, OLD.title, OLD.content, OLD.category);
-            INSERT INTO rules_fts(rowid, title, content, category)
-            VALUES (NEW.rowid, NEW.title, NEW.content, NEW.category);
-        END;
-        "#,
-    )
-    .execute(pool)
-    .await?;
+/// Bring the schema up to date using the numbered files in `migrations/`,
+/// then seed a handful of rules when the database is empty.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<(), MigrationError> {
+    let migrations = load_migrations(Path::new(MIGRATIONS_DIR))?;
+    apply_migrations(pool, &migrations).await?;
 
     // Seed initial data if table is empty
     let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM rules")
@@ -98,59 +66,73 @@ async fn seed_rules(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         VALUES
             ('adv-disadv', 'Advantage and Disadvantage', 'Combat', 'Rolling',
              'Sometimes a special ability or spell tells you that you have advantage or disadvantage on an ability check, a saving throw, or an attack roll. When that happens, you roll a second d20 when you make the roll. Use the higher of the two rolls if you have advantage, and use the lower roll if you have disadvantage.',
-             'Player''s Handbook 2024', 25, datetime('now'), datetime('now')),
+             'Player''s Handbook 2024', 25, ?1, ?1),
 
             ('sneak-attack', 'Sneak Attack', 'Combat', 'Class Features',
              'Once per turn, you can deal extra 1d6 damage to one creature you hit with an attack if you have advantage on the attack roll. The attack must use a finesse or a ranged weapon. You don''t need advantage on the attack roll if another enemy of the target is within 5 feet of it, that enemy isn''t incapacitated, and you don''t have disadvantage on the attack roll.',
-             'Player''s Handbook 2024', 98, datetime('now'), datetime('now')),
+             'Player''s Handbook 2024', 98, ?1, ?1),
 
             ('opportunity-attack', 'Opportunity Attack', 'Combat', 'Actions',
              'You can make an opportunity attack when a hostile creature that you can see moves out of your reach. To make the opportunity attack, you use your reaction to make one melee attack against the provoking creature. The attack occurs right before the creature leaves your reach.',
-             'Player''s Handbook 2024', 195, datetime('now'), datetime('now')),
+             'Player''s Handbook 2024', 195, ?1, ?1),
 
             ('concentration', 'Concentration', 'Spellcasting', 'Mechanics',
              'Some spells require you to maintain concentration to keep their magic active. If you lose concentration, such a spell ends. Taking damage can break your concentration. When you take damage while concentrating on a spell, make a Constitution saving throw. The DC equals 10 or half the damage taken, whichever is higher.',
-             'Player''s Handbook 2024', 233, datetime('now'), datetime('now')),
+             'Player''s Handbook 2024', 233, ?1, ?1),
 
             ('cover', 'Cover', 'Combat', 'Environment',
              'Walls, trees, creatures, and other obstacles can provide cover during combat. Half cover grants +2 to AC and Dexterity saving throws. Three-quarters cover grants +5 to AC and Dexterity saving throws. Total cover means a target cannot be targeted directly by an attack or spell.',
-             'Player''s Handbook 2024', 196, datetime('now'), datetime('now')),
+             'Player''s Handbook 2024', 196, ?1, ?1),
 
             ('attack-action', 'Attack Action', 'Combat', 'Actions',
              'When you take the Attack action, you make one melee or ranged attack. Certain features, such as the Extra Attack feature of the Fighter, let you make more than one attack with this action.',
-             'Player''s Handbook 2024', 189, datetime('now'), datetime('now')),
+             'Player''s Handbook 2024', 189, ?1, ?1),
 
             ('dash-action', 'Dash Action', 'Combat', 'Actions',
              'When you take the Dash action, you gain extra movement for the current turn. The increase equals your speed, after applying any modifiers.',
-             'Player''s Handbook 2024', 189, datetime('now'), datetime('now')),
+             'Player''s Handbook 2024', 189, ?1, ?1),
 
             ('dodge-action', 'Dodge Action', 'Combat', 'Actions',
              'When you take the Dodge action, you focus entirely on avoiding attacks. Until the start of your next turn, any attack roll made against you has disadvantage if you can see the attacker, and you make Dexterity saving throws with advantage.',
-             'Player''s Handbook 2024', 189, datetime('now'), datetime('now')),
+             'Player''s Handbook 2024', 189, ?1, ?1),
 
             ('prone', 'Prone Condition', 'Conditions', NULL,
              'A prone creature''s only movement option is to crawl, unless it stands up. The creature has disadvantage on attack rolls. An attack roll against the creature has advantage if the attacker is within 5 feet. Otherwise, the attack roll has disadvantage.',
-             'Player''s Handbook 2024', 369, datetime('now'), datetime('now')),
+             'Player''s Handbook 2024', 369, ?1, ?1),
 
             ('grappled', 'Grappled Condition', 'Conditions', NULL,
              'A grappled creature''s speed becomes 0, and it can''t benefit from any bonus to its speed. The condition ends if the grappler is incapacitated or if an effect removes the grappled creature from the grappler''s reach.',
-             'Player''s Handbook 2024', 367, datetime('now'), datetime('now'))
+             'Player''s Handbook 2024', 367, ?1, ?1)
     "#)
+    .bind(now_utc())
     .execute(pool)
     .await?;
 
+    link_rule_sources(pool).await?;
+
     tracing::info!("Seeded {} initial rules", 10);
     Ok(())
 }
 
+/// Every rule not in the trash
 pub async fn get_all_rules(pool: &SqlitePool) -> Result<Vec<Rule>, sqlx::Error> {
-    sqlx::query_as::<_, Rule>("SELECT * FROM rules ORDER BY category, title")
+    sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE deleted_at IS NULL ORDER BY category, title")
         .fetch_all(pool)
         .await
 }
 
+/// All rules from the sources allowed by `filter`
+pub async fn get_rules_filtered(pool: &SqlitePool, filter: &RuleFilter) -> Result<Vec<Rule>, sqlx::Error> {
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM rules WHERE 1 = 1");
+    push_rule_filter(&mut query, filter, "");
+    query.push(" ORDER BY category, title");
+
+    query.build_query_as::<Rule>().fetch_all(pool).await
+}
+
+/// The rule with `id`, unless it is in the trash
 pub async fn get_rule_by_id(pool: &SqlitePool, id: &str) -> Result<Option<Rule>, sqlx::Error> {
-    sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE id = ?")
+    sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE id = ? AND deleted_at IS NULL")
         .bind(id)
         .fetch_optional(pool)
         .await
@@ -169,10 +151,15 @@ pub async fn get_rules_by_ids(pool: &SqlitePool, ids: &[String]) -> Result<Vec<R
 }
 
 pub async fn create_rule(pool: &SqlitePool, rule: &Rule) -> Result<(), sqlx::Error> {
+    let source_id = match &rule.source_id {
+        Some(source_id) => Some(source_id.clone()),
+        None => resolve_source(pool, &rule.source).await?.map(|s| s.abbreviation),
+    };
+
     sqlx::query(
         r#"
-        INSERT INTO rules (id, title, category, subcategory, content, source, page, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO rules (id, title, category, subcategory, content, source, source_id, page, created_at, updated_at, collection_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(&rule.id)
@@ -181,72 +168,285 @@ pub async fn create_rule(pool: &SqlitePool, rule: &Rule) -> Result<(), sqlx::Err
     .bind(&rule.subcategory)
     .bind(&rule.content)
     .bind(&rule.source)
-    .bind(&rule.page)
-    .bind(&rule.created_at)
-    .bind(&rule.updated_at)
+    .bind(&source_id)
+    .bind(rule.page)
+    .bind(rule.created_at)
+    .bind(rule.updated_at)
+    .bind(&rule.collection_id)
     .execute(pool)
     .await?;
 
     Ok(())
 }
 
-pub async fn search_rules_fts(pool: &SqlitePool, query: &str) -> Result<Vec<Rule>, sqlx::Error> {
-    sqlx::query_as::<_, Rule>(
+/// bm25() weights for the `rules_fts` columns title, category, subcategory,
+/// source and content: a title hit outranks a category hit, which outranks
+/// a word in the text
+const FTS_COLUMN_WEIGHTS: [f64; 5] = [10.0, 5.0, 3.0, 1.0, 1.0];
+
+/// A full-text hit with its FTS5 highlights
+#[derive(sqlx::FromRow)]
+struct FtsHit {
+    #[sqlx(flatten)]
+    rule: Rule,
+    title_marked: String,
+    snippet_marked: String,
+}
+
+/// Full-text search ranked by weighted bm25(); each rule comes with its
+/// title and a passage of its text highlighted
+pub async fn search_rules_fts(
+    pool: &SqlitePool,
+    query: &str,
+    limit: i32,
+    filter: &RuleFilter,
+) -> Result<Vec<Rule>, sqlx::Error> {
+    let weights = FTS_COLUMN_WEIGHTS.map(|weight| weight.to_string()).join(", ");
+    let mut sql = QueryBuilder::<Sqlite>::new(format!(
         r#"
-        SELECT r.* FROM rules r
+        SELECT r.*,
+            highlight(rules_fts, 0, char({start}), char({end})) AS title_marked,
+            snippet(rules_fts, 4, char({start}), char({end}), '…', {words}) AS snippet_marked
+        FROM rules r
         JOIN rules_fts fts ON r.rowid = fts.rowid
-        WHERE rules_fts MATCH ?
-        ORDER BY rank
-        LIMIT 20
-        "#,
-    )
-    .bind(query)
-    .fetch_all(pool)
-    .await
+        WHERE rules_fts MATCH "#,
+        start = MATCH_START as u32,
+        end = MATCH_END as u32,
+        words = SNIPPET_WORDS,
+    ));
+    sql.push_bind(query);
+    push_rule_filter(&mut sql, filter, "r.");
+    sql.push(format!(" ORDER BY bm25(rules_fts, {weights}) LIMIT "))
+        .push_bind(limit);
+
+    let hits = sql.build_query_as::<FtsHit>().fetch_all(pool).await?;
+    Ok(hits
+        .into_iter()
+        .map(|hit| Rule {
+            highlight: Some(SearchHighlight::from_marked(&hit.title_marked, &hit.snippet_marked)),
+            ..hit.rule
+        })
+        .collect())
+}
+
+/// Every term in `rules_fts` with the number of rules passing `filter` that
+/// contain it. Trashed rules stay in the index but are left out here.
+pub async fn get_search_vocabulary(pool: &SqlitePool, filter: &RuleFilter) -> Result<HashMap<String, i64>, sqlx::Error> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT v.term, COUNT(DISTINCT v.doc) FROM rules_vocab_instances v JOIN rules r ON r.rowid = v.doc WHERE 1 = 1",
+    );
+    push_rule_filter(&mut query, filter, "r.");
+    query.push(" GROUP BY v.term");
+
+    let terms: Vec<(String, i64)> = query.build_query_as().fetch_all(pool).await?;
+    Ok(terms.into_iter().collect())
 }
 
 /// Fuzzy search using LIKE patterns and prefix matching
 /// Searches title, content, and category with case-insensitive matching
-pub async fn fuzzy_search(pool: &SqlitePool, query: &str, limit: i32) -> Result<Vec<Rule>, sqlx::Error> {
+pub async fn fuzzy_search(
+    pool: &SqlitePool,
+    query: &str,
+    limit: i32,
+    filter: &RuleFilter,
+) -> Result<Vec<Rule>, sqlx::Error> {
     let query_lower = query.to_lowercase();
     let like_pattern = format!("%{}%", query_lower);
     let prefix_pattern = format!("{}%", query_lower);
 
-    sqlx::query_as::<_, Rule>(
+    let mut sql = QueryBuilder::<Sqlite>::new("SELECT * FROM rules WHERE (LOWER(title) LIKE ");
+    sql.push_bind(&like_pattern)
+        .push(" OR LOWER(content) LIKE ")
+        .push_bind(&like_pattern)
+        .push(" OR LOWER(category) LIKE ")
+        .push_bind(&like_pattern)
+        .push(")");
+    push_rule_filter(&mut sql, filter, "");
+    sql.push(" ORDER BY CASE WHEN LOWER(title) LIKE ")
+        .push_bind(&prefix_pattern)
+        .push(" THEN 1 WHEN LOWER(title) LIKE ")
+        .push_bind(&like_pattern)
+        .push(" THEN 2 WHEN LOWER(category) LIKE ")
+        .push_bind(&like_pattern)
+        .push(" THEN 3 ELSE 4 END, title LIMIT ")
+        .push_bind(limit);
+
+    sql.build_query_as::<Rule>().fetch_all(pool).await
+}
+
+/// Append `AND ...` clauses restricting `{alias}source_id`, the category,
+/// subcategory, page, excluded words, the rule's tags and its homebrew
+/// collection to the filter. Trashed rules are always left out.
+fn push_rule_filter(query: &mut QueryBuilder<'_, Sqlite>, filter: &RuleFilter, alias: &str) {
+    query.push(format!(" AND {alias}deleted_at IS NULL"));
+
+    if !filter.sources.is_empty() {
+        query.push(format!(" AND {alias}source_id IN ("));
+        let mut list = query.separated(", ");
+        for source in &filter.sources {
+            list.push_bind(source.clone());
+        }
+        list.push_unseparated(")");
+    }
+
+    if !filter.excluded_sources.is_empty() {
+        query.push(format!(" AND ({alias}source_id IS NULL OR {alias}source_id NOT IN ("));
+        let mut list = query.separated(", ");
+        for source in &filter.excluded_sources {
+            list.push_bind(source.clone());
+        }
+        list.push_unseparated("))");
+    }
+
+    if let Some(edition) = &filter.edition {
+        query
+            .push(format!(" AND {alias}source_id IN (SELECT abbreviation FROM sources WHERE edition = "))
+            .push_bind(edition.clone())
+            .push(")");
+    }
+
+    if !filter.categories.is_empty() {
+        query.push(format!(" AND LOWER({alias}category) IN ("));
+        let mut list = query.separated(", ");
+        for category in &filter.categories {
+            list.push_bind(category.clone());
+        }
+        list.push_unseparated(")");
+    }
+
+    if !filter.excluded_categories.is_empty() {
+        query.push(format!(" AND LOWER({alias}category) NOT IN ("));
+        let mut list = query.separated(", ");
+        for category in &filter.excluded_categories {
+            list.push_bind(category.clone());
+        }
+        list.push_unseparated(")");
+    }
+
+    if !filter.subcategories.is_empty() {
+        query.push(format!(" AND LOWER({alias}subcategory) IN ("));
+        let mut list = query.separated(", ");
+        for subcategory in &filter.subcategories {
+            list.push_bind(subcategory.clone());
+        }
+        list.push_unseparated(")");
+    }
+
+    if !filter.pages.is_empty() {
+        query.push(format!(" AND {alias}page IN ("));
+        let mut list = query.separated(", ");
+        for page in &filter.pages {
+            list.push_bind(*page);
+        }
+        list.push_unseparated(")");
+    }
+
+    if !filter.excluded_terms.is_empty() {
+        query
+            .push(format!(
+                " AND {alias}rowid NOT IN (SELECT rowid FROM rules_fts WHERE rules_fts MATCH "
+            ))
+            .push_bind(filter.excluded_terms.join(" OR "))
+            .push(")");
+    }
+
+    for tag in &filter.tags {
+        query
+            .push(format!(" AND {alias}id IN (SELECT rule_id FROM rule_tags WHERE tag_slug = "))
+            .push_bind(tag.clone())
+            .push(")");
+    }
+
+    if filter.exclude_homebrew {
+        query.push(format!(" AND {alias}collection_id IS NULL"));
+    } else if !filter.include_private_homebrew {
+        query.push(format!(
+            " AND ({alias}collection_id IS NULL OR {alias}collection_id NOT IN \
+             (SELECT id FROM homebrew_collections WHERE visibility = 'private'))"
+        ));
+    }
+}
+
+/// All known sources in display order
+pub async fn get_sources(pool: &SqlitePool) -> Result<Vec<Source>, sqlx::Error> {
+    sqlx::query_as::<_, Source>("SELECT * FROM sources ORDER BY display_order, name")
+        .fetch_all(pool)
+        .await
+}
+
+/// Find the source whose name or abbreviation matches `text` (case-insensitive)
+pub async fn resolve_source(pool: &SqlitePool, text: &str) -> Result<Option<Source>, sqlx::Error> {
+    sqlx::query_as::<_, Source>(
+        "SELECT * FROM sources WHERE LOWER(name) = LOWER(?1) OR LOWER(abbreviation) = LOWER(?1)",
+    )
+    .bind(text.trim())
+    .fetch_optional(pool)
+    .await
+}
+
+/// Link rules whose free-text source matches a known source
+async fn link_rule_sources(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
         r#"
-        SELECT * FROM rules
-        WHERE
-            LOWER(title) LIKE ?1
-            OR LOWER(content) LIKE ?1
-            OR LOWER(category) LIKE ?1
-        ORDER BY
-            CASE
-                WHEN LOWER(title) LIKE ?2 THEN 1
-                WHEN LOWER(title) LIKE ?1 THEN 2
-                WHEN LOWER(category) LIKE ?1 THEN 3
-                ELSE 4
-            END,
-            title
-        LIMIT ?3
+        UPDATE rules
+        SET source_id = (
+            SELECT abbreviation FROM sources
+            WHERE LOWER(sources.name) = LOWER(rules.source)
+               OR LOWER(sources.abbreviation) = LOWER(rules.source)
+        )
+        WHERE source_id IS NULL
         "#,
     )
-    .bind(&like_pattern)
-    .bind(&prefix_pattern)
-    .bind(limit)
-    .fetch_all(pool)
-    .await
+    .execute(pool)
+    .await?;
+
+    Ok(())
 }
 
 /// Upsert a rule - insert if new, update if exists
-/// Returns true if a new rule was inserted, false if an existing rule was updated
-pub async fn upsert_rule(pool: &SqlitePool, rule: &Rule) -> Result<bool, sqlx::Error> {
+/// Returns true if a new rule was inserted, false if an existing rule was updated.
+/// When an update changes the rule, its previous version is saved to
+/// `rule_revisions` attributed to `changed_by`.
+pub async fn upsert_rule(pool: &SqlitePool, rule: &Rule, changed_by: &str) -> Result<bool, sqlx::Error> {
+    let mut rule = rule.clone();
+    if rule.source_id.is_none() {
+        rule.source_id = resolve_source(pool, &rule.source).await?.map(|s| s.abbreviation);
+    }
+    let rule = &rule;
+    let now = now_utc();
+
+    let mut tx = pool.begin().await?;
+
     // Check if rule exists
-    let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM rules WHERE id = ?")
+    let existing = sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE id = ?")
         .bind(&rule.id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    let is_new = if let Some(existing) = existing {
+        if same_rule_fields(&existing, rule) {
+            // Nothing changed - avoid empty revisions on repeated imports
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO rule_revisions (rule_id, title, category, subcategory, content, source, page, changed_by, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&existing.id)
+        .bind(&existing.title)
+        .bind(&existing.category)
+        .bind(&existing.subcategory)
+        .bind(&existing.content)
+        .bind(&existing.source)
+        .bind(existing.page)
+        .bind(changed_by)
+        .bind(now)
+        .execute(&mut *tx)
         .await?;
 
-    if existing.is_some() {
         // Update existing rule
         sqlx::query(
             r#"
@@ -256,8 +456,10 @@ pub async fn upsert_rule(pool: &SqlitePool, rule: &Rule) -> Result<bool, sqlx::E
                 subcategory = ?,
                 content = ?,
                 source = ?,
+                source_id = ?,
                 page = ?,
-                updated_at = datetime('now')
+                collection_id = ?,
+                updated_at = ?
             WHERE id = ?
             "#,
         )
@@ -266,17 +468,20 @@ pub async fn upsert_rule(pool: &SqlitePool, rule: &Rule) -> Result<bool, sqlx::E
         .bind(&rule.subcategory)
         .bind(&rule.content)
         .bind(&rule.source)
-        .bind(&rule.page)
+        .bind(&rule.source_id)
+        .bind(rule.page)
+        .bind(&rule.collection_id)
+        .bind(now)
         .bind(&rule.id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
-        Ok(false)
+        false
     } else {
         // Insert new rule
         sqlx::query(
             r#"
-            INSERT INTO rules (id, title, category, subcategory, content, source, page, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, datetime('now'), datetime('now'))
+            INSERT INTO rules (id, title, category, subcategory, content, source, source_id, page, created_at, updated_at, collection_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&rule.id)
@@ -285,18 +490,1691 @@ pub async fn upsert_rule(pool: &SqlitePool, rule: &Rule) -> Result<bool, sqlx::E
         .bind(&rule.subcategory)
         .bind(&rule.content)
         .bind(&rule.source)
-        .bind(&rule.page)
+        .bind(&rule.source_id)
+        .bind(rule.page)
+        .bind(now)
+        .bind(now)
+        .bind(&rule.collection_id)
+        .execute(&mut *tx)
+        .await?;
+        true
+    };
+
+    tx.commit().await?;
+    Ok(is_new)
+}
+
+pub(super) fn same_rule_fields(a: &Rule, b: &Rule) -> bool {
+    a.title == b.title
+        && a.category == b.category
+        && a.subcategory == b.subcategory
+        && a.content == b.content
+        && a.source == b.source
+        && a.source_id == b.source_id
+        && a.page == b.page
+        && a.collection_id == b.collection_id
+}
+
+/// The most recently added or updated rules, newest first. Rules from
+/// private homebrew collections are left out, as the list is public.
+pub async fn get_recent_changes(pool: &SqlitePool, limit: i64) -> Result<Vec<Rule>, sqlx::Error> {
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM rules WHERE 1 = 1");
+    push_rule_filter(&mut query, &RuleFilter::default(), "");
+    query
+        .push(" ORDER BY updated_at DESC, created_at DESC, title LIMIT ")
+        .push_bind(limit);
+
+    query.build_query_as::<Rule>().fetch_all(pool).await
+}
+
+/// Revisions of a rule, newest first
+pub async fn get_rule_revisions(pool: &SqlitePool, rule_id: &str) -> Result<Vec<RuleRevision>, sqlx::Error> {
+    sqlx::query_as::<_, RuleRevision>(
+        "SELECT * FROM rule_revisions WHERE rule_id = ? ORDER BY id DESC",
+    )
+    .bind(rule_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_rule_revision(
+    pool: &SqlitePool,
+    rule_id: &str,
+    revision_id: i64,
+) -> Result<Option<RuleRevision>, sqlx::Error> {
+    sqlx::query_as::<_, RuleRevision>(
+        "SELECT * FROM rule_revisions WHERE rule_id = ? AND id = ?",
+    )
+    .bind(rule_id)
+    .bind(revision_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Move a rule to the trash. Returns false if there is no such rule or it
+/// is already trashed.
+pub async fn trash_rule(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE rules SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+        .bind(now_utc())
+        .bind(id)
         .execute(pool)
         .await?;
-        Ok(true)
-    }
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Take a rule back out of the trash. Returns false if it was not trashed.
+pub async fn restore_rule(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE rules SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Rules in the trash, most recently deleted first
+pub async fn get_trashed_rules(pool: &SqlitePool) -> Result<Vec<TrashedRule>, sqlx::Error> {
+    sqlx::query_as::<_, TrashedRule>(
+        "SELECT id, title, category, source, deleted_at FROM rules WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, title",
+    )
+    .fetch_all(pool)
+    .await
 }
 
-/// Delete a rule by its ID
-pub async fn delete_rule(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+/// Permanently delete a rule with its tags, links, spell, equipment, errata and
+/// revision rows, and unlink glossary terms that pointed at it, so a new rule
+/// reusing the ID starts clean
+pub async fn purge_rule(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
     sqlx::query("DELETE FROM rules WHERE id = ?")
         .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM rule_tags WHERE rule_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    delete_unused_tags(&mut tx).await?;
+    sqlx::query("DELETE FROM rule_links WHERE source_rule_id = ?1 OR target_rule_id = ?1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM spells WHERE rule_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM spell_classes WHERE rule_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    delete_equipment_rows(&mut tx, id).await?;
+    sqlx::query("DELETE FROM rule_aliases WHERE rule_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM rule_errata WHERE rule_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM rule_revisions WHERE rule_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE glossary_terms SET rule_id = NULL WHERE rule_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Tables whose `rule_id` column follows a rule when it is renamed
+const RULE_ID_TABLES: [&str; 9] = [
+    "rule_revisions",
+    "rule_errata",
+    "rule_tags",
+    "spells",
+    "spell_classes",
+    "weapons",
+    "armor",
+    "gear",
+    "glossary_terms",
+];
+
+/// Give a rule a new ID. Everything keyed by the rule follows it and the old
+/// ID (and any earlier ones) become aliases of the new one. The caller checks
+/// that `new_id` is free and retargets `[[rule:old]]` links in other rules
+/// (see [`get_rules_linking_to`]), saving them as ordinary edits.
+pub async fn rename_rule(pool: &SqlitePool, old_id: &str, new_id: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE rules SET id = ? WHERE id = ?")
+        .bind(new_id)
+        .bind(old_id)
+        .execute(&mut *tx)
+        .await?;
+    for table in RULE_ID_TABLES {
+        sqlx::query(&format!("UPDATE {table} SET rule_id = ? WHERE rule_id = ?"))
+            .bind(new_id)
+            .bind(old_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("UPDATE rule_links SET source_rule_id = ? WHERE source_rule_id = ?")
+        .bind(new_id)
+        .bind(old_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE rule_links SET target_rule_id = ? WHERE target_rule_id = ?")
+        .bind(new_id)
+        .bind(old_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM rule_aliases WHERE alias = ?")
+        .bind(new_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE rule_aliases SET rule_id = ? WHERE rule_id = ?")
+        .bind(new_id)
+        .bind(old_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO rule_aliases (alias, rule_id, created_at) VALUES (?, ?, ?)")
+        .bind(old_id)
+        .bind(new_id)
+        .bind(now_utc())
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Rules, trashed ones included, whose content may hold a `[[rule:id]]` link
+/// to `rule_id`
+pub async fn get_rules_linking_to(pool: &SqlitePool, rule_id: &str) -> Result<Vec<Rule>, sqlx::Error> {
+    sqlx::query_as::<_, Rule>("SELECT * FROM rules WHERE INSTR(content, ?) > 0 ORDER BY id")
+        .bind(format!("[[rule:{}", rule_id))
+        .fetch_all(pool)
+        .await
+}
+
+/// The rule an old ID now points at, if it is an alias
+pub async fn resolve_rule_alias(pool: &SqlitePool, alias: &str) -> Result<Option<String>, sqlx::Error> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT rule_id FROM rule_aliases WHERE alias = ?")
+        .bind(alias)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.map(|(rule_id,)| rule_id))
+}
+
+/// Every alias mapped to the rule it now points at
+pub async fn get_rule_alias_map(pool: &SqlitePool) -> Result<HashMap<String, String>, sqlx::Error> {
+    let rows: Vec<(String, String)> = sqlx::query_as("SELECT alias, rule_id FROM rule_aliases")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.into_iter().collect())
+}
+
+/// Former IDs of a rule, oldest first
+pub async fn get_rule_aliases(pool: &SqlitePool, rule_id: &str) -> Result<Vec<String>, sqlx::Error> {
+    let rows: Vec<(String,)> =
+        sqlx::query_as("SELECT alias FROM rule_aliases WHERE rule_id = ? ORDER BY created_at, alias")
+            .bind(rule_id)
+            .fetch_all(pool)
+            .await?;
+
+    Ok(rows.into_iter().map(|(alias,)| alias).collect())
+}
+
+/// Whether `id` belongs to a rule (trashed ones included) or is an alias
+pub async fn rule_id_in_use(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let (in_use,): (bool,) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM rules WHERE id = ?1) OR EXISTS (SELECT 1 FROM rule_aliases WHERE alias = ?1)",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(in_use)
+}
+
+/// A free rule ID derived from `title`: its slug, or the slug with `-2`,
+/// `-3`, ... appended when that is taken
+pub async fn available_rule_id(pool: &SqlitePool, title: &str) -> Result<String, sqlx::Error> {
+    let base = match slugify(title) {
+        slug if slug.is_empty() => "rule".to_string(),
+        slug => slug,
+    };
+
+    let mut candidate = base.clone();
+    let mut suffix = 2;
+    while rule_id_in_use(pool, &candidate).await? {
+        candidate = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+
+    Ok(candidate)
+}
+
+/// Errata for a rule, oldest first
+pub async fn get_rule_errata(pool: &SqlitePool, rule_id: &str) -> Result<Vec<Erratum>, sqlx::Error> {
+    sqlx::query_as::<_, Erratum>(
+        "SELECT * FROM rule_errata WHERE rule_id = ? ORDER BY published_on, id",
+    )
+    .bind(rule_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Errata for any of the given rules, oldest first
+pub async fn get_errata_for_rules(pool: &SqlitePool, rule_ids: &[String]) -> Result<Vec<Erratum>, sqlx::Error> {
+    let mut errata = Vec::new();
+
+    for rule_id in rule_ids {
+        errata.extend(get_rule_errata(pool, rule_id).await?);
+    }
+
+    Ok(errata)
+}
+
+/// Insert an erratum, or update the correction and note of an existing
+/// entry with the same rule, date and original text
+pub async fn upsert_erratum(pool: &SqlitePool, erratum: &Erratum) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO rule_errata (rule_id, published_on, original_text, corrected_text, source_note, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT (rule_id, published_on, original_text) DO UPDATE SET
+            corrected_text = excluded.corrected_text,
+            source_note = excluded.source_note
+        "#,
+    )
+    .bind(&erratum.rule_id)
+    .bind(&erratum.published_on)
+    .bind(&erratum.original_text)
+    .bind(&erratum.corrected_text)
+    .bind(&erratum.source_note)
+    .bind(now_utc())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_erratum(pool: &SqlitePool, rule_id: &str, erratum_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM rule_errata WHERE rule_id = ? AND id = ?")
+        .bind(rule_id)
+        .bind(erratum_id)
         .execute(pool)
         .await?;
     Ok(())
 }
+
+/// Tags of a rule, by name
+pub async fn get_rule_tags(pool: &SqlitePool, rule_id: &str) -> Result<Vec<Tag>, sqlx::Error> {
+    sqlx::query_as::<_, Tag>(
+        r#"
+        SELECT t.slug, t.name FROM tags t
+        JOIN rule_tags rt ON rt.tag_slug = t.slug
+        WHERE rt.rule_id = ?
+        ORDER BY t.name
+        "#,
+    )
+    .bind(rule_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Fill in `tags` on each of `rules` with a single query
+pub async fn attach_tags(pool: &SqlitePool, rules: &mut [Rule]) -> Result<(), sqlx::Error> {
+    if rules.is_empty() {
+        return Ok(());
+    }
+
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT rt.rule_id, t.slug, t.name FROM tags t JOIN rule_tags rt ON rt.tag_slug = t.slug WHERE rt.rule_id IN (",
+    );
+    let mut ids = query.separated(", ");
+    for rule in rules.iter() {
+        ids.push_bind(rule.id.clone());
+    }
+    ids.push_unseparated(") ORDER BY t.name");
+
+    let rows: Vec<(String, String, String)> = query.build_query_as().fetch_all(pool).await?;
+
+    for rule in rules.iter_mut() {
+        rule.tags = rows
+            .iter()
+            .filter(|(rule_id, _, _)| *rule_id == rule.id)
+            .map(|(_, slug, name)| Tag {
+                slug: slug.clone(),
+                name: name.clone(),
+            })
+            .collect();
+    }
+
+    Ok(())
+}
+
+/// Replace the tags of a rule. Tags are created on first use (keeping the
+/// first spelling of their name) and removed once no rule carries them.
+pub async fn set_rule_tags(pool: &SqlitePool, rule_id: &str, tags: &[Tag]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    write_rule_tags(&mut tx, rule_id, tags).await?;
+    tx.commit().await
+}
+
+async fn write_rule_tags(tx: &mut sqlx::Transaction<'_, Sqlite>, rule_id: &str, tags: &[Tag]) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM rule_tags WHERE rule_id = ?")
+        .bind(rule_id)
+        .execute(&mut **tx)
+        .await?;
+
+    for tag in tags {
+        sqlx::query("INSERT OR IGNORE INTO tags (slug, name) VALUES (?, ?)")
+            .bind(&tag.slug)
+            .bind(&tag.name)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query("INSERT OR IGNORE INTO rule_tags (rule_id, tag_slug) VALUES (?, ?)")
+            .bind(rule_id)
+            .bind(&tag.slug)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    delete_unused_tags(tx).await?;
+
+    Ok(())
+}
+
+async fn delete_unused_tags(tx: &mut sqlx::Transaction<'_, Sqlite>) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM tags WHERE slug NOT IN (SELECT tag_slug FROM rule_tags)")
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_tag(pool: &SqlitePool, slug: &str) -> Result<Option<Tag>, sqlx::Error> {
+    sqlx::query_as::<_, Tag>("SELECT slug, name FROM tags WHERE slug = ?")
+        .bind(slug)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Every tag in use with its rule count, by name. Rules in private homebrew
+/// collections only count when `include_private_homebrew` is set.
+pub async fn get_tags(pool: &SqlitePool, include_private_homebrew: bool) -> Result<Vec<TagSummary>, sqlx::Error> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        r#"
+        SELECT t.slug, t.name, COUNT(rt.rule_id) AS rule_count
+        FROM tags t
+        JOIN rule_tags rt ON rt.tag_slug = t.slug
+        JOIN rules r ON r.id = rt.rule_id
+        WHERE 1 = 1"#,
+    );
+    let visibility = RuleFilter::default().with_homebrew(None, include_private_homebrew);
+    push_rule_filter(&mut query, &visibility, "r.");
+    query.push(" GROUP BY t.slug, t.name ORDER BY t.name");
+
+    query.build_query_as::<TagSummary>().fetch_all(pool).await
+}
+
+/// Every homebrew collection, by name
+pub async fn get_homebrew_collections(pool: &SqlitePool) -> Result<Vec<HomebrewCollection>, sqlx::Error> {
+    sqlx::query_as::<_, HomebrewCollection>("SELECT * FROM homebrew_collections ORDER BY name")
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn get_homebrew_collection(pool: &SqlitePool, id: &str) -> Result<Option<HomebrewCollection>, sqlx::Error> {
+    sqlx::query_as::<_, HomebrewCollection>("SELECT * FROM homebrew_collections WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Insert a homebrew collection, or update the name, owner, visibility and
+/// description of an existing one (its `created_at` is kept)
+pub async fn upsert_homebrew_collection(pool: &SqlitePool, collection: &HomebrewCollection) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO homebrew_collections (id, name, owner, visibility, description, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            owner = excluded.owner,
+            visibility = excluded.visibility,
+            description = excluded.description
+        "#,
+    )
+    .bind(&collection.id)
+    .bind(&collection.name)
+    .bind(&collection.owner)
+    .bind(&collection.visibility)
+    .bind(&collection.description)
+    .bind(collection.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Fill in `collection` on each homebrew rule in `rules`
+pub async fn attach_collections(pool: &SqlitePool, rules: &mut [Rule]) -> Result<(), sqlx::Error> {
+    if !rules.iter().any(Rule::is_homebrew) {
+        return Ok(());
+    }
+
+    let collections = get_homebrew_collections(pool).await?;
+    for rule in rules.iter_mut() {
+        rule.collection = rule
+            .collection_id
+            .as_deref()
+            .and_then(|id| collections.iter().find(|collection| collection.id == id))
+            .cloned();
+    }
+
+    Ok(())
+}
+
+/// Every glossary term with its aliases, alphabetically. `rule_id` is
+/// cleared when the linked rule is missing or in the trash.
+pub async fn get_glossary_terms(pool: &SqlitePool) -> Result<Vec<GlossaryTerm>, sqlx::Error> {
+    let mut terms = sqlx::query_as::<_, GlossaryTerm>(
+        r#"
+        SELECT g.id, g.term, g.definition, r.id AS rule_id
+        FROM glossary_terms g
+        LEFT JOIN rules r ON r.id = g.rule_id AND r.deleted_at IS NULL
+        ORDER BY g.term COLLATE NOCASE
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let aliases: Vec<(String, String)> =
+        sqlx::query_as("SELECT term_id, alias FROM glossary_aliases ORDER BY alias")
+            .fetch_all(pool)
+            .await?;
+    for term in terms.iter_mut() {
+        term.aliases = aliases
+            .iter()
+            .filter(|(term_id, _)| *term_id == term.id)
+            .map(|(_, alias)| alias.clone())
+            .collect();
+    }
+
+    Ok(terms)
+}
+
+/// Insert or update a glossary term, replacing its aliases
+pub async fn upsert_glossary_term(pool: &SqlitePool, term: &GlossaryTerm) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO glossary_terms (id, term, definition, rule_id)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            term = excluded.term,
+            definition = excluded.definition,
+            rule_id = excluded.rule_id
+        "#,
+    )
+    .bind(&term.id)
+    .bind(&term.term)
+    .bind(&term.definition)
+    .bind(&term.rule_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM glossary_aliases WHERE term_id = ?")
+        .bind(&term.id)
+        .execute(&mut *tx)
+        .await?;
+    for alias in &term.aliases {
+        sqlx::query("INSERT OR IGNORE INTO glossary_aliases (term_id, alias) VALUES (?, ?)")
+            .bind(&term.id)
+            .bind(alias)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Every search synonym, by term then expansion
+pub async fn get_synonyms(pool: &SqlitePool) -> Result<Vec<Synonym>, sqlx::Error> {
+    sqlx::query_as::<_, Synonym>("SELECT term, expansion FROM search_synonyms ORDER BY term, expansion")
+        .fetch_all(pool)
+        .await
+}
+
+/// Add a search synonym; returns false if it already existed
+pub async fn add_synonym(pool: &SqlitePool, synonym: &Synonym) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("INSERT OR IGNORE INTO search_synonyms (term, expansion) VALUES (?, ?)")
+        .bind(&synonym.term)
+        .bind(&synonym.expansion)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_synonym(pool: &SqlitePool, synonym: &Synonym) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM search_synonyms WHERE term = ? AND expansion = ?")
+        .bind(&synonym.term)
+        .bind(&synonym.expansion)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Store the embedding of a rule for the SQLite vector index, replacing any
+/// earlier one
+pub async fn upsert_rule_vector(pool: &SqlitePool, rule_id: &str, vector: &[f32]) -> Result<(), sqlx::Error> {
+    let bytes: Vec<u8> = vector.iter().flat_map(|value| value.to_le_bytes()).collect();
+    sqlx::query(
+        "INSERT INTO rule_vectors (rule_id, dimension, vector, updated_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(rule_id) DO UPDATE SET dimension = excluded.dimension, vector = excluded.vector, updated_at = excluded.updated_at",
+    )
+    .bind(rule_id)
+    .bind(vector.len() as i64)
+    .bind(bytes)
+    .bind(now_utc())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Every stored rule embedding of `dimension` values
+pub async fn get_rule_vectors(pool: &SqlitePool, dimension: usize) -> Result<Vec<(String, Vec<f32>)>, sqlx::Error> {
+    let rows: Vec<(String, Vec<u8>)> = sqlx::query_as("SELECT rule_id, vector FROM rule_vectors WHERE dimension = ?")
+        .bind(dimension as i64)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(rule_id, bytes)| {
+            let vector = bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect();
+            (rule_id, vector)
+        })
+        .collect())
+}
+
+pub async fn delete_rule_vector(pool: &SqlitePool, rule_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM rule_vectors WHERE rule_id = ?")
+        .bind(rule_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Drop every stored rule embedding
+pub async fn clear_rule_vectors(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM rule_vectors").execute(pool).await?;
+
+    Ok(())
+}
+
+pub async fn get_vector_records(pool: &SqlitePool) -> Result<Vec<VectorRecord>, sqlx::Error> {
+    sqlx::query_as::<_, VectorRecord>(
+        "SELECT rule_id, content_hash, embedding_model, vector_index FROM vector_records ORDER BY rule_id",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Record that the vector index now holds `record`'s embedding of its rule
+pub async fn upsert_vector_record(pool: &SqlitePool, record: &VectorRecord) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO vector_records (rule_id, content_hash, embedding_model, vector_index, indexed_at) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(rule_id) DO UPDATE SET content_hash = excluded.content_hash, embedding_model = excluded.embedding_model,
+             vector_index = excluded.vector_index, indexed_at = excluded.indexed_at",
+    )
+    .bind(&record.rule_id)
+    .bind(&record.content_hash)
+    .bind(&record.embedding_model)
+    .bind(&record.vector_index)
+    .bind(now_utc())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_vector_record(pool: &SqlitePool, rule_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM vector_records WHERE rule_id = ?")
+        .bind(rule_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn clear_vector_records(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM vector_records").execute(pool).await?;
+
+    Ok(())
+}
+
+/// Queue a rule whose vector failed to sync, or count another failed
+/// attempt of one already queued
+pub async fn queue_vector_sync(pool: &SqlitePool, rule_id: &str, error: &str) -> Result<(), sqlx::Error> {
+    let now = now_utc();
+    sqlx::query(
+        "INSERT INTO vector_sync_queue (rule_id, attempts, last_error, queued_at, last_attempt_at) VALUES (?, 1, ?, ?, ?)
+         ON CONFLICT(rule_id) DO UPDATE SET attempts = attempts + 1, last_error = excluded.last_error,
+             last_attempt_at = excluded.last_attempt_at",
+    )
+    .bind(rule_id)
+    .bind(error)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Rules awaiting a vector sync retry, longest waiting first
+pub async fn get_vector_sync_queue(pool: &SqlitePool) -> Result<Vec<QueuedVectorSync>, sqlx::Error> {
+    sqlx::query_as::<_, QueuedVectorSync>(
+        "SELECT rule_id, attempts, last_error, queued_at FROM vector_sync_queue ORDER BY queued_at, rule_id",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn dequeue_vector_sync(pool: &SqlitePool, rule_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM vector_sync_queue WHERE rule_id = ?")
+        .bind(rule_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Replace the whole cross-reference graph with `links`
+pub async fn replace_rule_links(pool: &SqlitePool, links: &[RuleLink]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM rule_links").execute(&mut *tx).await?;
+
+    for link in links {
+        sqlx::query(
+            "INSERT OR IGNORE INTO rule_links (source_rule_id, target_rule_id, kind) VALUES (?, ?, ?)",
+        )
+        .bind(&link.source_id)
+        .bind(&link.target_id)
+        .bind(link.kind.as_str())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replace the links from and to `rule_id` with `links`
+pub async fn replace_links_of_rule(pool: &SqlitePool, rule_id: &str, links: &[RuleLink]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM rule_links WHERE source_rule_id = ?1 OR target_rule_id = ?1")
+        .bind(rule_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for link in links {
+        sqlx::query(
+            "INSERT OR IGNORE INTO rule_links (source_rule_id, target_rule_id, kind) VALUES (?, ?, ?)",
+        )
+        .bind(&link.source_id)
+        .bind(&link.target_id)
+        .bind(link.kind.as_str())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Rules linked to or from a rule, explicit links first, then by title
+pub async fn get_related_rules(pool: &SqlitePool, rule_id: &str) -> Result<Vec<Rule>, sqlx::Error> {
+    sqlx::query_as::<_, Rule>(
+        r#"
+        SELECT r.* FROM rules r
+        JOIN (
+            SELECT target_rule_id AS id, kind FROM rule_links WHERE source_rule_id = ?1
+            UNION
+            SELECT source_rule_id AS id, kind FROM rule_links WHERE target_rule_id = ?1
+        ) l ON l.id = r.id
+        WHERE r.deleted_at IS NULL
+        GROUP BY r.id
+        ORDER BY MIN(CASE l.kind WHEN 'explicit' THEN 0 ELSE 1 END), r.title
+        "#,
+    )
+    .bind(rule_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Insert or replace the structured record of a spell rule
+pub async fn upsert_spell(pool: &SqlitePool, spell: &Spell) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    write_spell(&mut tx, spell).await?;
+    tx.commit().await
+}
+
+async fn write_spell(tx: &mut sqlx::Transaction<'_, Sqlite>, spell: &Spell) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO spells (rule_id, name, level, school, casting_time, casting_time_kind, range, components, duration, concentration, ritual)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (rule_id) DO UPDATE SET
+            name = excluded.name,
+            level = excluded.level,
+            school = excluded.school,
+            casting_time = excluded.casting_time,
+            casting_time_kind = excluded.casting_time_kind,
+            range = excluded.range,
+            components = excluded.components,
+            duration = excluded.duration,
+            concentration = excluded.concentration,
+            ritual = excluded.ritual
+        "#,
+    )
+    .bind(&spell.rule_id)
+    .bind(&spell.name)
+    .bind(spell.level)
+    .bind(&spell.school)
+    .bind(&spell.casting_time)
+    .bind(&spell.casting_time_kind)
+    .bind(&spell.range)
+    .bind(&spell.components)
+    .bind(&spell.duration)
+    .bind(spell.concentration)
+    .bind(spell.ritual)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query("DELETE FROM spell_classes WHERE rule_id = ?")
+        .bind(&spell.rule_id)
+        .execute(&mut **tx)
+        .await?;
+
+    for class in &spell.classes {
+        sqlx::query("INSERT OR IGNORE INTO spell_classes (rule_id, class) VALUES (?, ?)")
+            .bind(&spell.rule_id)
+            .bind(class)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Drop the spell record of a rule that is no longer a spell
+async fn delete_spell_rows(tx: &mut sqlx::Transaction<'_, Sqlite>, rule_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM spell_classes WHERE rule_id = ?")
+        .bind(rule_id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("DELETE FROM spells WHERE rule_id = ?")
+        .bind(rule_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Replace a rule's tags, spell record and equipment rows in one
+/// transaction, so a failure leaves all of them as they were. A `spell` of
+/// None drops any spell record.
+pub async fn set_rule_details(
+    pool: &SqlitePool,
+    rule_id: &str,
+    tags: &[Tag],
+    spell: Option<&Spell>,
+    equipment: &EquipmentSet,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    write_rule_tags(&mut tx, rule_id, tags).await?;
+    match spell {
+        Some(spell) => write_spell(&mut tx, spell).await?,
+        None => delete_spell_rows(&mut tx, rule_id).await?,
+    }
+    write_rule_equipment(&mut tx, rule_id, equipment).await?;
+
+    tx.commit().await
+}
+
+pub async fn get_spell(pool: &SqlitePool, rule_id: &str) -> Result<Option<Spell>, sqlx::Error> {
+    let spell = sqlx::query_as::<_, Spell>("SELECT * FROM spells WHERE rule_id = ?")
+        .bind(rule_id)
+        .fetch_optional(pool)
+        .await?;
+
+    match spell {
+        Some(spell) => {
+            let mut spells = vec![spell];
+            attach_spell_classes(pool, &mut spells).await?;
+            Ok(spells.pop())
+        }
+        None => Ok(None),
+    }
+}
+
+/// Restricts spell and equipment rows to rules that are not in the trash and,
+/// unless `include_private_homebrew`, not in a private homebrew collection
+fn push_visible_rule_ids(query: &mut QueryBuilder<'_, Sqlite>, include_private_homebrew: bool) {
+    let visibility = RuleFilter::default().with_homebrew(None, include_private_homebrew);
+    query.push(" AND rule_id IN (SELECT id FROM rules WHERE 1 = 1");
+    push_rule_filter(query, &visibility, "");
+    query.push(")");
+}
+
+/// Spells matching `filter`, by level then name
+pub async fn search_spells(pool: &SqlitePool, filter: &SpellFilter) -> Result<Vec<Spell>, sqlx::Error> {
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM spells WHERE 1 = 1");
+    push_visible_rule_ids(&mut query, filter.include_private_homebrew);
+
+    if let Some(class) = &filter.class {
+        query
+            .push(" AND rule_id IN (SELECT rule_id FROM spell_classes WHERE LOWER(class) = LOWER(")
+            .push_bind(class.clone())
+            .push("))");
+    }
+    if let Some(level) = filter.level {
+        query.push(" AND level = ").push_bind(level);
+    }
+    if let Some(school) = &filter.school {
+        query.push(" AND LOWER(school) = LOWER(").push_bind(school.clone()).push(")");
+    }
+    if let Some(ritual) = filter.ritual {
+        query.push(" AND ritual = ").push_bind(ritual);
+    }
+    if let Some(concentration) = filter.concentration {
+        query.push(" AND concentration = ").push_bind(concentration);
+    }
+    if let Some(casting_time) = &filter.casting_time {
+        query.push(" AND casting_time_kind = ").push_bind(casting_time.clone());
+    }
+    query.push(" ORDER BY level, name");
+
+    let mut spells = query.build_query_as::<Spell>().fetch_all(pool).await?;
+    attach_spell_classes(pool, &mut spells).await?;
+    Ok(spells)
+}
+
+async fn attach_spell_classes(pool: &SqlitePool, spells: &mut [Spell]) -> Result<(), sqlx::Error> {
+    if spells.is_empty() {
+        return Ok(());
+    }
+
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT rule_id, class FROM spell_classes WHERE rule_id IN (");
+    let mut ids = query.separated(", ");
+    for spell in spells.iter() {
+        ids.push_bind(spell.rule_id.clone());
+    }
+    ids.push_unseparated(") ORDER BY class");
+
+    let rows: Vec<(String, String)> = query.build_query_as().fetch_all(pool).await?;
+
+    for spell in spells.iter_mut() {
+        spell.classes = rows
+            .iter()
+            .filter(|(rule_id, _)| *rule_id == spell.rule_id)
+            .map(|(_, class)| class.clone())
+            .collect();
+    }
+
+    Ok(())
+}
+
+/// Distinct spell classes and schools, for filter dropdowns
+pub async fn get_spell_facets(pool: &SqlitePool, include_private_homebrew: bool) -> Result<(Vec<String>, Vec<String>), sqlx::Error> {
+    Ok((
+        visible_distinct(pool, "SELECT DISTINCT class FROM spell_classes WHERE 1 = 1", " ORDER BY class", include_private_homebrew).await?,
+        visible_distinct(pool, "SELECT DISTINCT school FROM spells WHERE 1 = 1", " ORDER BY school", include_private_homebrew).await?,
+    ))
+}
+
+/// Values of a `SELECT DISTINCT` over rows of visible rules: `select` ends in
+/// a `WHERE` over a table with a `rule_id` column and `rest` closes the query
+async fn visible_distinct(
+    pool: &SqlitePool,
+    select: &str,
+    rest: &str,
+    include_private_homebrew: bool,
+) -> Result<Vec<String>, sqlx::Error> {
+    let mut query = QueryBuilder::<Sqlite>::new(select);
+    push_visible_rule_ids(&mut query, include_private_homebrew);
+    query.push(rest);
+
+    let rows: Vec<(String,)> = query.build_query_as().fetch_all(pool).await?;
+    Ok(rows.into_iter().map(|(value,)| value).collect())
+}
+
+/// Replace the weapons, armor and gear listed by a rule
+pub async fn set_rule_equipment(pool: &SqlitePool, rule_id: &str, equipment: &EquipmentSet) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    write_rule_equipment(&mut tx, rule_id, equipment).await?;
+    tx.commit().await
+}
+
+async fn write_rule_equipment(tx: &mut sqlx::Transaction<'_, Sqlite>, rule_id: &str, equipment: &EquipmentSet) -> Result<(), sqlx::Error> {
+    delete_equipment_rows(tx, rule_id).await?;
+
+    for weapon in &equipment.weapons {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO weapons (id, rule_id, name, category, damage_dice, damage_type, average_damage, properties, mastery, cost_cp, weight_lb)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&weapon.id)
+        .bind(rule_id)
+        .bind(&weapon.name)
+        .bind(&weapon.category)
+        .bind(&weapon.damage_dice)
+        .bind(&weapon.damage_type)
+        .bind(weapon.average_damage)
+        .bind(&weapon.properties)
+        .bind(&weapon.mastery)
+        .bind(weapon.cost_cp)
+        .bind(weapon.weight_lb)
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query("DELETE FROM weapon_properties WHERE weapon_id = ?")
+            .bind(&weapon.id)
+            .execute(&mut **tx)
+            .await?;
+        for property in weapon_property_names(&weapon.properties) {
+            sqlx::query("INSERT OR IGNORE INTO weapon_properties (weapon_id, property) VALUES (?, ?)")
+                .bind(&weapon.id)
+                .bind(property)
+                .execute(&mut **tx)
+                .await?;
+        }
+    }
+
+    for armor in &equipment.armor {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO armor (id, rule_id, name, category, ac_formula, base_ac, adds_dex, dex_cap, strength_requirement, stealth_disadvantage, cost_cp, weight_lb)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&armor.id)
+        .bind(rule_id)
+        .bind(&armor.name)
+        .bind(&armor.category)
+        .bind(&armor.ac_formula)
+        .bind(armor.base_ac)
+        .bind(armor.adds_dex)
+        .bind(armor.dex_cap)
+        .bind(armor.strength_requirement)
+        .bind(armor.stealth_disadvantage)
+        .bind(armor.cost_cp)
+        .bind(armor.weight_lb)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    for gear in &equipment.gear {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO gear (id, rule_id, name, category, cost_cp, weight_lb, description)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&gear.id)
+        .bind(rule_id)
+        .bind(&gear.name)
+        .bind(&gear.category)
+        .bind(gear.cost_cp)
+        .bind(gear.weight_lb)
+        .bind(&gear.description)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn delete_equipment_rows(tx: &mut sqlx::Transaction<'_, Sqlite>, rule_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM weapon_properties WHERE weapon_id IN (SELECT id FROM weapons WHERE rule_id = ?)")
+        .bind(rule_id)
+        .execute(&mut **tx)
+        .await?;
+    for table in ["weapons", "armor", "gear"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE rule_id = ?", table))
+            .bind(rule_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Fill in `equipment` on each Equipment-category rule in `rules`
+pub async fn attach_equipment(pool: &SqlitePool, rules: &mut [Rule]) -> Result<(), sqlx::Error> {
+    let ids: Vec<String> = rules.iter().filter(|r| r.is_equipment()).map(|r| r.id.clone()).collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let select = |table: &str, order: &str| {
+        let mut query = QueryBuilder::<Sqlite>::new(format!("SELECT * FROM {} WHERE rule_id IN (", table));
+        let mut separated = query.separated(", ");
+        for id in &ids {
+            separated.push_bind(id.clone());
+        }
+        separated.push_unseparated(format!(") ORDER BY {}", order));
+        query
+    };
+
+    let weapons: Vec<Weapon> = select("weapons", "rowid").build_query_as().fetch_all(pool).await?;
+    let armor: Vec<Armor> = select("armor", "rowid").build_query_as().fetch_all(pool).await?;
+    let gear: Vec<Gear> = select("gear", "rowid").build_query_as().fetch_all(pool).await?;
+
+    for rule in rules.iter_mut().filter(|r| r.is_equipment()) {
+        rule.equipment = EquipmentSet {
+            weapons: weapons.iter().filter(|w| w.rule_id == rule.id).cloned().collect(),
+            armor: armor.iter().filter(|a| a.rule_id == rule.id).cloned().collect(),
+            gear: gear.iter().filter(|g| g.rule_id == rule.id).cloned().collect(),
+        };
+    }
+
+    Ok(())
+}
+
+/// Weapons matching `filter`
+pub async fn search_weapons(pool: &SqlitePool, filter: &EquipmentFilter) -> Result<Vec<Weapon>, sqlx::Error> {
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM weapons WHERE 1 = 1");
+    push_visible_rule_ids(&mut query, filter.include_private_homebrew);
+
+    if let Some(category) = &filter.category {
+        query.push(" AND LOWER(category) = LOWER(").push_bind(category.clone()).push(")");
+    }
+    if let Some(property) = &filter.property {
+        query
+            .push(" AND id IN (SELECT weapon_id FROM weapon_properties WHERE LOWER(property) = LOWER(")
+            .push_bind(property.clone())
+            .push("))");
+    }
+    if let Some(mastery) = &filter.mastery {
+        query.push(" AND LOWER(mastery) = LOWER(").push_bind(mastery.clone()).push(")");
+    }
+    if let Some(damage_type) = &filter.damage_type {
+        query.push(" AND LOWER(damage_type) = LOWER(").push_bind(damage_type.clone()).push(")");
+    }
+    query.push(match filter.sort {
+        EquipmentSort::Damage => " ORDER BY average_damage DESC, name",
+        EquipmentSort::Cost => " ORDER BY cost_cp IS NULL, cost_cp, name",
+        EquipmentSort::Weight => " ORDER BY weight_lb IS NULL, weight_lb, name",
+        EquipmentSort::Name | EquipmentSort::ArmorClass => " ORDER BY name",
+    });
+
+    query.build_query_as::<Weapon>().fetch_all(pool).await
+}
+
+/// Armor and shields matching `filter`
+pub async fn search_armor(pool: &SqlitePool, filter: &EquipmentFilter) -> Result<Vec<Armor>, sqlx::Error> {
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM armor WHERE 1 = 1");
+    push_visible_rule_ids(&mut query, filter.include_private_homebrew);
+
+    if let Some(category) = &filter.category {
+        query.push(" AND LOWER(category) = LOWER(").push_bind(category.clone()).push(")");
+    }
+    if let Some(stealth_disadvantage) = filter.stealth_disadvantage {
+        query.push(" AND stealth_disadvantage = ").push_bind(stealth_disadvantage);
+    }
+    query.push(match filter.sort {
+        EquipmentSort::ArmorClass => " ORDER BY base_ac DESC, name",
+        EquipmentSort::Cost => " ORDER BY cost_cp IS NULL, cost_cp, name",
+        EquipmentSort::Weight => " ORDER BY weight_lb IS NULL, weight_lb, name",
+        EquipmentSort::Name | EquipmentSort::Damage => " ORDER BY name",
+    });
+
+    query.build_query_as::<Armor>().fetch_all(pool).await
+}
+
+/// Gear matching `filter`
+pub async fn search_gear(pool: &SqlitePool, filter: &EquipmentFilter) -> Result<Vec<Gear>, sqlx::Error> {
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM gear WHERE 1 = 1");
+    push_visible_rule_ids(&mut query, filter.include_private_homebrew);
+
+    if let Some(category) = &filter.category {
+        query.push(" AND LOWER(category) = LOWER(").push_bind(category.clone()).push(")");
+    }
+    query.push(match filter.sort {
+        EquipmentSort::Cost => " ORDER BY cost_cp IS NULL, cost_cp, name",
+        EquipmentSort::Weight => " ORDER BY weight_lb IS NULL, weight_lb, name",
+        _ => " ORDER BY name",
+    });
+
+    query.build_query_as::<Gear>().fetch_all(pool).await
+}
+
+/// Distinct categories of one equipment table plus weapon properties,
+/// masteries and damage types, for filter dropdowns
+pub async fn get_equipment_facets(
+    pool: &SqlitePool,
+    kind: EquipmentKind,
+    include_private_homebrew: bool,
+) -> Result<EquipmentFacets, sqlx::Error> {
+    let distinct = |select: String, rest: &'static str| async move {
+        visible_distinct(pool, &select, rest, include_private_homebrew).await
+    };
+
+    Ok(EquipmentFacets {
+        categories: distinct(format!("SELECT DISTINCT category FROM {} WHERE 1 = 1", kind.slug()), " ORDER BY category").await?,
+        properties: distinct(
+            "SELECT DISTINCT property FROM weapon_properties WHERE weapon_id IN (SELECT id FROM weapons WHERE 1 = 1".to_string(),
+            ") ORDER BY property",
+        )
+        .await?,
+        masteries: distinct("SELECT DISTINCT mastery FROM weapons WHERE mastery != ''".to_string(), " ORDER BY mastery").await?,
+        damage_types: distinct("SELECT DISTINCT damage_type FROM weapons WHERE 1 = 1".to_string(), " ORDER BY damage_type").await?,
+    })
+}
+
+/// Fresh in-memory database with every migration applied
+#[cfg(test)]
+pub(crate) async fn test_pool() -> SqlitePool {
+    use super::migrations::apply_migrations;
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let migrations = load_migrations(Path::new(MIGRATIONS_DIR)).unwrap();
+    apply_migrations(&pool, &migrations).await.unwrap();
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{parse_tag_list, LinkKind};
+
+    fn rule(id: &str, content: &str) -> Rule {
+        Rule {
+            category: "Conditions".to_string(),
+            page: Some(367),
+            ..Rule::test(id, "Grappled", content)
+        }
+    }
+
+    #[tokio::test]
+    async fn upsert_records_previous_version_as_revision() {
+        let pool = test_pool().await;
+
+        assert!(upsert_rule(&pool, &rule("grappled", "Speed 0."), "import_rules").await.unwrap());
+        assert!(!upsert_rule(&pool, &rule("grappled", "Speed becomes 0."), "dm").await.unwrap());
+
+        let revisions = get_rule_revisions(&pool, "grappled").await.unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].content, "Speed 0.");
+        assert_eq!(revisions[0].changed_by, "dm");
+
+        let current = get_rule_by_id(&pool, "grappled").await.unwrap().unwrap();
+        assert_eq!(current.content, "Speed becomes 0.");
+    }
+
+    #[tokio::test]
+    async fn unchanged_upsert_does_not_create_revision() {
+        let pool = test_pool().await;
+
+        upsert_rule(&pool, &rule("grappled", "Speed 0."), "import_rules").await.unwrap();
+        upsert_rule(&pool, &rule("grappled", "Speed 0."), "import_rules").await.unwrap();
+
+        assert!(get_rule_revisions(&pool, "grappled").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reverting_restores_revision_and_keeps_history() {
+        let pool = test_pool().await;
+        upsert_rule(&pool, &rule("grappled", "v1"), "import_rules").await.unwrap();
+        upsert_rule(&pool, &rule("grappled", "v2"), "dm").await.unwrap();
+
+        let revision = get_rule_revisions(&pool, "grappled").await.unwrap().remove(0);
+        let fetched = get_rule_revision(&pool, "grappled", revision.id).await.unwrap().unwrap();
+        upsert_rule(&pool, &fetched.to_rule(), "dm").await.unwrap();
+
+        let current = get_rule_by_id(&pool, "grappled").await.unwrap().unwrap();
+        assert_eq!(current.content, "v1");
+        let history: Vec<_> = get_rule_revisions(&pool, "grappled")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.content)
+            .collect();
+        assert_eq!(history, vec!["v2", "v1"]);
+    }
+
+    #[tokio::test]
+    async fn recent_changes_are_newest_first() {
+        let pool = test_pool().await;
+        let at = |time: &str| time.parse::<chrono::DateTime<chrono::Utc>>().unwrap();
+        let mut added = rule("grappled", "Speed 0.");
+        added.created_at = at("2025-01-10T09:00:00Z");
+        added.updated_at = added.created_at;
+        let mut edited = rule("prone", "Crawl.");
+        edited.created_at = at("2024-06-01T09:00:00Z");
+        edited.updated_at = at("2025-02-01T09:00:00Z");
+        create_rule(&pool, &added).await.unwrap();
+        create_rule(&pool, &edited).await.unwrap();
+
+        let changes = get_recent_changes(&pool, 10).await.unwrap();
+        let ids: Vec<_> = changes.iter().map(|r| (r.id.as_str(), r.was_updated())).collect();
+        assert_eq!(ids, vec![("prone", true), ("grappled", false)]);
+        assert_eq!(changes[0].updated_at, edited.updated_at);
+    }
+
+    #[tokio::test]
+    async fn upsert_links_known_sources() {
+        let pool = test_pool().await;
+        let mut homebrew = rule("house-rule", "Speed 0.");
+        homebrew.source = "Our Table".to_string();
+
+        upsert_rule(&pool, &rule("grappled", "Speed 0."), "import_rules").await.unwrap();
+        upsert_rule(&pool, &homebrew, "import_rules").await.unwrap();
+
+        let grappled = get_rule_by_id(&pool, "grappled").await.unwrap().unwrap();
+        let house_rule = get_rule_by_id(&pool, "house-rule").await.unwrap().unwrap();
+        assert_eq!(grappled.source_id.as_deref(), Some("PHB"));
+        assert_eq!(house_rule.source_id, None);
+    }
+
+    #[tokio::test]
+    async fn rule_filter_applies_to_listing_and_search() {
+        let pool = test_pool().await;
+        let mut dmg_rule = rule("grappled-dmg", "Grappled monsters are slowed.");
+        dmg_rule.source = "Dungeon Master's Guide 2024".to_string();
+        upsert_rule(&pool, &rule("grappled", "Grappled creatures are slowed."), "import_rules").await.unwrap();
+        upsert_rule(&pool, &dmg_rule, "import_rules").await.unwrap();
+
+        let players = RuleFilter::from_params(None, Some("DMG"), None);
+        let ids = |rules: Vec<Rule>| rules.into_iter().map(|r| r.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(get_rules_filtered(&pool, &players).await.unwrap()), vec!["grappled"]);
+        assert_eq!(ids(search_rules_fts(&pool, "slowed", 20, &players).await.unwrap()), vec!["grappled"]);
+        assert_eq!(ids(fuzzy_search(&pool, "slowed", 10, &players).await.unwrap()), vec!["grappled"]);
+
+        let dmg_only = RuleFilter::from_params(Some("dmg"), None, Some("2024"));
+        assert_eq!(ids(search_rules_fts(&pool, "slowed", 20, &dmg_only).await.unwrap()), vec!["grappled-dmg"]);
+        assert_eq!(search_rules_fts(&pool, "slowed", 20, &RuleFilter::default()).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn query_syntax_filters_run_in_sql() {
+        let pool = test_pool().await;
+        let mut grappled = rule("grappled", "Grappled creatures have speed 0 and can escape.");
+        grappled.page = Some(361);
+        let mut spell = rule("web", "A spell that restrains; creatures can escape.");
+        spell.title = "Web".to_string();
+        spell.category = "Spells".to_string();
+        spell.page = Some(338);
+        upsert_rule(&pool, &grappled, "import_rules").await.unwrap();
+        upsert_rule(&pool, &spell, "import_rules").await.unwrap();
+
+        let query = crate::search::query::ParsedQuery::parse(r#"escap* -"restrains" category:conditions"#).unwrap();
+        let mut filter = RuleFilter::default();
+        query.apply_to(&mut filter);
+        let expression = query.fts_expression().unwrap();
+        let ids = |rules: Vec<Rule>| rules.into_iter().map(|r| r.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(search_rules_fts(&pool, &expression, 20, &filter).await.unwrap()), vec!["grappled"]);
+
+        let page = RuleFilter {
+            pages: vec![338],
+            ..RuleFilter::default()
+        };
+        assert_eq!(ids(get_rules_filtered(&pool, &page).await.unwrap()), vec!["web"]);
+        assert_eq!(ids(search_rules_fts(&pool, r#"title:"web""#, 20, &RuleFilter::default()).await.unwrap()), vec!["web"]);
+    }
+
+    #[tokio::test]
+    async fn fts_ranks_title_hits_first_and_highlights_matches() {
+        let pool = test_pool().await;
+        let mut shove = rule("shove", "Push a prone creature, or knock a creature prone. A prone target is easy to hit.");
+        shove.title = "Shove".to_string();
+        let mut prone = rule("prone", "You can only crawl.");
+        prone.title = "Prone".to_string();
+        let mut cover = rule("cover", "Walls block attacks.");
+        cover.title = "Cover".to_string();
+        cover.subcategory = Some("Obstacles".to_string());
+        for rule in [&shove, &prone, &cover] {
+            upsert_rule(&pool, rule, "import_rules").await.unwrap();
+        }
+
+        let found = search_rules_fts(&pool, "prone", 20, &RuleFilter::default()).await.unwrap();
+        assert_eq!(found.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["prone", "shove"]);
+
+        let highlight = found[1].highlight.as_ref().unwrap();
+        assert_eq!(highlight.title, "Shove");
+        assert!(highlight.snippet.contains("a <mark>prone</mark> creature"));
+
+        let by_subcategory = search_rules_fts(&pool, "obstacles", 20, &RuleFilter::default()).await.unwrap();
+        assert_eq!(by_subcategory[0].id, "cover");
+    }
+
+    #[tokio::test]
+    async fn vocabulary_counts_rules_per_indexed_term() {
+        let pool = test_pool().await;
+        upsert_rule(&pool, &rule("grappled", "Grappled creatures are slowed."), "import_rules").await.unwrap();
+        upsert_rule(&pool, &rule("restrained", "Restrained creatures are slowed too."), "import_rules").await.unwrap();
+
+        let vocabulary = get_search_vocabulary(&pool, &RuleFilter::default()).await.unwrap();
+        assert_eq!(vocabulary.get("slowed"), Some(&2));
+        assert_eq!(vocabulary.get("restrained"), Some(&1));
+
+        trash_rule(&pool, "restrained").await.unwrap();
+        let vocabulary = get_search_vocabulary(&pool, &RuleFilter::default()).await.unwrap();
+        assert_eq!(vocabulary.get("slowed"), Some(&1));
+        assert_eq!(vocabulary.get("restrained"), None);
+    }
+
+    #[tokio::test]
+    async fn homebrew_filter_hides_private_and_excluded_collections() {
+        let pool = test_pool().await;
+        for (id, visibility) in [("table-rules", "public"), ("dm-notes", "private")] {
+            upsert_homebrew_collection(&pool, &HomebrewCollection {
+                id: id.to_string(),
+                name: id.to_string(),
+                owner: "Sam".to_string(),
+                visibility: visibility.to_string(),
+                description: None,
+                created_at: now_utc(),
+            })
+            .await
+            .unwrap();
+        }
+        upsert_rule(&pool, &rule("grappled", "Grappled creatures are slowed."), "import_rules").await.unwrap();
+        for (id, collection) in [("slow-grapple", "table-rules"), ("secret-grapple", "dm-notes")] {
+            let mut house_rule = rule(id, "Grappled creatures are slowed further.");
+            house_rule.collection_id = Some(collection.to_string());
+            upsert_rule(&pool, &house_rule, "dm").await.unwrap();
+        }
+
+        let ids = |rules: Vec<Rule>| rules.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        let public = RuleFilter::default();
+        let admin = RuleFilter::default().with_homebrew(None, true);
+        let official = RuleFilter::default().with_homebrew(Some("exclude"), true);
+
+        assert_eq!(ids(get_rules_filtered(&pool, &public).await.unwrap()), vec!["grappled", "slow-grapple"]);
+        assert_eq!(search_rules_fts(&pool, "slowed", 20, &admin).await.unwrap().len(), 3);
+        assert_eq!(ids(fuzzy_search(&pool, "slowed", 10, &official).await.unwrap()), vec!["grappled"]);
+        assert!(!ids(get_recent_changes(&pool, 10).await.unwrap()).contains(&"secret-grapple".to_string()));
+
+        let mut rules = get_rules_filtered(&pool, &public).await.unwrap();
+        attach_collections(&pool, &mut rules).await.unwrap();
+        assert!(rules[0].collection.is_none());
+        assert_eq!(rules[1].collection.as_ref().unwrap().id, "table-rules");
+    }
+
+    #[tokio::test]
+    async fn renaming_a_rule_moves_its_data_and_keeps_old_ids_as_aliases() {
+        let pool = test_pool().await;
+        upsert_rule(&pool, &rule("grappled", "Speed 0."), "import_rules").await.unwrap();
+        upsert_rule(&pool, &rule("prone", "See [[rule:grappled]] and [[rule:grappled|held]]."), "import_rules")
+            .await
+            .unwrap();
+        set_rule_tags(&pool, "grappled", &parse_tag_list("Movement")).await.unwrap();
+        upsert_rule(&pool, &rule("grappled", "Speed 0, edited."), "admin").await.unwrap();
+
+        rename_rule(&pool, "grappled", "grappled-condition").await.unwrap();
+        rename_rule(&pool, "grappled-condition", "held").await.unwrap();
+
+        assert!(get_rule_by_id(&pool, "grappled").await.unwrap().is_none());
+        assert_eq!(get_rule_tags(&pool, "held").await.unwrap().len(), 1);
+        assert_eq!(get_rule_revisions(&pool, "held").await.unwrap().len(), 1);
+        let linking: Vec<_> = get_rules_linking_to(&pool, "grappled").await.unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(linking, vec!["prone"]);
+        assert_eq!(resolve_rule_alias(&pool, "grappled").await.unwrap().as_deref(), Some("held"));
+        assert_eq!(get_rule_aliases(&pool, "held").await.unwrap(), vec!["grappled", "grappled-condition"]);
+
+        // Taking back a former ID drops it from the aliases
+        rename_rule(&pool, "held", "grappled").await.unwrap();
+        assert_eq!(get_rule_aliases(&pool, "grappled").await.unwrap(), vec!["grappled-condition", "held"]);
+        assert!(rule_id_in_use(&pool, "held").await.unwrap());
+        assert_eq!(available_rule_id(&pool, "Grappled!").await.unwrap(), "grappled-2");
+        assert_eq!(available_rule_id(&pool, "Prone Condition").await.unwrap(), "prone-condition");
+    }
+
+    #[tokio::test]
+    async fn glossary_terms_keep_aliases_and_drop_links_to_trashed_rules() {
+        let pool = test_pool().await;
+        upsert_rule(&pool, &rule("grappled", "Speed 0."), "import_rules").await.unwrap();
+        let mut term = GlossaryTerm {
+            id: "grappled".to_string(),
+            term: "Grappled".to_string(),
+            definition: "Speed 0 while held".to_string(),
+            rule_id: Some("grappled".to_string()),
+            aliases: vec!["Grapple".to_string(), "Held".to_string()],
+        };
+        upsert_glossary_term(&pool, &term).await.unwrap();
+        term.aliases.pop();
+        upsert_glossary_term(&pool, &term).await.unwrap();
+
+        let terms = get_glossary_terms(&pool).await.unwrap();
+        assert_eq!(terms, vec![term.clone()]);
+
+        trash_rule(&pool, "grappled").await.unwrap();
+        assert_eq!(get_glossary_terms(&pool).await.unwrap()[0].rule_id, None);
+    }
+
+    #[tokio::test]
+    async fn tags_are_replaced_and_filter_rules() {
+        let pool = test_pool().await;
+        upsert_rule(&pool, &rule("grappled", "Grappled creatures are slowed."), "import_rules").await.unwrap();
+        upsert_rule(&pool, &rule("prone", "Prone creatures are slowed."), "import_rules").await.unwrap();
+        set_rule_tags(&pool, "grappled", &parse_tag_list("Movement, Mounted Combat")).await.unwrap();
+        set_rule_tags(&pool, "prone", &parse_tag_list("movement")).await.unwrap();
+
+        let filter = RuleFilter {
+            tags: vec!["movement".to_string(), "mounted-combat".to_string()],
+            ..RuleFilter::default()
+        };
+        let ids = |rules: Vec<Rule>| rules.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids(search_rules_fts(&pool, "slowed", 20, &filter).await.unwrap()), vec!["grappled"]);
+
+        set_rule_tags(&pool, "grappled", &parse_tag_list("movement")).await.unwrap();
+        let tags: Vec<_> = get_tags(&pool, false).await.unwrap().into_iter().map(|t| (t.name, t.rule_count)).collect();
+        assert_eq!(tags, vec![("Movement".to_string(), 2)]);
+
+        let mut rules = get_rules_filtered(&pool, &RuleFilter::default()).await.unwrap();
+        attach_tags(&pool, &mut rules).await.unwrap();
+        assert!(rules.iter().all(|r| r.tags.len() == 1 && r.tags[0].slug == "movement"));
+    }
+
+    #[tokio::test]
+    async fn related_rules_include_links_in_both_directions() {
+        let pool = test_pool().await;
+        for id in ["grappled", "prone", "cover"] {
+            upsert_rule(&pool, &rule(id, "Text."), "import_rules").await.unwrap();
+        }
+        let link = |source: &str, target: &str, kind| RuleLink {
+            source_id: source.to_string(),
+            target_id: target.to_string(),
+            kind,
+        };
+        replace_rule_links(&pool, &[
+            link("grappled", "prone", LinkKind::Mention),
+            link("cover", "grappled", LinkKind::Explicit),
+        ])
+        .await
+        .unwrap();
+
+        let related: Vec<_> = get_related_rules(&pool, "grappled").await.unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(related, vec!["cover", "prone"]);
+
+        purge_rule(&pool, "cover").await.unwrap();
+        assert_eq!(get_related_rules(&pool, "grappled").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_purged_id_can_be_reused_without_the_old_history() {
+        let pool = test_pool().await;
+        upsert_rule(&pool, &rule("grappled", "Speed 0."), "import_rules").await.unwrap();
+        upsert_rule(&pool, &rule("grappled", "Speed becomes 0."), "admin").await.unwrap();
+        upsert_erratum(&pool, &Erratum {
+            id: 0,
+            rule_id: "grappled".to_string(),
+            published_on: "2025-01-15".to_string(),
+            original_text: "Speed becomes 0.".to_string(),
+            corrected_text: "Speed is 0.".to_string(),
+            source_note: None,
+            created_at: String::new(),
+        })
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO glossary_terms (id, term, definition, rule_id) VALUES ('grappled', 'Grappled', 'Held.', 'grappled')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(get_rule_revisions(&pool, "grappled").await.unwrap().len(), 1);
+
+        purge_rule(&pool, "grappled").await.unwrap();
+        assert!(!rule_id_in_use(&pool, "grappled").await.unwrap());
+        upsert_rule(&pool, &rule("grappled", "A new rule."), "admin").await.unwrap();
+
+        let ids = vec!["grappled".to_string()];
+        assert!(get_errata_for_rules(&pool, &ids).await.unwrap().is_empty());
+        assert!(get_rule_revisions(&pool, "grappled").await.unwrap().is_empty());
+        let (glossary_rule,): (Option<String>,) = sqlx::query_as("SELECT rule_id FROM glossary_terms WHERE id = 'grappled'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(glossary_rule, None);
+    }
+
+    #[tokio::test]
+    async fn spells_filter_by_class_level_and_ritual() {
+        let pool = test_pool().await;
+        let spell = |id: &str, level, ritual, classes: &[&str]| Spell {
+            rule_id: id.to_string(),
+            name: id.to_string(),
+            level,
+            school: "Divination".to_string(),
+            casting_time: "1 Action".to_string(),
+            casting_time_kind: "action".to_string(),
+            range: "Self".to_string(),
+            components: "V, S".to_string(),
+            duration: "Instantaneous".to_string(),
+            concentration: false,
+            ritual,
+            classes: classes.iter().map(|c| c.to_string()).collect(),
+        };
+        for id in ["detect-magic", "identify", "guidance"] {
+            upsert_rule(&pool, &rule(id, "A spell."), "test").await.unwrap();
+        }
+        upsert_spell(&pool, &spell("detect-magic", 1, true, &["Cleric", "Wizard"])).await.unwrap();
+        upsert_spell(&pool, &spell("identify", 1, true, &["Bard"])).await.unwrap();
+        upsert_spell(&pool, &spell("guidance", 0, false, &["Wizard"])).await.unwrap();
+
+        let wizard_rituals = SpellFilter::from_params(Some("wizard"), Some("1"), None, Some("true"), None, None);
+        let found = search_spells(&pool, &wizard_rituals).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].rule_id, "detect-magic");
+        assert_eq!(found[0].classes, vec!["Cleric", "Wizard"]);
+
+        upsert_spell(&pool, &spell("detect-magic", 1, true, &["Bard"])).await.unwrap();
+        assert!(search_spells(&pool, &wizard_rituals).await.unwrap().is_empty());
+        assert_eq!(get_spell_facets(&pool, false).await.unwrap().0, vec!["Bard", "Wizard"]);
+    }
+
+    #[tokio::test]
+    async fn equipment_is_replaced_filtered_and_attached() {
+        let pool = test_pool().await;
+        let mut weapons = rule("simple-melee-weapons", "- **Dagger:** 1d4 Piercing, Finesse, Light. Mastery: Nick. 1 lb., 2 GP\n- **Club:** 1d4 Bludgeoning, Light. Mastery: Slow. 2 lb., 1 SP\n- **Greatclub:** 1d8 Bludgeoning, Two-Handed. Mastery: Push. 10 lb., 2 SP");
+        weapons.title = "Simple Melee Weapons".to_string();
+        weapons.category = "Equipment".to_string();
+        weapons.subcategory = Some("Weapons".to_string());
+        upsert_rule(&pool, &weapons, "import_rules").await.unwrap();
+        set_rule_equipment(&pool, &weapons.id, &EquipmentSet::from_rule(&weapons).unwrap()).await.unwrap();
+
+        let names = |weapons: Vec<Weapon>| weapons.into_iter().map(|w| w.name).collect::<Vec<_>>();
+        let finesse = EquipmentFilter::from_params(None, Some("finesse"), None, None, None, None);
+        assert_eq!(names(search_weapons(&pool, &finesse).await.unwrap()), vec!["Dagger"]);
+        let by_damage = EquipmentFilter::from_params(None, None, None, Some("bludgeoning"), None, Some("damage"));
+        assert_eq!(names(search_weapons(&pool, &by_damage).await.unwrap()), vec!["Greatclub", "Club"]);
+        let by_cost = EquipmentFilter::from_params(None, None, None, None, None, Some("cost"));
+        assert_eq!(names(search_weapons(&pool, &by_cost).await.unwrap()), vec!["Club", "Greatclub", "Dagger"]);
+
+        let mut rules = vec![get_rule_by_id(&pool, &weapons.id).await.unwrap().unwrap()];
+        attach_equipment(&pool, &mut rules).await.unwrap();
+        assert_eq!(rules[0].equipment.weapons.len(), 3);
+        assert_eq!(get_equipment_facets(&pool, EquipmentKind::Weapons, false).await.unwrap().masteries, vec!["Nick", "Push", "Slow"]);
+
+        trash_rule(&pool, &weapons.id).await.unwrap();
+        let facets = get_equipment_facets(&pool, EquipmentKind::Weapons, true).await.unwrap();
+        assert!(facets.properties.is_empty() && facets.masteries.is_empty() && facets.categories.is_empty());
+
+        purge_rule(&pool, &weapons.id).await.unwrap();
+        assert!(search_weapons(&pool, &EquipmentFilter::default()).await.unwrap().is_empty());
+        assert!(get_equipment_facets(&pool, EquipmentKind::Weapons, false).await.unwrap().properties.is_empty());
+    }
+
+    #[tokio::test]
+    async fn erratum_upsert_updates_matching_entry() {
+        let pool = test_pool().await;
+        let mut erratum = Erratum {
+            id: 0,
+            rule_id: "grappled".to_string(),
+            published_on: "2025-01-15".to_string(),
+            original_text: "Speed 0.".to_string(),
+            corrected_text: "Speed becomes 0.".to_string(),
+            source_note: None,
+            created_at: String::new(),
+        };
+
+        upsert_erratum(&pool, &erratum).await.unwrap();
+        erratum.source_note = Some("PHB errata 1.1".to_string());
+        upsert_erratum(&pool, &erratum).await.unwrap();
+
+        let errata = get_rule_errata(&pool, "grappled").await.unwrap();
+        assert_eq!(errata.len(), 1);
+        assert_eq!(errata[0].source_note.as_deref(), Some("PHB errata 1.1"));
+        assert!(chrono::DateTime::parse_from_rfc3339(&errata[0].created_at).is_ok());
+
+        delete_erratum(&pool, "grappled", errata[0].id).await.unwrap();
+        assert!(get_rule_errata(&pool, "grappled").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn requeued_vector_syncs_count_attempts() {
+        let pool = test_pool().await;
+
+        queue_vector_sync(&pool, "grappled", "embedding API timed out").await.unwrap();
+        queue_vector_sync(&pool, "grappled", "Qdrant unreachable").await.unwrap();
+
+        let queue = get_vector_sync_queue(&pool).await.unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!((queue[0].attempts, queue[0].last_error.as_str()), (2, "Qdrant unreachable"));
+
+        dequeue_vector_sync(&pool, "grappled").await.unwrap();
+        assert!(get_vector_sync_queue(&pool).await.unwrap().is_empty());
+    }
+}
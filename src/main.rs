@@ -1,5 +1,7 @@
 use axum::Router;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 use tower_http::{
     services::ServeDir,
     trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse, TraceLayer},
@@ -7,16 +9,13 @@ use tower_http::{
 use tracing::Level;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-mod config;
-mod routes;
-mod models;
-mod db;
-mod search;
-mod ai;
-mod middleware;
-
-pub use config::Config;
-use middleware::{RateLimitConfig, RateLimitState};
+use rulecraft::{
+    db,
+    middleware::{RateLimitConfig, RateLimitState},
+    routes,
+    search,
+    Config,
+};
 
 #[tokio::main]
 async fn main() {
@@ -44,14 +43,27 @@ async fn main() {
     tracing::info!("Admin API: {}", if config.admin_api_key.is_some() { "configured" } else { "not configured" });
     tracing::info!("AI rate limit: {} requests/hour", config.ai_rate_limit_per_hour);
     tracing::info!("Search rate limit: {} requests/minute", config.search_rate_limit_per_minute);
+    tracing::info!("Trash retention: {} days", config.trash_retention_days);
 
     // Initialize database
     let db_pool = db::init_pool(&config.database_url).await
         .expect("Failed to initialize database");
 
-    // Run migrations
-    db::run_migrations(&db_pool).await
-        .expect("Failed to run migrations");
+    // Run migrations (refuses to start against a newer schema)
+    if let Err(e) = db::run_migrations(&db_pool).await {
+        tracing::error!("Failed to run migrations: {}", e);
+        std::process::exit(1);
+    }
+
+    // Rebuild the cross-reference graph from the current rule text
+    match search::links::index_rule_links(&db_pool).await {
+        Ok(report) => {
+            for broken in &report.broken {
+                tracing::warn!("Rule '{}' links to missing rule '{}'", broken.rule_id, broken.target_id);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to index rule links: {}", e),
+    }
 
     // Configure rate limiting
     let rate_limit_state = RateLimitState::new(RateLimitConfig {
@@ -62,11 +74,36 @@ async fn main() {
 
     // Build application state
     let state = routes::AppState {
+        rules: Arc::new(db::SqliteRuleRepository::new(db_pool.clone())),
         db: db_pool,
         config,
         rate_limiter: rate_limit_state,
     };
 
+    // Purge rules whose trash retention has run out, once at startup and then hourly
+    if state.config.trash_retention_days > 0 {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+            loop {
+                interval.tick().await;
+                purge_expired_trash(&state).await;
+            }
+        });
+    }
+
+    // Retry rules whose vector failed to sync after a write, every five minutes
+    if state.config.vector.enabled {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(5 * 60));
+            loop {
+                interval.tick().await;
+                retry_vector_syncs(&state).await;
+            }
+        });
+    }
+
     // Build application routes with middleware
     let app = Router::new()
         .merge(routes::router())
@@ -87,3 +124,34 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
 }
+
+async fn purge_expired_trash(state: &routes::AppState) {
+    let purged = match db::purge_expired_trash(state.rules.as_ref(), state.config.trash_retention_days).await {
+        Ok(purged) => purged,
+        Err(e) => {
+            tracing::warn!("Failed to purge expired trash: {}", e);
+            return;
+        }
+    };
+    if purged.is_empty() {
+        return;
+    }
+
+    tracing::info!("Purged {} rule(s) from the trash: {}", purged.len(), purged.join(", "));
+    search::vector_sync::sync_rules(&state.db, state.rules.as_ref(), &state.config.vector, &purged).await;
+    for rule_id in &purged {
+        if let Err(e) = search::links::index_links_of_rule(&state.db, rule_id).await {
+            tracing::warn!("Failed to reindex links after purging {}: {}", rule_id, e);
+        }
+    }
+}
+
+async fn retry_vector_syncs(state: &routes::AppState) {
+    match search::vector_sync::retry_queued(&state.db, state.rules.as_ref(), &state.config.vector).await {
+        Ok(report) if report.synced > 0 || report.queued > 0 => {
+            tracing::info!("Retried vector syncs: {} synced, {} still queued", report.synced, report.queued);
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to read the vector sync queue: {}", e),
+    }
+}
@@ -0,0 +1,578 @@
+use serde::{Deserialize, Serialize};
+
+use super::{slugify, Rule};
+
+/// A weapon row parsed from a `- **Dagger:** 1d4 Piercing, Finesse, ...` line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Weapon {
+    pub id: String,
+    pub rule_id: String,
+    pub name: String,
+    /// From the rule title without "Weapons", e.g. Simple Melee
+    pub category: String,
+    /// "1d8", or a flat "1" for the Blowgun
+    pub damage_dice: String,
+    pub damage_type: String,
+    /// Expected damage of `damage_dice`, used for sorting
+    pub average_damage: f64,
+    /// Properties as written, e.g. "Finesse, Light, Thrown (20/60)"
+    pub properties: String,
+    pub mastery: String,
+    pub cost_cp: Option<i64>,
+    pub weight_lb: Option<f64>,
+}
+
+/// An armor row parsed from a `- **Chain Mail:** AC 16, Str 13 req., ...` line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Armor {
+    pub id: String,
+    pub rule_id: String,
+    pub name: String,
+    /// Light, Medium, Heavy or Shield
+    pub category: String,
+    /// As written after "AC", e.g. "12 + Dex (max 2)" or "+2" for shields
+    pub ac_formula: String,
+    pub base_ac: i32,
+    pub adds_dex: bool,
+    pub dex_cap: Option<i32>,
+    pub strength_requirement: Option<i32>,
+    pub stealth_disadvantage: bool,
+    pub cost_cp: Option<i64>,
+    pub weight_lb: Option<f64>,
+}
+
+/// Any other priced item: adventuring gear, packs, mounts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Gear {
+    pub id: String,
+    pub rule_id: String,
+    pub name: String,
+    /// The rule title, e.g. Equipment Packs
+    pub category: String,
+    pub cost_cp: Option<i64>,
+    pub weight_lb: Option<f64>,
+    pub description: String,
+}
+
+/// Distinct values offered in the `/equipment` filter dropdowns
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EquipmentFacets {
+    pub categories: Vec<String>,
+    pub properties: Vec<String>,
+    pub masteries: Vec<String>,
+    pub damage_types: Vec<String>,
+}
+
+/// Every item listed in one Equipment rule
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EquipmentSet {
+    pub weapons: Vec<Weapon>,
+    pub armor: Vec<Armor>,
+    pub gear: Vec<Gear>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum EquipmentParseError {
+    #[error("cannot read damage for '{0}'")]
+    InvalidDamage(String),
+
+    #[error("cannot read AC for '{0}'")]
+    InvalidArmorClass(String),
+}
+
+impl EquipmentSet {
+    /// Parse the `- **Name:** ...` item lines of a rule in the Equipment
+    /// category. The subcategory decides how lines are read: Weapons and
+    /// Armor rules hold stat lines, everything else is gear.
+    pub fn from_rule(rule: &Rule) -> Result<Self, EquipmentParseError> {
+        let mut set = Self::default();
+        let subcategory = rule.subcategory.as_deref().unwrap_or_default();
+
+        for (name, rest) in rule.content.lines().filter_map(item_line) {
+            if subcategory.eq_ignore_ascii_case("Weapons") {
+                set.weapons.push(parse_weapon(rule, &name, &rest)?);
+            } else if subcategory.eq_ignore_ascii_case("Armor") {
+                set.armor.push(parse_armor(rule, &name, &rest)?);
+            } else {
+                set.gear.push(parse_gear(rule, &name, &rest));
+            }
+        }
+
+        Ok(set)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weapons.is_empty() && self.armor.is_empty() && self.gear.is_empty()
+    }
+}
+
+impl Weapon {
+    pub fn cost_label(&self) -> String {
+        cost_label(self.cost_cp)
+    }
+
+    pub fn weight_label(&self) -> String {
+        weight_label(self.weight_lb)
+    }
+}
+
+impl Armor {
+    pub fn cost_label(&self) -> String {
+        cost_label(self.cost_cp)
+    }
+
+    pub fn weight_label(&self) -> String {
+        weight_label(self.weight_lb)
+    }
+
+    /// "Str 13", or "—" without a requirement
+    pub fn strength_label(&self) -> String {
+        self.strength_requirement
+            .map(|score| format!("Str {}", score))
+            .unwrap_or_else(|| "—".to_string())
+    }
+}
+
+impl Gear {
+    pub fn cost_label(&self) -> String {
+        cost_label(self.cost_cp)
+    }
+
+    pub fn weight_label(&self) -> String {
+        weight_label(self.weight_lb)
+    }
+}
+
+/// Which table an `/equipment/:kind` page or API call lists
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EquipmentKind {
+    Weapons,
+    Armor,
+    Gear,
+}
+
+impl EquipmentKind {
+    pub const ALL: [EquipmentKind; 3] = [Self::Weapons, Self::Armor, Self::Gear];
+
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        match slug.trim().to_lowercase().as_str() {
+            "weapons" | "weapon" => Some(Self::Weapons),
+            "armor" | "armour" => Some(Self::Armor),
+            "gear" => Some(Self::Gear),
+            _ => None,
+        }
+    }
+
+    pub fn slug(&self) -> &'static str {
+        match self {
+            Self::Weapons => "weapons",
+            Self::Armor => "armor",
+            Self::Gear => "gear",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Weapons => "Weapons",
+            Self::Armor => "Armor",
+            Self::Gear => "Gear",
+        }
+    }
+}
+
+/// Sort orders for equipment lists. Damage and AC sort highest first, cost
+/// and weight cheapest/lightest first; a sort that does not apply to a
+/// table falls back to name.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum EquipmentSort {
+    #[default]
+    Name,
+    Damage,
+    ArmorClass,
+    Cost,
+    Weight,
+}
+
+impl EquipmentSort {
+    pub fn from_param(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "name" => Some(Self::Name),
+            "damage" => Some(Self::Damage),
+            "ac" | "armor-class" => Some(Self::ArmorClass),
+            "cost" => Some(Self::Cost),
+            "weight" => Some(Self::Weight),
+            _ => None,
+        }
+    }
+
+    pub fn as_param(&self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Damage => "damage",
+            Self::ArmorClass => "ac",
+            Self::Cost => "cost",
+            Self::Weight => "weight",
+        }
+    }
+}
+
+/// Filters for `/equipment/:kind` and `/api/equipment/:kind`; fields that do
+/// not apply to the listed table are ignored
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EquipmentFilter {
+    pub category: Option<String>,
+    /// Weapon property name without parameters, e.g. Finesse or Two-Handed
+    pub property: Option<String>,
+    pub mastery: Option<String>,
+    pub damage_type: Option<String>,
+    pub stealth_disadvantage: Option<bool>,
+    pub sort: EquipmentSort,
+    /// Admins also see items from private homebrew collections
+    pub include_private_homebrew: bool,
+}
+
+impl EquipmentFilter {
+    /// Build a filter from raw query parameters; blank and unknown values
+    /// are ignored so empty form selects mean "any"
+    pub fn from_params(
+        category: Option<&str>,
+        property: Option<&str>,
+        mastery: Option<&str>,
+        damage_type: Option<&str>,
+        stealth: Option<&str>,
+        sort: Option<&str>,
+    ) -> Self {
+        let text = |value: Option<&str>| value.map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+
+        Self {
+            category: text(category),
+            property: text(property),
+            mastery: text(mastery),
+            damage_type: text(damage_type),
+            stealth_disadvantage: stealth.and_then(parse_flag),
+            sort: sort.and_then(EquipmentSort::from_param).unwrap_or_default(),
+            include_private_homebrew: false,
+        }
+    }
+}
+
+/// Property names without their parameters, for filtering:
+/// "Thrown (20/60)" → Thrown, "Two-Handed unless mounted" → Two-Handed
+pub fn weapon_property_names(properties: &str) -> Vec<String> {
+    split_outside_parens(properties)
+        .into_iter()
+        .map(|property| {
+            let name = property.split('(').next().unwrap_or_default();
+            let name = name.split(" unless ").next().unwrap_or_default();
+            name.trim().to_string()
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// "10 GP", "5 SP", "4 CP", or "—" for items without a price
+pub fn cost_label(cost_cp: Option<i64>) -> String {
+    match cost_cp {
+        None => "—".to_string(),
+        Some(cp) if cp % 100 == 0 => format!("{} GP", cp / 100),
+        Some(cp) if cp % 10 == 0 => format!("{} SP", cp / 10),
+        Some(cp) => format!("{} CP", cp),
+    }
+}
+
+/// "3 lb.", "0.25 lb.", or "—" when no weight is listed
+pub fn weight_label(weight_lb: Option<f64>) -> String {
+    match weight_lb {
+        None => "—".to_string(),
+        Some(lb) if lb.fract() == 0.0 => format!("{:.0} lb.", lb),
+        Some(lb) => format!("{} lb.", lb),
+    }
+}
+
+/// Name and remainder of a `- **Name:** rest` line
+fn item_line(line: &str) -> Option<(String, String)> {
+    let line = line.trim().strip_prefix("- **")?;
+    let (name, rest) = line.split_once(":**")?;
+    Some((name.trim().to_string(), rest.trim().to_string()))
+}
+
+fn parse_weapon(rule: &Rule, name: &str, rest: &str) -> Result<Weapon, EquipmentParseError> {
+    let (stats, tail) = match rest.split_once(". Mastery: ") {
+        Some((stats, tail)) => (stats, tail),
+        None => rest.split_once(". ").unwrap_or((rest.trim_end_matches('.'), "")),
+    };
+    let (mastery, tail) = if rest.contains(". Mastery: ") {
+        tail.split_once('.').unwrap_or((tail, ""))
+    } else {
+        ("", tail)
+    };
+
+    let parts = split_outside_parens(stats);
+    let (damage_dice, damage_type) = parts
+        .first()
+        .and_then(|damage| damage.split_once(' '))
+        .ok_or_else(|| EquipmentParseError::InvalidDamage(name.to_string()))?;
+    let average_damage =
+        average_damage(damage_dice).ok_or_else(|| EquipmentParseError::InvalidDamage(name.to_string()))?;
+    let (cost_cp, weight_lb) = cost_and_weight(&split_outside_parens(tail));
+
+    let category = rule.title.trim_end_matches(" Weapons").trim().to_string();
+
+    Ok(Weapon {
+        id: slugify(name),
+        rule_id: rule.id.clone(),
+        name: name.to_string(),
+        category,
+        damage_dice: damage_dice.to_string(),
+        damage_type: damage_type.trim().to_string(),
+        average_damage,
+        properties: parts[1..].join(", "),
+        mastery: mastery.trim().to_string(),
+        cost_cp,
+        weight_lb,
+    })
+}
+
+fn parse_armor(rule: &Rule, name: &str, rest: &str) -> Result<Armor, EquipmentParseError> {
+    let invalid = || EquipmentParseError::InvalidArmorClass(name.to_string());
+    let parts = split_outside_parens(rest.trim_end_matches('.'));
+
+    let ac_formula = parts
+        .first()
+        .and_then(|ac| ac.strip_prefix("AC "))
+        .ok_or_else(invalid)?
+        .trim()
+        .to_string();
+    let base_ac = leading_number(ac_formula.trim_start_matches('+')).ok_or_else(invalid)?;
+    let adds_dex = ac_formula.contains("Dex");
+    let dex_cap = ac_formula
+        .split_once("(max ")
+        .and_then(|(_, cap)| leading_number(cap));
+
+    let strength_requirement = parts
+        .iter()
+        .find_map(|part| part.strip_prefix("Str "))
+        .and_then(leading_number);
+    let stealth_disadvantage = parts
+        .iter()
+        .any(|part| part.eq_ignore_ascii_case("Disadvantage on Stealth"));
+    let (cost_cp, weight_lb) = cost_and_weight(&parts);
+
+    let category = if ac_formula.starts_with('+') {
+        "Shield".to_string()
+    } else {
+        rule.title.trim_end_matches(" Armor").trim().to_string()
+    };
+
+    Ok(Armor {
+        id: slugify(name),
+        rule_id: rule.id.clone(),
+        name: name.to_string(),
+        category,
+        ac_formula,
+        base_ac,
+        adds_dex,
+        dex_cap,
+        strength_requirement,
+        stealth_disadvantage,
+        cost_cp,
+        weight_lb,
+    })
+}
+
+/// Gear lines put the price either in the name ("Burglar's Pack (16 GP)") or
+/// first in the text ("50 GP. Drink to ...", "8 GP, Speed 40 ft")
+fn parse_gear(rule: &Rule, name: &str, rest: &str) -> Gear {
+    let (name, name_cost) = match name.rsplit_once(" (") {
+        Some((base, paren)) => match parse_cost(paren.trim_end_matches(')')) {
+            Some(cost) => (base.trim(), Some(cost)),
+            None => (name, None),
+        },
+        None => (name, None),
+    };
+
+    let mut description = rest;
+    let mut cost_cp = name_cost;
+    if cost_cp.is_none() {
+        let end = rest.find(['.', ',']).unwrap_or(rest.len());
+        if let Some(cost) = parse_cost(&rest[..end]) {
+            cost_cp = Some(cost);
+            description = rest[end..].trim_start_matches(['.', ',']).trim();
+        }
+    }
+
+    let (_, weight_lb) = cost_and_weight(&split_outside_parens(description));
+
+    Gear {
+        id: slugify(name),
+        rule_id: rule.id.clone(),
+        name: name.to_string(),
+        category: rule.title.clone(),
+        cost_cp,
+        weight_lb,
+        description: description.to_string(),
+    }
+}
+
+/// Cost and weight from standalone "75 GP" and "55 lb." list entries
+fn cost_and_weight(parts: &[String]) -> (Option<i64>, Option<f64>) {
+    let cost = parts.iter().find_map(|part| parse_cost(part));
+    let weight = parts.iter().find_map(|part| {
+        part.trim()
+            .strip_suffix(" lb.")
+            .or_else(|| part.trim().strip_suffix(" lb"))
+            .and_then(|lb| lb.trim().parse().ok())
+    });
+    (cost, weight)
+}
+
+/// "75 GP" → 7500 copper pieces
+fn parse_cost(text: &str) -> Option<i64> {
+    let (amount, coin) = text.trim().split_once(' ')?;
+    let amount: i64 = amount.replace(',', "").parse().ok()?;
+    let rate = match coin.trim().to_uppercase().as_str() {
+        "CP" => 1,
+        "SP" => 10,
+        "EP" => 50,
+        "GP" => 100,
+        "PP" => 1000,
+        _ => return None,
+    };
+    Some(amount * rate)
+}
+
+/// Expected roll of "2d6" (7.0) or a flat "1"
+fn average_damage(dice: &str) -> Option<f64> {
+    match dice.split_once('d') {
+        Some((count, sides)) => {
+            let count: f64 = if count.is_empty() { 1.0 } else { count.parse().ok()? };
+            let sides: f64 = sides.parse().ok()?;
+            Some(count * (sides + 1.0) / 2.0)
+        }
+        None => dice.parse().ok(),
+    }
+}
+
+fn leading_number(text: &str) -> Option<i32> {
+    let digits: String = text.trim().chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Split on commas that are not inside parentheses
+fn split_outside_parens(text: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    parts.push(current.trim().to_string());
+
+    parts.into_iter().filter(|part| !part.is_empty()).collect()
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn equipment_rule(id: &str, title: &str, subcategory: &str, content: &str) -> Rule {
+        Rule {
+            category: "Equipment".to_string(),
+            subcategory: Some(subcategory.to_string()),
+            ..Rule::test(id, title, content)
+        }
+    }
+
+    #[test]
+    fn parses_weapon_lines() {
+        let rule = equipment_rule(
+            "simple-ranged-weapons",
+            "Simple Ranged Weapons",
+            "Weapons",
+            "- **Crossbow, Light:** 1d8 Piercing, Ammunition (Range 80/320), Loading, Two-Handed. Mastery: Slow. 5 lb., 25 GP\n\n- **Blowgun:** 1 Piercing, Loading. Mastery: Vex. 1 lb., 10 GP",
+        );
+        let set = EquipmentSet::from_rule(&rule).unwrap();
+        let crossbow = &set.weapons[0];
+
+        assert_eq!(crossbow.id, "crossbow-light");
+        assert_eq!(crossbow.category, "Simple Ranged");
+        assert_eq!((crossbow.damage_dice.as_str(), crossbow.damage_type.as_str()), ("1d8", "Piercing"));
+        assert_eq!(crossbow.average_damage, 4.5);
+        assert_eq!(crossbow.properties, "Ammunition (Range 80/320), Loading, Two-Handed");
+        assert_eq!(crossbow.mastery, "Slow");
+        assert_eq!((crossbow.cost_cp, crossbow.weight_lb), (Some(2500), Some(5.0)));
+        assert_eq!(set.weapons[1].average_damage, 1.0);
+    }
+
+    #[test]
+    fn parses_armor_lines() {
+        let rule = equipment_rule(
+            "medium-armor",
+            "Medium Armor",
+            "Armor",
+            "Intro.\n\n- **Half Plate:** AC 15 + Dex (max 2), Disadvantage on Stealth, 40 lb., 750 GP\n- **Chain Mail:** AC 16, Str 13 req., 55 lb., 75 GP\n- **Shield:** AC +2, 6 lb., 10 GP",
+        );
+        let armor = EquipmentSet::from_rule(&rule).unwrap().armor;
+
+        assert_eq!(armor[0].category, "Medium");
+        assert_eq!((armor[0].base_ac, armor[0].adds_dex, armor[0].dex_cap), (15, true, Some(2)));
+        assert!(armor[0].stealth_disadvantage);
+        assert_eq!(armor[1].strength_requirement, Some(13));
+        assert!(!armor[1].adds_dex);
+        assert_eq!((armor[2].category.as_str(), armor[2].base_ac), ("Shield", 2));
+    }
+
+    #[test]
+    fn parses_gear_prices_from_name_or_text() {
+        let rule = equipment_rule(
+            "gear",
+            "Adventuring Gear",
+            "Equipment",
+            "- **Burglar's Pack (16 GP):** Backpack, crowbar\n- **Camel:** 50 GP, Speed 50 ft\n- **Arcane Focus:** Used as a focus.",
+        );
+        let gear = EquipmentSet::from_rule(&rule).unwrap().gear;
+
+        assert_eq!((gear[0].name.as_str(), gear[0].cost_cp), ("Burglar's Pack", Some(1600)));
+        assert_eq!((gear[1].cost_cp, gear[1].description.as_str()), (Some(5000), "Speed 50 ft"));
+        assert_eq!(gear[2].cost_cp, None);
+    }
+
+    #[test]
+    fn reports_unreadable_stat_lines() {
+        let rule = equipment_rule("w", "Martial Melee Weapons", "Weapons", "- **Net:** Special");
+        assert_eq!(
+            EquipmentSet::from_rule(&rule),
+            Err(EquipmentParseError::InvalidDamage("Net".to_string()))
+        );
+    }
+
+    #[test]
+    fn property_names_and_labels() {
+        assert_eq!(
+            weapon_property_names("Finesse, Thrown (20/60), Two-Handed unless mounted"),
+            vec!["Finesse", "Thrown", "Two-Handed"]
+        );
+        assert_eq!(cost_label(Some(150_000)), "1500 GP");
+        assert_eq!(cost_label(Some(20)), "2 SP");
+        assert_eq!(weight_label(Some(0.25)), "0.25 lb.");
+    }
+}
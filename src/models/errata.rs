@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+/// A published correction to a rule's text
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Erratum {
+    pub id: i64,
+    pub rule_id: String,
+    /// Publication date as `YYYY-MM-DD`; the erratum takes effect on this day
+    pub published_on: String,
+    pub original_text: String,
+    pub corrected_text: String,
+    pub source_note: Option<String>,
+    pub created_at: String,
+}
+
+impl Erratum {
+    pub fn is_effective(&self, today: &str) -> bool {
+        self.published_on.as_str() <= today
+    }
+}
+
+/// Today's date in the `YYYY-MM-DD` form used by `Erratum::published_on`
+pub fn errata_today() -> String {
+    chrono::Utc::now().format("%Y-%m-%d").to_string()
+}
+
+/// Whether `date` is a valid `YYYY-MM-DD` publication date
+pub fn is_valid_errata_date(date: &str) -> bool {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok()
+}
+
+/// Apply the effective errata to `content`, oldest first.
+///
+/// Text that has already been corrected (for example because the YAML was
+/// updated alongside the erratum) is left untouched.
+pub fn apply_errata(content: &str, errata: &[Erratum], today: &str) -> String {
+    let mut effective: Vec<&Erratum> = errata.iter().filter(|e| e.is_effective(today)).collect();
+    effective.sort_by(|a, b| a.published_on.cmp(&b.published_on));
+
+    effective.into_iter().fold(content.to_string(), |text, erratum| {
+        if erratum.original_text.is_empty() {
+            text
+        } else {
+            text.replace(&erratum.original_text, &erratum.corrected_text)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn erratum(published_on: &str, original: &str, corrected: &str) -> Erratum {
+        Erratum {
+            id: 1,
+            rule_id: "grappled".to_string(),
+            published_on: published_on.to_string(),
+            original_text: original.to_string(),
+            corrected_text: corrected.to_string(),
+            source_note: None,
+            created_at: "2025-01-01".to_string(),
+        }
+    }
+
+    #[test]
+    fn applies_effective_errata_in_date_order() {
+        let errata = vec![
+            erratum("2025-03-01", "Speed is 5", "Speed is 0"),
+            erratum("2025-01-01", "Speed is 10", "Speed is 5"),
+        ];
+
+        let text = apply_errata("Your Speed is 10.", &errata, "2025-06-01");
+
+        assert_eq!(text, "Your Speed is 0.");
+    }
+
+    #[test]
+    fn future_errata_are_not_applied_yet() {
+        let errata = vec![erratum("2030-01-01", "Speed is 10", "Speed is 0")];
+
+        assert_eq!(apply_errata("Speed is 10", &errata, "2025-06-01"), "Speed is 10");
+    }
+
+    #[test]
+    fn validates_publication_dates() {
+        assert!(is_valid_errata_date("2025-01-15"));
+        assert!(!is_valid_errata_date("15/01/2025"));
+    }
+}
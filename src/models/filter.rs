@@ -0,0 +1,199 @@
+use super::{HomebrewCollection, Source};
+
+/// Restricts rule listings, searches and Oracle retrieval.
+///
+/// `sources` and `excluded_sources` hold source abbreviations (PHB, DMG, ...);
+/// an empty `sources` list means every source is allowed. `tags` holds tag
+/// slugs, and a rule must carry all of them to pass. `categories` holds
+/// lower-case category names, `subcategories` lower-case subcategory names
+/// and `pages` page numbers, each of which a rule must match when set. `excluded_terms` holds quoted FTS5 strings a rule
+/// must not contain (see [`crate::search::query`]). Homebrew rules from
+/// public collections pass unless `exclude_homebrew` is set; rules from
+/// private collections only pass with `include_private_homebrew`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleFilter {
+    pub sources: Vec<String>,
+    pub excluded_sources: Vec<String>,
+    pub edition: Option<String>,
+    pub tags: Vec<String>,
+    pub categories: Vec<String>,
+    pub excluded_categories: Vec<String>,
+    pub subcategories: Vec<String>,
+    pub pages: Vec<i32>,
+    pub excluded_terms: Vec<String>,
+    pub exclude_homebrew: bool,
+    pub include_private_homebrew: bool,
+}
+
+impl RuleFilter {
+    /// Build a filter from comma-separated query parameters such as
+    /// `source=PHB,DMG`, `exclude_source=DMG` and `edition=2024`
+    pub fn from_params(source: Option<&str>, exclude_source: Option<&str>, edition: Option<&str>) -> Self {
+        Self {
+            sources: split_abbreviations(source),
+            excluded_sources: split_abbreviations(exclude_source),
+            edition: edition
+                .map(|e| e.trim().to_string())
+                .filter(|e| !e.is_empty()),
+            tags: Vec::new(),
+            categories: Vec::new(),
+            excluded_categories: Vec::new(),
+            subcategories: Vec::new(),
+            pages: Vec::new(),
+            excluded_terms: Vec::new(),
+            exclude_homebrew: false,
+            include_private_homebrew: false,
+        }
+    }
+
+    /// Apply the `homebrew=exclude` toggle; admins also see private collections
+    pub fn with_homebrew(mut self, homebrew: Option<&str>, is_admin: bool) -> Self {
+        self.exclude_homebrew = homebrew
+            .map(|value| value.trim().eq_ignore_ascii_case("exclude"))
+            .unwrap_or(false);
+        self.include_private_homebrew = is_admin;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+            && self.excluded_sources.is_empty()
+            && self.edition.is_none()
+            && self.tags.is_empty()
+            && self.categories.is_empty()
+            && self.excluded_categories.is_empty()
+            && self.subcategories.is_empty()
+            && self.pages.is_empty()
+            && self.excluded_terms.is_empty()
+            && !self.exclude_homebrew
+    }
+
+    /// Whether a rule from `source` (None for an unlinked source) passes the
+    /// source and edition constraints; tags are not checked here
+    pub fn matches_source(&self, source: Option<&Source>) -> bool {
+        let abbreviation = source.map(|s| s.abbreviation.as_str());
+
+        if !self.sources.is_empty()
+            && !abbreviation.map(|a| self.sources.iter().any(|s| s == a)).unwrap_or(false)
+        {
+            return false;
+        }
+
+        if abbreviation
+            .map(|a| self.excluded_sources.iter().any(|s| s == a))
+            .unwrap_or(false)
+        {
+            return false;
+        }
+
+        match &self.edition {
+            Some(edition) => source.map(|s| &s.edition == edition).unwrap_or(false),
+            None => true,
+        }
+    }
+
+    /// Whether a rule in `category` and `subcategory` on `page` passes the
+    /// category, subcategory and page constraints
+    pub fn matches_placement(&self, category: &str, subcategory: Option<&str>, page: Option<i32>) -> bool {
+        let category = category.to_lowercase();
+        let subcategory = subcategory.map(str::to_lowercase);
+        (self.categories.is_empty() || self.categories.contains(&category))
+            && !self.excluded_categories.contains(&category)
+            && (self.subcategories.is_empty() || subcategory.is_some_and(|s| self.subcategories.contains(&s)))
+            && (self.pages.is_empty() || page.is_some_and(|page| self.pages.contains(&page)))
+    }
+
+    /// Whether a rule in the collection `collection_id` (None for official
+    /// content) passes the homebrew constraints, looking up its visibility in
+    /// `collections`
+    pub fn matches_collection(&self, collection_id: Option<&str>, collections: &[HomebrewCollection]) -> bool {
+        let Some(collection_id) = collection_id else {
+            return true;
+        };
+        if self.exclude_homebrew {
+            return false;
+        }
+
+        self.include_private_homebrew
+            || !collections
+                .iter()
+                .any(|collection| collection.id == collection_id && collection.is_private())
+    }
+}
+
+fn split_abbreviations(value: Option<&str>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_uppercase())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(abbreviation: &str, edition: &str) -> Source {
+        Source {
+            abbreviation: abbreviation.to_string(),
+            name: abbreviation.to_string(),
+            edition: edition.to_string(),
+            published_year: None,
+            display_order: 0,
+        }
+    }
+
+    #[test]
+    fn parses_comma_separated_params() {
+        let filter = RuleFilter::from_params(Some("phb, dmg"), Some("MM"), Some(" "));
+
+        assert_eq!(filter.sources, vec!["PHB", "DMG"]);
+        assert_eq!(filter.excluded_sources, vec!["MM"]);
+        assert_eq!(filter.edition, None);
+    }
+
+    #[test]
+    fn excluding_a_source_keeps_the_others() {
+        let filter = RuleFilter::from_params(None, Some("DMG"), None);
+
+        assert!(filter.matches_source(Some(&source("PHB", "2024"))));
+        assert!(!filter.matches_source(Some(&source("DMG", "2024"))));
+        assert!(filter.matches_source(None));
+    }
+
+    #[test]
+    fn included_sources_and_edition_require_a_linked_source() {
+        let filter = RuleFilter::from_params(Some("PHB"), None, Some("2024"));
+
+        assert!(filter.matches_source(Some(&source("PHB", "2024"))));
+        assert!(!filter.matches_source(Some(&source("PHB", "2014"))));
+        assert!(!filter.matches_source(None));
+    }
+
+    #[test]
+    fn homebrew_toggle_and_private_collections() {
+        let collections = |visibility: &str| {
+            vec![HomebrewCollection {
+                id: "table".to_string(),
+                name: "Table Rules".to_string(),
+                owner: "Sam".to_string(),
+                visibility: visibility.to_string(),
+                description: None,
+                created_at: crate::models::now_utc(),
+            }]
+        };
+
+        let default = RuleFilter::default();
+        assert!(default.matches_collection(None, &collections("private")));
+        assert!(default.matches_collection(Some("table"), &collections("public")));
+        assert!(!default.matches_collection(Some("table"), &collections("private")));
+
+        let admin = RuleFilter::default().with_homebrew(None, true);
+        assert!(admin.matches_collection(Some("table"), &collections("private")));
+
+        let official = RuleFilter::default().with_homebrew(Some("exclude"), true);
+        assert!(official.matches_collection(None, &collections("public")));
+        assert!(!official.matches_collection(Some("table"), &collections("public")));
+    }
+}
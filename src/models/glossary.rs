@@ -0,0 +1,231 @@
+use pulldown_cmark::{html, Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::section::section_starts;
+
+/// A game term explained in the glossary, e.g. "Difficulty Class" (DC).
+/// Aliases live in `glossary_aliases`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct GlossaryTerm {
+    /// Slug of the term
+    pub id: String,
+    pub term: String,
+    /// One line, shown as the tooltip
+    pub definition: String,
+    /// Rule that covers the term in full
+    pub rule_id: Option<String>,
+    /// Abbreviations and alternate names, e.g. "DC"
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl GlossaryTerm {
+    /// Tooltip text, e.g. "Difficulty Class: Target number for checks and saves"
+    pub fn tooltip(&self) -> String {
+        format!("{}: {}", self.term, self.definition)
+    }
+
+    /// The term followed by its aliases
+    fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.term.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+}
+
+/// Render Markdown to HTML, marking the first mention of each glossary term
+/// with a hover tooltip. Mentions inside links, headings and code are left
+/// alone. Abbreviations written in capitals (DC, HP) only match in capitals;
+/// other names match in any case. Headings and labels that start a section
+/// carry the section's anchor as an `id`.
+pub fn render_markdown(markdown: &str, terms: &[GlossaryTerm]) -> String {
+    let mut names: Vec<(&str, usize)> = terms
+        .iter()
+        .enumerate()
+        .flat_map(|(index, term)| term.names().map(move |name| (name, index)))
+        .filter(|(name, _)| !name.trim().is_empty())
+        .collect();
+    // Longest first, so "Bonus Action" wins over "Action"
+    names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    let mut anchors: HashMap<usize, String> = section_starts(markdown)
+        .into_iter()
+        .map(|start| (start.offset, start.anchor))
+        .collect();
+
+    let mut annotated = HashSet::new();
+    let mut skip_depth = 0usize;
+    let events = Parser::new(markdown).into_offset_iter().flat_map(|(event, range)| {
+        // A paragraph and the label opening it share an offset; the paragraph
+        // takes the anchor
+        let anchor = match &event {
+            Event::Start(Tag::Heading { .. } | Tag::Paragraph | Tag::Strong | Tag::Emphasis) => {
+                anchors.remove(&range.start)
+            }
+            _ => None,
+        };
+        match (event, anchor) {
+            (Event::Start(Tag::Heading { level, classes, attrs, .. }), Some(id)) => {
+                skip_depth += 1;
+                vec![Event::Start(Tag::Heading { level, id: Some(id.into()), classes, attrs })]
+            }
+            (Event::Start(Tag::Paragraph), Some(id)) => vec![Event::Html(format!(r#"<p id="{}">"#, id).into())],
+            (event, Some(id)) => vec![Event::InlineHtml(format!(r#"<span id="{}"></span>"#, id).into()), event],
+            (event, None) => vec![annotate_event(event, terms, &names, &mut annotated, &mut skip_depth)],
+        }
+    });
+
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, events);
+    html_output
+}
+
+/// Track where tooltips are not wanted and annotate text elsewhere
+fn annotate_event<'a>(
+    event: Event<'a>,
+    terms: &[GlossaryTerm],
+    names: &[(&str, usize)],
+    annotated: &mut HashSet<usize>,
+    skip_depth: &mut usize,
+) -> Event<'a> {
+    match event {
+        Event::Start(tag @ (Tag::Link { .. } | Tag::Heading { .. } | Tag::CodeBlock(_) | Tag::Image { .. })) => {
+            *skip_depth += 1;
+            Event::Start(tag)
+        }
+        Event::End(tag @ (TagEnd::Link | TagEnd::Heading(_) | TagEnd::CodeBlock | TagEnd::Image)) => {
+            *skip_depth = skip_depth.saturating_sub(1);
+            Event::End(tag)
+        }
+        Event::Text(text) if *skip_depth == 0 && !names.is_empty() => {
+            match annotate_text(&text, terms, names, annotated) {
+                Some(html) => Event::InlineHtml(html.into()),
+                None => Event::Text(text),
+            }
+        }
+        event => event,
+    }
+}
+
+/// HTML for `text` with tooltips around the first mention of each term not
+/// yet `annotated`, or None when nothing in it matched
+fn annotate_text(
+    text: &str,
+    terms: &[GlossaryTerm],
+    names: &[(&str, usize)],
+    annotated: &mut HashSet<usize>,
+) -> Option<String> {
+    let mut output = String::new();
+    let mut copied = 0;
+    let mut position = 0;
+    let mut matched = false;
+
+    while position < text.len() {
+        let at_word_start = text[..position]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        let found = at_word_start
+            .then(|| {
+                names.iter().find(|(name, index)| {
+                    !annotated.contains(index) && matches_at(&text[position..], name)
+                })
+            })
+            .flatten();
+
+        match found {
+            Some(&(name, index)) => {
+                let end = position + name.len();
+                output.push_str(&escape_html(&text[copied..position]));
+                output.push_str(&format!(
+                    r#"<abbr class="glossary-term" title="{}">{}</abbr>"#,
+                    escape_html(&terms[index].tooltip()),
+                    escape_html(&text[position..end])
+                ));
+                annotated.insert(index);
+                copied = end;
+                position = end;
+                matched = true;
+            }
+            None => position += text[position..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+
+    matched.then(|| {
+        output.push_str(&escape_html(&text[copied..]));
+        output
+    })
+}
+
+/// Whether `text` starts with `name` as a whole word
+fn matches_at(text: &str, name: &str) -> bool {
+    let Some(candidate) = text.get(..name.len()) else {
+        return false;
+    };
+    let is_abbreviation = name.chars().any(char::is_alphabetic)
+        && name.chars().all(|c| !c.is_alphabetic() || c.is_uppercase());
+    let same = if is_abbreviation {
+        candidate == name
+    } else {
+        candidate.eq_ignore_ascii_case(name)
+    };
+
+    same && text[name.len()..].chars().next().is_none_or(|c| !c.is_alphanumeric())
+}
+
+pub(super) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(term: &str, aliases: &[&str], definition: &str) -> GlossaryTerm {
+        GlossaryTerm {
+            id: crate::models::slugify(term),
+            term: term.to_string(),
+            definition: definition.to_string(),
+            rule_id: None,
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn first_mention_of_each_term_gets_a_tooltip() {
+        let terms = vec![
+            term("Difficulty Class", &["DC"], "Target number for checks"),
+            term("Action", &[], "Main activity on your turn"),
+            term("Bonus Action", &[], "Quick activity"),
+        ];
+
+        let html = render_markdown("Make a DC 15 check as a bonus action, then a DC 10 one as an Action.", &terms);
+
+        assert!(html.contains(r#"<abbr class="glossary-term" title="Difficulty Class: Target number for checks">DC</abbr> 15"#));
+        assert!(html.contains(r#"title="Bonus Action: Quick activity">bonus action</abbr>"#));
+        assert!(html.contains(r#"title="Action: Main activity on your turn">Action</abbr>."#));
+        assert_eq!(html.matches("<abbr").count(), 3);
+    }
+
+    #[test]
+    fn section_starts_carry_their_anchors() {
+        let html = render_markdown("Intro.\n\n## Recovery\n\n**Sap:** Slowed.\n**Topple:** Prone.", &[]);
+
+        assert!(html.contains(r#"<h2 id="recovery">Recovery</h2>"#));
+        assert!(html.contains(r#"<p id="sap"><strong>Sap:</strong> Slowed."#));
+        assert!(html.contains(r#"<span id="topple"></span><strong>Topple:</strong> Prone.</p>"#));
+    }
+
+    #[test]
+    fn tooltips_skip_code_links_headings_and_partial_words() {
+        let terms = vec![term("Difficulty Class", &["DC"], "Target <number>"), term("Prone", &[], "On the ground")];
+
+        let html = render_markdown("# Prone\n\n`DC` and [DC](/rules/dc), dc, DCs, pronely.\n\nNow DC & co.", &terms);
+
+        assert_eq!(html.matches("<abbr").count(), 1);
+        assert!(html.contains(r#"Now <abbr class="glossary-term" title="Difficulty Class: Target &lt;number&gt;">DC</abbr> &amp; co."#));
+    }
+}
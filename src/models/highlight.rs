@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+use super::glossary::escape_html;
+
+/// Marks the start of a matched term in FTS5 `highlight()`/`snippet()` output
+pub const MATCH_START: char = '\u{2}';
+/// Marks the end of a matched term
+pub const MATCH_END: char = '\u{3}';
+/// Words of rule text shown around the first match
+pub const SNIPPET_WORDS: usize = 24;
+
+/// A search hit's title and a passage of its text with the matched terms in
+/// `<mark>`. Both are HTML, escaped apart from the marks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHighlight {
+    pub title: String,
+    pub snippet: String,
+}
+
+impl SearchHighlight {
+    /// From FTS5 output whose matches are wrapped in [`MATCH_START`] and
+    /// [`MATCH_END`]; the snippet is rule Markdown and is reduced to plain text
+    pub fn from_marked(title: &str, snippet: &str) -> Self {
+        Self {
+            title: marks_to_html(title),
+            snippet: marks_to_html(&plain_text(snippet)),
+        }
+    }
+
+    /// Mark the words of `title` and `content` starting with any of `terms`
+    /// (lower-case words), for hits FTS5 did not highlight: substring
+    /// matches and the in-memory repository. None when no word matches.
+    pub fn for_terms(title: &str, content: &str, terms: &[String]) -> Option<Self> {
+        let is_match = |word: &str| {
+            let word = word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+            !word.is_empty() && terms.iter().any(|term| !term.is_empty() && word.starts_with(term.as_str()))
+        };
+        let mark = |words: &[&str]| {
+            words
+                .iter()
+                .map(|word| match is_match(word) {
+                    true => format!("{}{}{}", MATCH_START, word, MATCH_END),
+                    false => word.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let title_words: Vec<&str> = title.split_whitespace().collect();
+        let text = plain_text(content);
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let first = words.iter().position(|word| is_match(word));
+        if first.is_none() && !title_words.iter().any(|word| is_match(word)) {
+            return None;
+        }
+
+        let start = first.map_or(0, |first| first.saturating_sub(SNIPPET_WORDS / 4));
+        let end = (start + SNIPPET_WORDS).min(words.len());
+        let mut snippet = mark(&words[start..end]);
+        if start > 0 {
+            snippet.insert_str(0, "… ");
+        }
+        if end < words.len() {
+            snippet.push_str(" …");
+        }
+
+        Some(Self {
+            title: marks_to_html(&mark(&title_words)),
+            snippet: marks_to_html(&snippet),
+        })
+    }
+}
+
+/// Escape `text` and turn the match markers into `<mark>` elements
+fn marks_to_html(text: &str) -> String {
+    escape_html(text)
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
+
+/// Rule Markdown as one line of plain text: emphasis, headings, list bullets
+/// and quote markers dropped, `[[rule:id|label]]` links reduced to the label
+fn plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut rest = markdown;
+    while let Some(start) = rest.find("[[rule:") {
+        text.push_str(&rest[..start]);
+        let link = &rest[start + "[[rule:".len()..];
+        match link.find("]]") {
+            Some(end) => {
+                let inner = &link[..end];
+                text.push_str(inner.split_once('|').map_or(inner, |(_, label)| label));
+                rest = &link[end + 2..];
+            }
+            // A link cut off by the snippet
+            None => rest = link,
+        }
+    }
+    text.push_str(rest);
+
+    text.lines()
+        .map(|line| {
+            let line = line.trim_start();
+            line.strip_prefix("- ")
+                .or_else(|| line.strip_prefix("> "))
+                .unwrap_or(line)
+        })
+        .flat_map(|line| line.split_whitespace())
+        .map(|word| word.replace(['*', '#', '`'], ""))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fts_marks_become_escaped_html() {
+        let highlight = SearchHighlight::from_marked(
+            "\u{2}Grappled\u{3} <Condition>",
+            "…speed is 0.\n\n- **\u{2}Grappled\u{3}:** see [[rule:escape|Escaping a \u{2}Grapple\u{3}]] & [[rule:sh",
+        );
+
+        assert_eq!(highlight.title, "<mark>Grappled</mark> &lt;Condition&gt;");
+        assert_eq!(
+            highlight.snippet,
+            "…speed is 0. <mark>Grappled</mark>: see Escaping a <mark>Grapple</mark> &amp; sh"
+        );
+    }
+
+    #[test]
+    fn term_highlights_center_on_the_first_match() {
+        let content = (1..=40).map(|n| format!("w{}", n)).collect::<Vec<_>>().join(" ") + " grappled end";
+        let highlight = SearchHighlight::for_terms("Grappled", &content, &["grap".to_string()]).unwrap();
+
+        assert_eq!(highlight.title, "<mark>Grappled</mark>");
+        assert!(highlight.snippet.starts_with("… w35 w36"));
+        assert!(highlight.snippet.ends_with("<mark>grappled</mark> end"));
+        assert!(SearchHighlight::for_terms("Prone", "You crawl.", &["grap".to_string()]).is_none());
+    }
+}
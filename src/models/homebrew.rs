@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A named set of house rules, e.g. "Curse of Strahd table rules".
+/// Rules join a collection through `rules.collection_id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct HomebrewCollection {
+    pub id: String,
+    pub name: String,
+    /// Who the house rules belong to, e.g. a DM or a table name
+    pub owner: String,
+    /// "public" or "private"; see [`Visibility`]
+    pub visibility: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl HomebrewCollection {
+    pub fn is_private(&self) -> bool {
+        self.visibility == Visibility::Private.as_str()
+    }
+}
+
+/// Who may see the rules of a homebrew collection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Listed, searched and used by the Oracle for everyone
+    Public,
+    /// Only shown to admins
+    Private,
+}
+
+impl Visibility {
+    pub const ALL: [Visibility; 2] = [Visibility::Public, Visibility::Private];
+
+    pub fn from_param(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "public" => Some(Self::Public),
+            "private" => Some(Self::Private),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Private => "private",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Public => "Public",
+            Self::Private => "Private (admins only)",
+        }
+    }
+}
@@ -0,0 +1,23 @@
+/// How one rule came to reference another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    Explicit,
+    Mention,
+}
+
+impl LinkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkKind::Explicit => "explicit",
+            LinkKind::Mention => "mention",
+        }
+    }
+}
+
+/// Edge in the `rule_links` cross-reference graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleLink {
+    pub source_id: String,
+    pub target_id: String,
+    pub kind: LinkKind,
+}
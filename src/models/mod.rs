@@ -1,3 +1,38 @@
+mod equipment;
+mod errata;
+mod filter;
+mod glossary;
+mod highlight;
+mod homebrew;
+mod link;
+mod revision;
 mod rule;
+mod search_page;
+mod section;
+mod slug;
+mod source;
+mod spell;
+mod synonym;
+mod tag;
+mod vector_record;
 
-pub use rule::Rule;
+pub use equipment::{
+    cost_label, weapon_property_names, weight_label, Armor, EquipmentFacets, EquipmentFilter, EquipmentKind,
+    EquipmentParseError, EquipmentSet, EquipmentSort, Gear, Weapon,
+};
+pub use errata::{apply_errata, errata_today, is_valid_errata_date, Erratum};
+pub use filter::RuleFilter;
+pub use glossary::{render_markdown, GlossaryTerm};
+pub use highlight::{SearchHighlight, MATCH_END, MATCH_START, SNIPPET_WORDS};
+pub use homebrew::{HomebrewCollection, Visibility};
+pub use link::{LinkKind, RuleLink};
+pub use revision::{diff_lines, field_changes, DiffKind, DiffLine, FieldChange, RuleRevision};
+pub use rule::{now_utc, Rule, TrashedRule};
+pub use search_page::{FacetCount, SearchFacets, SearchPage};
+pub use section::{matching_section, rule_sections, RuleSection};
+pub use slug::slugify;
+pub use source::Source;
+pub use spell::{level_label, Spell, SpellFilter, SpellParseError};
+pub use synonym::{normalize_phrase, Synonym};
+pub use tag::{parse_tag_list, Tag, TagSummary};
+pub use vector_record::{QueuedVectorSync, VectorRecord};
@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+use super::{EquipmentSet, Rule};
+
+/// Snapshot of a rule taken just before it was overwritten
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RuleRevision {
+    pub id: i64,
+    pub rule_id: String,
+    pub title: String,
+    pub category: String,
+    pub subcategory: Option<String>,
+    pub content: String,
+    pub source: String,
+    pub page: Option<i32>,
+    pub changed_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RuleRevision {
+    /// The rule as it looked when this snapshot was taken
+    pub fn to_rule(&self) -> Rule {
+        Rule {
+            id: self.rule_id.clone(),
+            title: self.title.clone(),
+            category: self.category.clone(),
+            subcategory: self.subcategory.clone(),
+            content: self.content.clone(),
+            source: self.source.clone(),
+            source_id: None,
+            page: self.page,
+            created_at: self.created_at,
+            updated_at: self.created_at,
+            collection_id: None,
+            tags: Vec::new(),
+            equipment: EquipmentSet::default(),
+            collection: None,
+            section: None,
+            highlight: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffKind {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+impl DiffLine {
+    pub fn css_class(&self) -> &'static str {
+        match self.kind {
+            DiffKind::Unchanged => "diff-same",
+            DiffKind::Added => "diff-add",
+            DiffKind::Removed => "diff-del",
+        }
+    }
+
+    pub fn marker(&self) -> &'static str {
+        match self.kind {
+            DiffKind::Unchanged => " ",
+            DiffKind::Added => "+",
+            DiffKind::Removed => "-",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Line-by-line diff of two Markdown bodies
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            kind: match change.tag() {
+                ChangeTag::Equal => DiffKind::Unchanged,
+                ChangeTag::Insert => DiffKind::Added,
+                ChangeTag::Delete => DiffKind::Removed,
+            },
+            text: change.value().trim_end_matches('\n').to_string(),
+        })
+        .collect()
+}
+
+/// Metadata fields (everything except content) that differ between two versions
+pub fn field_changes(old: &Rule, new: &Rule) -> Vec<FieldChange> {
+    let page = |page: Option<i32>| page.map(|p| p.to_string()).unwrap_or_default();
+    let fields = [
+        ("Title", old.title.clone(), new.title.clone()),
+        ("Category", old.category.clone(), new.category.clone()),
+        (
+            "Subcategory",
+            old.subcategory.clone().unwrap_or_default(),
+            new.subcategory.clone().unwrap_or_default(),
+        ),
+        ("Source", old.source.clone(), new.source.clone()),
+        ("Page", page(old.page), page(new.page)),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(field, old, new)| FieldChange {
+            field: field.to_string(),
+            old,
+            new,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::slugify;
+
+    fn rule(title: &str, page: Option<i32>) -> Rule {
+        Rule {
+            page,
+            ..Rule::test(&slugify(title), title, "Text")
+        }
+    }
+
+    #[test]
+    fn diff_marks_added_and_removed_lines() {
+        let diff = diff_lines("one\ntwo\nthree", "one\n2\nthree");
+        let kinds: Vec<_> = diff.iter().map(|line| (line.kind, line.text.as_str())).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                (DiffKind::Unchanged, "one"),
+                (DiffKind::Removed, "two"),
+                (DiffKind::Added, "2"),
+                (DiffKind::Unchanged, "three"),
+            ]
+        );
+    }
+
+    #[test]
+    fn field_changes_only_lists_differences() {
+        let changes = field_changes(&rule("Grapple", Some(10)), &rule("Grappled", None));
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].field, "Title");
+        assert_eq!(changes[1], FieldChange {
+            field: "Page".to_string(),
+            old: "10".to_string(),
+            new: String::new(),
+        });
+    }
+}
@@ -1,6 +1,9 @@
+use chrono::{DateTime, SubsecRound, Utc};
 use serde::{Deserialize, Serialize};
 use pulldown_cmark::{Parser, Event};
 
+use super::{slugify, EquipmentSet, HomebrewCollection, RuleSection, SearchHighlight, Tag};
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Rule {
     pub id: String,
@@ -9,28 +12,113 @@ pub struct Rule {
     pub subcategory: Option<String>,
     pub content: String,
     pub source: String,
+    /// Abbreviation of the linked `sources` row, if the source is known
+    pub source_id: Option<String>,
     pub page: Option<i32>,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// ID of the homebrew collection the rule belongs to; None for official content
+    pub collection_id: Option<String>,
+    /// Tags from `rule_tags`; only filled in where a page displays them
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    /// Weapons, armor and gear listed by an Equipment rule; only filled in
+    /// where a page renders the stat table
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub equipment: EquipmentSet,
+    /// The rule's homebrew collection; only filled in where a page or the
+    /// Oracle needs to label homebrew
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub collection: Option<HomebrewCollection>,
+    /// The section a search or Oracle question matched; only filled in where
+    /// results link to or quote part of the rule
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub section: Option<RuleSection>,
+    /// Title and passage with the matched terms marked; only filled in for
+    /// search results
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub highlight: Option<SearchHighlight>,
 }
 
 impl Rule {
+    /// A rule whose ID is the slug of its title; callers that store it pick
+    /// a free ID with `db::available_rule_id`
     pub fn new(title: String, category: String, content: String, source: String) -> Self {
-        let now = chrono_now();
+        let now = now_utc();
         Self {
-            id: uuid::Uuid::new_v4().to_string(),
+            id: slugify(&title),
             title,
             category,
             subcategory: None,
             content,
             source,
+            source_id: None,
             page: None,
-            created_at: now.clone(),
+            created_at: now,
             updated_at: now,
+            collection_id: None,
+            tags: Vec::new(),
+            equipment: EquipmentSet::default(),
+            collection: None,
+            section: None,
+            highlight: None,
         }
     }
+
+    /// A Combat rule from the Player's Handbook 2024 for tests; other fields
+    /// are set with struct update syntax
+    #[cfg(test)]
+    pub(crate) fn test(id: &str, title: &str, content: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            ..Self::new(
+                title.to_string(),
+                "Combat".to_string(),
+                content.to_string(),
+                "Player's Handbook 2024".to_string(),
+            )
+        }
+    }
+
+    /// Whether the rule belongs to the Spells category and carries a spell block
+    pub fn is_spell(&self) -> bool {
+        self.category.eq_ignore_ascii_case("Spells")
+    }
+
+    /// Whether the rule belongs to the Equipment category and may list items
+    pub fn is_equipment(&self) -> bool {
+        self.category.eq_ignore_ascii_case("Equipment")
+    }
+
+    /// Whether the rule belongs to a homebrew collection rather than official content
+    pub fn is_homebrew(&self) -> bool {
+        self.collection_id.is_some()
+    }
+
+    /// Whether the rule was edited after it was first added
+    pub fn was_updated(&self) -> bool {
+        self.updated_at > self.created_at
+    }
+
+    /// Tag names as a comma-separated list, as edited in the admin form
+    pub fn tag_list(&self) -> String {
+        self.tags
+            .iter()
+            .map(|tag| tag.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Plain-text start of the matched section, or of the rule when no
+    /// section was matched
     pub fn excerpt(&self, max_len: usize) -> String {
-        let parser = Parser::new(&self.content);
+        let markdown = self.section.as_ref().map_or(&self.content, |section| &section.content);
+        let parser = Parser::new(markdown);
         let mut text_elements = Vec::new();
         
         for event in parser {
@@ -57,11 +145,25 @@ impl Rule {
     }
 }
 
-fn chrono_now() -> String {
-    // Simple ISO 8601 timestamp without chrono dependency
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    format!("{}", duration.as_secs())
+/// A rule in the trash, as listed on the admin Trash page
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TrashedRule {
+    pub id: String,
+    pub title: String,
+    pub category: String,
+    pub source: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl TrashedRule {
+    /// When automatic purging removes the rule, or None when it is kept forever
+    pub fn purge_due(&self, retention_days: u32) -> Option<DateTime<Utc>> {
+        (retention_days > 0).then(|| self.deleted_at + chrono::Duration::days(retention_days.into()))
+    }
+}
+
+/// Current time in UTC at whole-second precision, the resolution every
+/// stored rule timestamp uses
+pub fn now_utc() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(0)
 }
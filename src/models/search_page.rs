@@ -0,0 +1,145 @@
+use serde::Serialize;
+
+use super::{Rule, RuleFilter};
+
+/// One value of a facet and how many hits carry it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: usize,
+    /// Query qualifier narrowing the search to this value, e.g.
+    /// `category:"Combat"`
+    pub qualifier: String,
+    /// Whether the search is already narrowed to this value
+    pub active: bool,
+}
+
+/// Hit counts of a whole result set by category, subcategory and source,
+/// most common first
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SearchFacets {
+    pub categories: Vec<FacetCount>,
+    pub subcategories: Vec<FacetCount>,
+    /// By source abbreviation; rules from unknown sources are not counted
+    pub sources: Vec<FacetCount>,
+}
+
+impl SearchFacets {
+    pub fn count(rules: &[Rule], filter: &RuleFilter) -> Self {
+        Self {
+            categories: facet(rules.iter().map(|rule| Some(rule.category.as_str())), "category", |value| {
+                filter.categories.contains(&value.to_lowercase())
+            }),
+            subcategories: facet(rules.iter().map(|rule| rule.subcategory.as_deref()), "subcategory", |value| {
+                filter.subcategories.contains(&value.to_lowercase())
+            }),
+            sources: facet(rules.iter().map(|rule| rule.source_id.as_deref()), "source", |value| {
+                filter.sources.iter().any(|source| source == value)
+            }),
+        }
+    }
+}
+
+fn facet<'a>(
+    values: impl Iterator<Item = Option<&'a str>>,
+    field: &str,
+    is_active: impl Fn(&str) -> bool,
+) -> Vec<FacetCount> {
+    let mut counts: Vec<FacetCount> = Vec::new();
+    for value in values.flatten().filter(|value| !value.trim().is_empty()) {
+        match counts.iter_mut().find(|facet| facet.value == value) {
+            Some(facet) => facet.count += 1,
+            None => counts.push(FacetCount {
+                value: value.to_string(),
+                count: 1,
+                qualifier: format!("{}:\"{}\"", field, value.replace('"', "")),
+                active: is_active(value),
+            }),
+        }
+    }
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    counts
+}
+
+/// One page of search hits, with the total and the facets of all of them
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    pub rules: Vec<Rule>,
+    pub total: usize,
+    /// 1-based
+    pub page: usize,
+    pub per_page: usize,
+    pub facets: SearchFacets,
+}
+
+impl SearchPage {
+    /// Cut page `page` (1-based; 0 counts as 1) out of every hit
+    pub fn new(hits: Vec<Rule>, page: usize, per_page: usize, filter: &RuleFilter) -> Self {
+        let page = page.max(1);
+        let per_page = per_page.max(1);
+        let facets = SearchFacets::count(&hits, filter);
+        let total = hits.len();
+        let rules = hits.into_iter().skip((page - 1) * per_page).take(per_page).collect();
+
+        Self {
+            rules,
+            total,
+            page,
+            per_page,
+            facets,
+        }
+    }
+
+    /// The next page number, if there are hits after this page
+    pub fn next_page(&self) -> Option<usize> {
+        (self.page * self.per_page < self.total).then_some(self.page + 1)
+    }
+
+    /// Number of hits on earlier pages
+    pub fn offset(&self) -> usize {
+        (self.page - 1) * self.per_page
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, category: &str, subcategory: Option<&str>, source_id: Option<&str>) -> Rule {
+        Rule {
+            category: category.to_string(),
+            subcategory: subcategory.map(str::to_string),
+            source_id: source_id.map(str::to_string),
+            ..Rule::test(id, id, "")
+        }
+    }
+
+    #[test]
+    fn pages_and_facets_cover_every_hit() {
+        let hits = vec![
+            rule("a", "Combat", Some("Actions"), Some("PHB")),
+            rule("b", "Conditions", None, Some("PHB")),
+            rule("c", "Combat", Some("Actions"), Some("DMG")),
+            rule("d", "Combat", None, None),
+            rule("e", "Spells", Some(" "), Some("PHB")),
+        ];
+        let filter = RuleFilter {
+            categories: vec!["combat".to_string()],
+            ..RuleFilter::default()
+        };
+
+        let page = SearchPage::new(hits, 2, 2, &filter);
+
+        assert_eq!(page.rules.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["c", "d"]);
+        assert_eq!((page.total, page.offset(), page.next_page()), (5, 2, Some(3)));
+
+        let combat = &page.facets.categories[0];
+        assert_eq!((combat.value.as_str(), combat.count, combat.active), ("Combat", 3, true));
+        assert_eq!(combat.qualifier, "category:\"Combat\"");
+        assert_eq!(page.facets.subcategories.len(), 1);
+        assert_eq!(page.facets.sources.iter().map(|f| (f.value.as_str(), f.count)).collect::<Vec<_>>(), vec![("PHB", 3), ("DMG", 1)]);
+
+        let last = SearchPage::new(vec![rule("a", "Combat", None, None)], 0, 20, &filter);
+        assert_eq!((last.page, last.next_page()), (1, None));
+    }
+}
@@ -0,0 +1,248 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::{normalize_phrase, slugify};
+
+/// Part of a rule's Markdown that `/rules/:id#anchor` can link to: the text
+/// under a heading or under a bold or italic label paragraph such as
+/// "**Combat Effects:**".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleSection {
+    /// Heading or label text, e.g. "Combat Effects"; None for the text before
+    /// the first heading
+    pub heading: Option<String>,
+    /// Element ID on the rule page, unique within the rule; None for the text
+    /// before the first heading
+    pub anchor: Option<String>,
+    /// Markdown of the section, heading included
+    pub content: String,
+}
+
+/// Where a section begins in the Markdown
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SectionStart {
+    pub offset: usize,
+    pub heading: String,
+    pub anchor: String,
+}
+
+/// A top-level Markdown block as seen by the sectioning rules
+enum Block {
+    Heading(String),
+    Label(String),
+    Other,
+}
+
+/// Split Markdown into sections. Every heading starts a section, and so does
+/// a top-level paragraph or paragraph line opening with a bold or italic
+/// label ending in a colon, except in a run of such paragraphs a rule opens with before its
+/// text (a spell's "**Range:** 60 feet" block is one piece of text, not five
+/// sections).
+pub fn rule_sections(markdown: &str) -> Vec<RuleSection> {
+    let starts = section_starts(markdown);
+    let mut sections = Vec::new();
+
+    let first = starts.first().map_or(markdown.len(), |start| start.offset);
+    if !markdown[..first].trim().is_empty() {
+        sections.push(RuleSection {
+            heading: None,
+            anchor: None,
+            content: markdown[..first].trim().to_string(),
+        });
+    }
+
+    for (index, start) in starts.iter().enumerate() {
+        let end = starts.get(index + 1).map_or(markdown.len(), |next| next.offset);
+        sections.push(RuleSection {
+            heading: Some(start.heading.clone()),
+            anchor: Some(start.anchor.clone()),
+            content: markdown[start.offset..end].trim().to_string(),
+        });
+    }
+
+    sections
+}
+
+/// The section of `markdown` that best matches a search or question: the one
+/// mentioning the most query words, with words in its heading counting
+/// extra. Ties go to the earlier section; None when nothing matches.
+pub fn matching_section(markdown: &str, query: &str) -> Option<RuleSection> {
+    let words: HashSet<String> = normalize_phrase(query)
+        .split(' ')
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_string)
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut best: Option<(usize, RuleSection)> = None;
+    for section in rule_sections(markdown) {
+        let body: HashSet<String> = normalize_phrase(&section.content).split(' ').map(str::to_string).collect();
+        let heading: HashSet<String> = section
+            .heading
+            .as_deref()
+            .map(normalize_phrase)
+            .unwrap_or_default()
+            .split(' ')
+            .map(str::to_string)
+            .collect();
+        let score: usize = words
+            .iter()
+            .map(|word| usize::from(body.contains(word)) + 2 * usize::from(heading.contains(word)))
+            .sum();
+
+        if score > 0 && best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+            best = Some((score, section));
+        }
+    }
+
+    best.map(|(_, section)| section)
+}
+
+/// Byte offsets where sections begin, with their headings and anchors
+pub(crate) fn section_starts(markdown: &str) -> Vec<SectionStart> {
+    let events: Vec<(Event, std::ops::Range<usize>)> = Parser::new(markdown).into_offset_iter().collect();
+    let mut blocks: Vec<(usize, Block)> = Vec::new();
+    let mut depth = 0usize;
+
+    let mut index = 0;
+    while index < events.len() {
+        let (event, range) = &events[index];
+        match event {
+            Event::Start(Tag::Heading { .. }) if depth == 0 => {
+                let (text, end) = collect_text(&events, index + 1, |event| matches!(event, Event::End(TagEnd::Heading(_))));
+                blocks.push((range.start, Block::Heading(text)));
+                index = end;
+            }
+            Event::Start(Tag::Paragraph) if depth == 0 => {
+                let block = label_at(&events, index + 1).map_or(Block::Other, Block::Label);
+                blocks.push((range.start, block));
+                depth += 1;
+            }
+            // "**Topple:** ..." lines of one paragraph each start a section too
+            Event::SoftBreak | Event::HardBreak if depth == 1 => {
+                if let (Some(label), Some((_, line))) = (label_at(&events, index + 1), events.get(index + 1)) {
+                    blocks.push((line.start, Block::Label(label)));
+                }
+            }
+            Event::Start(_) => {
+                if depth == 0 {
+                    blocks.push((range.start, Block::Other));
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth = depth.saturating_sub(1),
+            Event::Rule if depth == 0 => blocks.push((range.start, Block::Other)),
+            _ => {}
+        }
+        index += 1;
+    }
+
+    // Label paragraphs followed by other text form a stat block; a rule made
+    // only of labels (weapon masteries) is a list of sections
+    let leading_labels = blocks.iter().take_while(|(_, block)| matches!(block, Block::Label(_))).count();
+    let stat_block = if leading_labels < blocks.len() { leading_labels } else { 0 };
+    let mut used: HashMap<String, usize> = HashMap::new();
+    blocks
+        .into_iter()
+        .enumerate()
+        .filter_map(|(position, (offset, block))| match block {
+            Block::Heading(heading) => Some((offset, heading)),
+            Block::Label(label) if position >= stat_block => Some((offset, label)),
+            _ => None,
+        })
+        .map(|(offset, heading)| {
+            let base = match slugify(&heading) {
+                slug if slug.is_empty() => "section".to_string(),
+                slug => slug,
+            };
+            let count = used.entry(base.clone()).or_insert(0);
+            *count += 1;
+            let anchor = if *count == 1 { base } else { format!("{}-{}", base, count) };
+            SectionStart { offset, heading, anchor }
+        })
+        .collect()
+}
+
+/// The label of a bold or italic element at `index` ending in a colon
+/// ("**Recovery:**" or "**Recovery**:"), without the colon
+fn label_at(events: &[(Event, std::ops::Range<usize>)], index: usize) -> Option<String> {
+    if !matches!(events.get(index), Some((Event::Start(Tag::Strong | Tag::Emphasis), _))) {
+        return None;
+    }
+    let (text, end) = collect_text(events, index + 1, |event| {
+        matches!(event, Event::End(TagEnd::Strong | TagEnd::Emphasis))
+    });
+    let colon_after = matches!(events.get(end + 1), Some((Event::Text(after), _)) if after.starts_with(':'));
+
+    let label = match text.trim().strip_suffix(':') {
+        Some(label) => label.trim(),
+        None if colon_after => text.trim(),
+        None => return None,
+    };
+    (!label.is_empty()).then(|| label.to_string())
+}
+
+/// Text inside an element starting at `from`, up to the event `is_end`
+/// matches; returns the text and the index of that end event
+fn collect_text(
+    events: &[(Event, std::ops::Range<usize>)],
+    from: usize,
+    is_end: impl Fn(&Event) -> bool,
+) -> (String, usize) {
+    let mut text = String::new();
+    let mut index = from;
+    while index < events.len() && !is_end(&events[index].0) {
+        match &events[index].0 {
+            Event::Text(part) | Event::Code(part) => text.push_str(part),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+        index += 1;
+    }
+    (text, index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXHAUSTION: &str = "Exhaustion is measured in six levels.\n\n**Level Effects:**\n- **Level 1:** Disadvantage on checks\n- **Level 6:** Death\n\n**Recovery:** A long rest reduces exhaustion by 1 level.\n\n## Recovery\n\nMore.";
+
+    #[test]
+    fn splits_at_headings_and_label_paragraphs() {
+        let sections = rule_sections(EXHAUSTION);
+        let anchors: Vec<_> = sections.iter().map(|s| s.anchor.as_deref()).collect();
+
+        assert_eq!(anchors, vec![None, Some("level-effects"), Some("recovery"), Some("recovery-2")]);
+        assert_eq!(sections[0].content, "Exhaustion is measured in six levels.");
+        assert!(sections[1].content.ends_with("- **Level 6:** Death"));
+        assert_eq!(sections[2].heading.as_deref(), Some("Recovery"));
+    }
+
+    #[test]
+    fn leading_label_block_stays_in_the_intro() {
+        let spell = "**Range:** 60 feet\n\n**Duration:** Instantaneous\n\nYou hurl acid.\n\n*Cantrip Upgrade:* More damage.";
+
+        let sections = rule_sections(spell);
+
+        assert_eq!(sections.len(), 2);
+        assert!(sections[0].content.ends_with("You hurl acid."));
+        assert_eq!(sections[1].anchor.as_deref(), Some("cantrip-upgrade"));
+        assert_eq!(sections[1].content, "*Cantrip Upgrade:* More damage.");
+
+        let masteries = rule_sections("**Sap:** Disadvantage.\n**Topple:** Prone.");
+        assert_eq!(masteries[1].anchor.as_deref(), Some("topple"));
+    }
+
+    #[test]
+    fn matching_section_prefers_heading_words() {
+        let section = matching_section(EXHAUSTION, "how does exhaustion recovery work").unwrap();
+        assert_eq!(section.anchor.as_deref(), Some("recovery"));
+
+        assert_eq!(matching_section(EXHAUSTION, "death").unwrap().anchor.as_deref(), Some("level-effects"));
+        assert!(matching_section(EXHAUSTION, "grapple").is_none());
+    }
+}
@@ -0,0 +1,24 @@
+/// Lowercase, hyphen-separated form of `text` used for rule IDs and tag slugs,
+/// e.g. "Mounted Combat" becomes "mounted-combat"
+pub fn slugify(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses_punctuation_into_single_hyphens() {
+        assert_eq!(slugify("Mounted Combat"), "mounted-combat");
+        assert_eq!(slugify("  Attack (Action) -- 2024 "), "attack-action-2024");
+        assert_eq!(slugify("!!"), "");
+    }
+}
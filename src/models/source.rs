@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// A published book rules can come from, e.g. the 2024 Player's Handbook
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Source {
+    pub abbreviation: String,
+    pub name: String,
+    pub edition: String,
+    pub published_year: Option<i32>,
+    pub display_order: i32,
+}
@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+
+use super::{slugify, Rule};
+
+/// Structured view of a spell rule, parsed from the bold `**Field:**` lines
+/// of its content and the "Level 1 Evocation" / "Evocation Cantrip" subcategory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Spell {
+    pub rule_id: String,
+    pub name: String,
+    /// 0 for cantrips
+    pub level: i32,
+    pub school: String,
+    pub casting_time: String,
+    /// Unit of the casting time as a slug: action, bonus-action, reaction, minute, hour
+    pub casting_time_kind: String,
+    pub range: String,
+    pub components: String,
+    pub duration: String,
+    pub concentration: bool,
+    pub ritual: bool,
+    /// Classes from `spell_classes`
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub classes: Vec<String>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum SpellParseError {
+    #[error("missing **{0}:** line")]
+    MissingField(&'static str),
+
+    #[error("cannot read level and school from '{0}'")]
+    InvalidLevel(String),
+}
+
+impl Spell {
+    /// Parse the spell details out of a rule in the Spells category
+    pub fn from_rule(rule: &Rule) -> Result<Self, SpellParseError> {
+        let field = |name: &'static str| {
+            bold_field(&rule.content, name).ok_or(SpellParseError::MissingField(name))
+        };
+
+        let (level, school, ritual_tag) = match (bold_field(&rule.content, "Level"), bold_field(&rule.content, "School")) {
+            (Some(level), Some(school)) => {
+                let level = parse_level(&level).ok_or(SpellParseError::InvalidLevel(level))?;
+                (level, school, false)
+            }
+            _ => {
+                let subcategory = rule.subcategory.clone().unwrap_or_default();
+                parse_level_and_school(&subcategory).ok_or(SpellParseError::InvalidLevel(subcategory))?
+            }
+        };
+
+        let casting_time = field("Casting Time")?;
+        let duration = field("Duration")?;
+        let classes = field("Classes")?
+            .split(',')
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect();
+
+        Ok(Self {
+            rule_id: rule.id.clone(),
+            name: rule.title.clone(),
+            level,
+            school,
+            casting_time_kind: casting_time_kind(&casting_time),
+            ritual: ritual_tag || casting_time.to_lowercase().contains("ritual"),
+            concentration: duration.to_lowercase().starts_with("concentration"),
+            casting_time,
+            range: field("Range")?,
+            components: field("Components")?,
+            duration,
+            classes,
+        })
+    }
+
+    /// "Cantrip", "1st level", "2nd level", ...
+    pub fn level_label(&self) -> String {
+        level_label(self.level)
+    }
+}
+
+pub fn level_label(level: i32) -> String {
+    let suffix = match level {
+        0 => return "Cantrip".to_string(),
+        1 => "st",
+        2 => "nd",
+        3 => "rd",
+        _ => "th",
+    };
+    format!("{}{} level", level, suffix)
+}
+
+/// Filters for `/spells` and `/api/spells`; every `None` matches all spells
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpellFilter {
+    pub class: Option<String>,
+    pub level: Option<i32>,
+    pub school: Option<String>,
+    pub ritual: Option<bool>,
+    pub concentration: Option<bool>,
+    /// Casting time kind slug, e.g. bonus-action
+    pub casting_time: Option<String>,
+    /// Admins also see spells from private homebrew collections
+    pub include_private_homebrew: bool,
+}
+
+impl SpellFilter {
+    /// Build a filter from raw query parameters; blank and unparseable
+    /// values are ignored so empty form selects mean "any"
+    pub fn from_params(
+        class: Option<&str>,
+        level: Option<&str>,
+        school: Option<&str>,
+        ritual: Option<&str>,
+        concentration: Option<&str>,
+        casting_time: Option<&str>,
+    ) -> Self {
+        let text = |value: Option<&str>| value.map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+
+        Self {
+            class: text(class),
+            level: level.and_then(parse_level),
+            school: text(school),
+            ritual: ritual.and_then(parse_flag),
+            concentration: concentration.and_then(parse_flag),
+            casting_time: text(casting_time).map(|c| slugify(&c)),
+            include_private_homebrew: false,
+        }
+    }
+}
+
+/// Value of a `**Name:** value` line in spell content
+fn bold_field(content: &str, name: &str) -> Option<String> {
+    let marker = format!("**{}:**", name);
+    content
+        .lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix(&marker))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Level, school and ritual flag from "Level 1 Divination (Ritual)" or "Evocation Cantrip"
+fn parse_level_and_school(subcategory: &str) -> Option<(i32, String, bool)> {
+    let ritual = subcategory.to_lowercase().contains("(ritual)");
+    let text = subcategory
+        .split('(')
+        .next()
+        .unwrap_or_default()
+        .trim();
+
+    if let Some(school) = text.strip_suffix(" Cantrip") {
+        return Some((0, school.trim().to_string(), ritual));
+    }
+
+    let rest = text.strip_prefix("Level ")?;
+    let (level, school) = rest.split_once(' ')?;
+    Some((level.parse().ok()?, school.trim().to_string(), ritual))
+}
+
+/// "0", "cantrip", "3", "3rd" → level number
+fn parse_level(value: &str) -> Option<i32> {
+    let value = value.trim().to_lowercase();
+    if value == "cantrip" {
+        return Some(0);
+    }
+
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok().filter(|level| (0..=9).contains(level))
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Slug of the casting time unit: "1 Bonus Action" → bonus-action,
+/// "10 minutes or Ritual" → minute
+fn casting_time_kind(casting_time: &str) -> String {
+    let main = casting_time
+        .split([',', '('])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let main = main.split(" or ").next().unwrap_or_default();
+
+    let unit = main
+        .trim()
+        .trim_start_matches(|c: char| c.is_ascii_digit() || c.is_whitespace())
+        .trim_end_matches('s');
+
+    slugify(unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spell_rule(subcategory: &str, content: &str) -> Rule {
+        Rule {
+            category: "Spells".to_string(),
+            subcategory: Some(subcategory.to_string()),
+            ..Rule::test("spell-detect-magic", "Detect Magic", content)
+        }
+    }
+
+    const DETECT_MAGIC: &str = "**Classes:** Bard, Cleric, Wizard\n\n**Casting Time:** 1 Action or Ritual\n\n**Range:** Self\n\n**Components:** V, S\n\n**Duration:** Concentration, up to 10 minutes\n\n\nYou sense magic.";
+
+    #[test]
+    fn parses_fields_from_content_and_subcategory() {
+        let spell = Spell::from_rule(&spell_rule("Level 1 Divination (Ritual)", DETECT_MAGIC)).unwrap();
+
+        assert_eq!(spell.level, 1);
+        assert_eq!(spell.school, "Divination");
+        assert_eq!(spell.classes, vec!["Bard", "Cleric", "Wizard"]);
+        assert_eq!(spell.casting_time, "1 Action or Ritual");
+        assert_eq!(spell.casting_time_kind, "action");
+        assert_eq!(spell.range, "Self");
+        assert!(spell.ritual);
+        assert!(spell.concentration);
+    }
+
+    #[test]
+    fn parses_cantrips_and_level_lines() {
+        let cantrip = Spell::from_rule(&spell_rule("Evocation Cantrip", DETECT_MAGIC)).unwrap();
+        assert_eq!((cantrip.level, cantrip.school.as_str()), (0, "Evocation"));
+
+        let content = format!("**Level:** 3rd\n**School:** Abjuration\n{}", DETECT_MAGIC);
+        let leveled = Spell::from_rule(&spell_rule("Misc", &content)).unwrap();
+        assert_eq!((leveled.level, leveled.school.as_str()), (3, "Abjuration"));
+    }
+
+    #[test]
+    fn reports_missing_fields() {
+        assert_eq!(
+            Spell::from_rule(&spell_rule("Level 1 Divination", "**Classes:** Wizard")),
+            Err(SpellParseError::MissingField("Casting Time"))
+        );
+        assert!(matches!(
+            Spell::from_rule(&spell_rule("Divination", DETECT_MAGIC)),
+            Err(SpellParseError::InvalidLevel(_))
+        ));
+    }
+
+    #[test]
+    fn casting_time_kinds() {
+        assert_eq!(casting_time_kind("1 Bonus Action"), "bonus-action");
+        assert_eq!(casting_time_kind("1 Reaction, which you take when you are hit"), "reaction");
+        assert_eq!(casting_time_kind("10 minutes"), "minute");
+        assert_eq!(casting_time_kind("1 hour or Ritual"), "hour");
+    }
+
+    #[test]
+    fn filter_ignores_blank_params() {
+        let filter = SpellFilter::from_params(Some("Wizard"), Some("1"), Some(""), Some("true"), None, Some("Bonus Action"));
+
+        assert_eq!(filter.class.as_deref(), Some("Wizard"));
+        assert_eq!(filter.level, Some(1));
+        assert_eq!(filter.school, None);
+        assert_eq!(filter.ritual, Some(true));
+        assert_eq!(filter.casting_time.as_deref(), Some("bonus-action"));
+    }
+}
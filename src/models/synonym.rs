@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+/// A search synonym: queries containing `term` are also searched for
+/// `expansion`, e.g. "oa" for "opportunity attack"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Synonym {
+    /// Normalized with [`normalize_phrase`]
+    pub term: String,
+    pub expansion: String,
+}
+
+impl Synonym {
+    /// Synonym with both sides normalized, or None when either is blank or
+    /// they are the same
+    pub fn new(term: &str, expansion: &str) -> Option<Self> {
+        let term = normalize_phrase(term);
+        let expansion = normalize_phrase(expansion);
+        if term.is_empty() || expansion.is_empty() || term == expansion {
+            return None;
+        }
+
+        Some(Self { term, expansion })
+    }
+}
+
+/// Lowercase words separated by single spaces, punctuation dropped:
+/// "Armor-Class " becomes "armor class"
+pub fn normalize_phrase(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synonyms_are_normalized_and_must_differ() {
+        assert_eq!(
+            Synonym::new(" AoO", "Opportunity-Attack"),
+            Some(Synonym {
+                term: "aoo".to_string(),
+                expansion: "opportunity attack".to_string(),
+            })
+        );
+        assert_eq!(Synonym::new("AC", "ac"), None);
+        assert_eq!(Synonym::new("??", "armor class"), None);
+    }
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+use super::slugify;
+
+/// A theme a rule belongs to, e.g. "Reaction" or "Mounted Combat".
+/// Rules and tags are linked many-to-many through `rule_tags`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Tag {
+    pub slug: String,
+    pub name: String,
+}
+
+impl Tag {
+    /// Tag named `name`, or None when the name has no usable characters
+    pub fn new(name: &str) -> Option<Self> {
+        let name = name.trim();
+        let slug = slugify(name);
+        if slug.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            slug,
+            name: name.to_string(),
+        })
+    }
+}
+
+/// A tag together with how many rules carry it
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TagSummary {
+    pub slug: String,
+    pub name: String,
+    pub rule_count: i64,
+}
+
+/// Parse a comma-separated tag list such as "Reaction, movement",
+/// dropping blanks and duplicates
+pub fn parse_tag_list(text: &str) -> Vec<Tag> {
+    let mut tags: Vec<Tag> = Vec::new();
+
+    for tag in text.split(',').filter_map(Tag::new) {
+        if !tags.iter().any(|t| t.slug == tag.slug) {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_list_is_slugged_and_deduplicated() {
+        let tags = parse_tag_list("Reaction, mounted combat,, reaction , Mounted-Combat");
+
+        assert_eq!(
+            tags,
+            vec![
+                Tag { slug: "reaction".to_string(), name: "Reaction".to_string() },
+                Tag { slug: "mounted-combat".to_string(), name: "mounted combat".to_string() },
+            ]
+        );
+    }
+}
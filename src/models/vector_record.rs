@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+
+/// The embedding the vector index holds for a rule, so reindexing can skip
+/// rules whose text, model and index are unchanged
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct VectorRecord {
+    pub rule_id: String,
+    /// Hash of the embedded text (see `search::indexer::content_hash`)
+    pub content_hash: String,
+    /// e.g. "text-embedding-3-small"
+    pub embedding_model: String,
+    /// e.g. "qdrant:rulecraft_rules_openai_small_v1" or "sqlite"
+    pub vector_index: String,
+}
+
+/// A rule whose vector failed to sync after a write and awaits a retry
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct QueuedVectorSync {
+    pub rule_id: String,
+    pub attempts: i64,
+    pub last_error: String,
+    pub queued_at: DateTime<Utc>,
+}
@@ -1,16 +1,22 @@
 use axum::{
     extract::{Path, State},
     http::{HeaderMap, StatusCode},
-    response::{Html, IntoResponse, Redirect},
+    response::{AppendHeaders, Html, IntoResponse, Redirect},
     routing::{get, post},
     Form,
     Router,
 };
 use askama::Template;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 use super::AppState;
-use crate::models::Rule;
+use crate::models::{
+    diff_lines, field_changes, is_valid_errata_date, now_utc, parse_tag_list, slugify, DiffLine, EquipmentSet,
+    Erratum, FieldChange, HomebrewCollection, Rule, RuleFilter, RuleRevision, Source, Spell, Synonym, TrashedRule,
+    Visibility,
+};
+use crate::search::vector_sync::{spawn_rule_sync, VectorIndexStatus};
 
 // ── Templates ──────────────────────────────────────────────
 
@@ -27,6 +33,8 @@ struct AdminDashboardTemplate {
     title: String,
     rules: Vec<Rule>,
     message: Option<String>,
+    /// None when vector search is disabled
+    vector_status: Option<VectorIndexStatus>,
 }
 
 #[derive(Template)]
@@ -36,6 +44,71 @@ struct AdminFormTemplate {
     is_edit: bool,
     rule: Option<Rule>,
     categories: Vec<String>,
+    sources: Vec<Source>,
+    collections: Vec<HomebrewCollection>,
+    error: Option<String>,
+    warning: Option<String>,
+    /// Former IDs of the rule being edited
+    aliases: Vec<String>,
+}
+
+impl AdminFormTemplate {
+    /// ID of the homebrew collection the rule belongs to, or "" for official content
+    fn collection_id(&self) -> &str {
+        self.rule
+            .as_ref()
+            .and_then(|rule| rule.collection_id.as_deref())
+            .unwrap_or("")
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/history.html")]
+struct AdminHistoryTemplate {
+    title: String,
+    rule: Rule,
+    changes: Vec<RevisionChange>,
+}
+
+/// One edit in a rule's history: the snapshot taken before the edit and
+/// how it differs from the version that replaced it.
+struct RevisionChange {
+    revision: RuleRevision,
+    fields: Vec<FieldChange>,
+    diff: Vec<DiffLine>,
+}
+
+#[derive(Template)]
+#[template(path = "admin/errata.html")]
+struct AdminErrataTemplate {
+    title: String,
+    rule: Rule,
+    errata: Vec<Erratum>,
+    error: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "admin/homebrew.html")]
+struct AdminHomebrewTemplate {
+    title: String,
+    collections: Vec<HomebrewCollection>,
+    visibilities: Vec<Visibility>,
+    error: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "admin/trash.html")]
+struct AdminTrashTemplate {
+    title: String,
+    rules: Vec<TrashedRule>,
+    retention_days: u32,
+}
+
+#[derive(Template)]
+#[template(path = "admin/synonyms.html")]
+struct AdminSynonymsTemplate {
+    title: String,
+    synonyms: Vec<Synonym>,
     error: Option<String>,
 }
 
@@ -50,6 +123,13 @@ struct AdminNotConfiguredTemplate {
 #[derive(Deserialize)]
 pub struct LoginForm {
     admin_key: String,
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct RenameForm {
+    new_id: String,
 }
 
 #[derive(Deserialize)]
@@ -61,6 +141,38 @@ pub struct RuleForm {
     source: String,
     page: Option<i32>,
     content: String,
+    /// Comma-separated tag names
+    #[serde(default)]
+    tags: String,
+    /// Homebrew collection ID; empty for official content
+    #[serde(default)]
+    collection_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct HomebrewForm {
+    /// Empty when creating a collection; the ID is then taken from the name
+    #[serde(default)]
+    id: String,
+    name: String,
+    owner: String,
+    visibility: String,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SynonymForm {
+    term: String,
+    /// One or more expansions, comma-separated
+    expansion: String,
+}
+
+#[derive(Deserialize)]
+pub struct ErratumForm {
+    published_on: String,
+    original_text: String,
+    corrected_text: String,
+    source_note: Option<String>,
 }
 
 // ── Auth Helper ────────────────────────────────────────────
@@ -77,7 +189,7 @@ fn get_cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
         })
 }
 
-fn is_authenticated(headers: &HeaderMap, admin_key: &Option<String>) -> bool {
+pub(crate) fn is_authenticated(headers: &HeaderMap, admin_key: &Option<String>) -> bool {
     match admin_key {
         Some(key) => {
             get_cookie_value(headers, "admin_token")
@@ -88,6 +200,21 @@ fn is_authenticated(headers: &HeaderMap, admin_key: &Option<String>) -> bool {
     }
 }
 
+/// Who to credit in revision history: the name given at login, or "admin"
+fn admin_actor(headers: &HeaderMap) -> String {
+    get_cookie_value(headers, "admin_name")
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "admin".to_string())
+}
+
+/// Keep login names cookie-safe and short
+fn sanitize_actor_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
+        .take(40)
+        .collect()
+}
+
 // ── Router ─────────────────────────────────────────────────
 
 pub fn router() -> Router<AppState> {
@@ -100,6 +227,20 @@ pub fn router() -> Router<AppState> {
         .route("/admin/rules/:id/edit", get(admin_edit_rule))
         .route("/admin/rules/:id/edit", post(admin_update_rule))
         .route("/admin/rules/:id/delete", post(admin_delete_rule))
+        .route("/admin/rules/:id/rename", post(admin_rename_rule))
+        .route("/admin/rules/:id/history", get(admin_rule_history))
+        .route("/admin/rules/:id/revisions/:revision_id/revert", post(admin_revert_rule))
+        .route("/admin/rules/:id/errata", get(admin_rule_errata))
+        .route("/admin/rules/:id/errata", post(admin_add_erratum))
+        .route("/admin/rules/:id/errata/:erratum_id/delete", post(admin_delete_erratum))
+        .route("/admin/homebrew", get(admin_homebrew))
+        .route("/admin/homebrew", post(admin_save_homebrew))
+        .route("/admin/synonyms", get(admin_synonyms))
+        .route("/admin/synonyms", post(admin_add_synonym))
+        .route("/admin/synonyms/delete", post(admin_delete_synonym))
+        .route("/admin/trash", get(admin_trash))
+        .route("/admin/trash/:id/restore", post(admin_restore_rule))
+        .route("/admin/trash/:id/purge", post(admin_purge_rule))
 }
 
 // ── Handlers ───────────────────────────────────────────────
@@ -126,11 +267,18 @@ async fn admin_index(
     }
 
     // Show dashboard
-    let rules = crate::db::get_all_rules(&state.db).await.unwrap_or_default();
+    let mut rules = state.rules.list(&RuleFilter::default().with_homebrew(None, true)).await.unwrap_or_default();
+    crate::db::attach_collections(&state.db, &mut rules).await.ok();
     let template = AdminDashboardTemplate {
         title: "Admin Dashboard".to_string(),
         rules,
         message: None,
+        vector_status: crate::search::vector_sync::vector_index_status(&state.db, &state.config.vector)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to check the vector index: {}", e);
+                None
+            }),
     };
     Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
 }
@@ -142,12 +290,17 @@ async fn admin_login(
     match &state.config.admin_api_key {
         Some(key) if form.admin_key == *key => {
             let cookie = format!("admin_token={}; Path=/; HttpOnly; SameSite=Strict", key);
+            let name_cookie = format!(
+                "admin_name={}; Path=/; HttpOnly; SameSite=Strict",
+                sanitize_actor_name(&form.name)
+            );
             (
                 StatusCode::SEE_OTHER,
-                [
+                AppendHeaders([
                     ("Location", "/admin"),
                     ("Set-Cookie", &cookie),
-                ],
+                    ("Set-Cookie", &name_cookie),
+                ]),
                 "",
             ).into_response()
         }
@@ -164,10 +317,11 @@ async fn admin_login(
 async fn admin_logout() -> impl IntoResponse {
     (
         StatusCode::SEE_OTHER,
-        [
+        AppendHeaders([
             ("Location", "/admin"),
             ("Set-Cookie", "admin_token=; Path=/; HttpOnly; Max-Age=0"),
-        ],
+            ("Set-Cookie", "admin_name=; Path=/; HttpOnly; Max-Age=0"),
+        ]),
         "",
     )
 }
@@ -186,7 +340,11 @@ async fn admin_new_rule(
         is_edit: false,
         rule: None,
         categories,
+        sources: get_sources(&state).await,
+        collections: get_collections(&state).await,
         error: None,
+        warning: None,
+        aliases: Vec::new(),
     };
     Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
 }
@@ -208,16 +366,57 @@ async fn admin_create_rule(
             is_edit: false,
             rule: Some(form_to_rule(&form)),
             categories,
+            sources: get_sources(&state).await,
+            collections: get_collections(&state).await,
             error: Some("Title, Category, Content, and Source are required.".to_string()),
+            warning: None,
+            aliases: Vec::new(),
         };
         return Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response();
     }
 
-    let rule = form_to_rule(&form);
-    match crate::db::upsert_rule(&state.db, &rule).await {
-        Ok(_) => {
+    let mut rule = form_to_rule(&form);
+    let taken = if form.id.trim().is_empty() {
+        // Auto-generated IDs step aside for existing ones: "grapple-2"
+        match crate::db::available_rule_id(&state.db, &rule.title).await {
+            Ok(id) => {
+                rule.id = id;
+                None
+            }
+            Err(e) => Some(format!("Database error: {}", e)),
+        }
+    } else if rule.id.is_empty() {
+        Some("The ID must contain letters or digits.".to_string())
+    } else {
+        match crate::db::rule_id_in_use(&state.db, &rule.id).await {
+            Ok(false) => None,
+            Ok(true) => Some(format!("The ID '{}' is already in use by another rule or a former rule ID.", rule.id)),
+            Err(e) => Some(format!("Database error: {}", e)),
+        }
+    };
+    if let Some(error) = taken {
+        let template = AdminFormTemplate {
+            title: "Add New Rule".to_string(),
+            is_edit: false,
+            rule: Some(rule),
+            categories: get_categories(&state).await,
+            sources: get_sources(&state).await,
+            collections: get_collections(&state).await,
+            error: Some(error),
+            warning: None,
+            aliases: Vec::new(),
+        };
+        return Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response();
+    }
+
+    match save_rule(&state, &rule, &admin_actor(&headers)).await {
+        Ok(warnings) => {
             tracing::info!("Admin created rule: {} ({})", rule.title, rule.id);
-            Redirect::to("/admin").into_response()
+            if warnings.is_empty() {
+                Redirect::to("/admin").into_response()
+            } else {
+                render_save_warnings(&state, &rule.id, &warnings).await
+            }
         }
         Err(e) => {
             tracing::error!("Error creating rule: {}", e);
@@ -227,7 +426,11 @@ async fn admin_create_rule(
                 is_edit: false,
                 rule: Some(rule),
                 categories,
+                sources: get_sources(&state).await,
+                collections: get_collections(&state).await,
                 error: Some(format!("Database error: {}", e)),
+                warning: None,
+                aliases: Vec::new(),
             };
             Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
         }
@@ -243,22 +446,7 @@ async fn admin_edit_rule(
         return Redirect::to("/admin").into_response();
     }
 
-    match crate::db::get_rule_by_id(&state.db, &id).await {
-        Ok(Some(rule)) => {
-            let categories = get_categories(&state).await;
-            let template = AdminFormTemplate {
-                title: format!("Edit: {}", rule.title),
-                is_edit: true,
-                rule: Some(rule),
-                categories,
-                error: None,
-            };
-            Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
-        }
-        _ => {
-            Redirect::to("/admin").into_response()
-        }
-    }
+    render_edit_form(&state, &id, None, None).await
 }
 
 async fn admin_update_rule(
@@ -271,6 +459,18 @@ async fn admin_update_rule(
         return Redirect::to("/admin").into_response();
     }
 
+    // A former ID edits the rule it now points at; this route never creates a rule
+    let id = match state.rules.get(&id).await {
+        Ok(Some(_)) => id,
+        _ => match crate::db::resolve_rule_alias(&state.db, &id).await {
+            Ok(Some(canonical)) => canonical,
+            _ => {
+                tracing::warn!("Refusing to update missing rule {}", id);
+                return super::not_found("Rule Not Found", "There is no rule with that ID.");
+            }
+        },
+    };
+
     if form.title.trim().is_empty() || form.category.trim().is_empty() || form.content.trim().is_empty() || form.source.trim().is_empty() {
         let categories = get_categories(&state).await;
         let template = AdminFormTemplate {
@@ -278,18 +478,26 @@ async fn admin_update_rule(
             is_edit: true,
             rule: Some(form_to_rule(&form)),
             categories,
+            sources: get_sources(&state).await,
+            collections: get_collections(&state).await,
             error: Some("Title, Category, Content, and Source are required.".to_string()),
+            warning: None,
+            aliases: Vec::new(),
         };
         return Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response();
     }
 
     let mut rule = form_to_rule(&form);
-    rule.id = id; // Preserve original ID
+    rule.id = id;
 
-    match crate::db::upsert_rule(&state.db, &rule).await {
-        Ok(_) => {
+    match save_rule(&state, &rule, &admin_actor(&headers)).await {
+        Ok(warnings) => {
             tracing::info!("Admin updated rule: {} ({})", rule.title, rule.id);
-            Redirect::to("/admin").into_response()
+            if warnings.is_empty() {
+                Redirect::to("/admin").into_response()
+            } else {
+                render_save_warnings(&state, &rule.id, &warnings).await
+            }
         }
         Err(e) => {
             tracing::error!("Error updating rule: {}", e);
@@ -299,7 +507,11 @@ async fn admin_update_rule(
                 is_edit: true,
                 rule: Some(rule),
                 categories,
+                sources: get_sources(&state).await,
+                collections: get_collections(&state).await,
                 error: Some(format!("Database error: {}", e)),
+                warning: None,
+                aliases: Vec::new(),
             };
             Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
         }
@@ -315,10 +527,15 @@ async fn admin_delete_rule(
         return Redirect::to("/admin").into_response();
     }
 
-    match crate::db::delete_rule(&state.db, &id).await {
-        Ok(_) => {
-            tracing::info!("Admin deleted rule: {}", id);
+    match state.rules.delete(&id).await {
+        Ok(true) => {
+            tracing::info!("Admin moved rule to the trash: {}", id);
+            spawn_rule_sync(&state.db, &state.rules, &state.config.vector, vec![id.clone()]);
+            if let Err(e) = crate::search::links::index_links_of_rule(&state.db, &id).await {
+                tracing::warn!("Failed to index rule links: {}", e);
+            }
         }
+        Ok(false) => tracing::warn!("Delete requested for unknown rule {}", id),
         Err(e) => {
             tracing::error!("Error deleting rule {}: {}", id, e);
         }
@@ -327,25 +544,580 @@ async fn admin_delete_rule(
     Redirect::to("/admin").into_response()
 }
 
+async fn admin_rename_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Form(form): Form<RenameForm>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    if !matches!(state.rules.get(&id).await, Ok(Some(_))) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    let new_id = slugify(&form.new_id);
+    if new_id == id {
+        return Redirect::to(&format!("/admin/rules/{}/edit", id)).into_response();
+    }
+    if new_id.is_empty() {
+        return render_edit_form(&state, &id, Some("The new ID must contain letters or digits.".to_string()), None).await;
+    }
+
+    // A former ID of this same rule may be taken back; anything else in use may not
+    let own_alias = matches!(
+        crate::db::resolve_rule_alias(&state.db, &new_id).await,
+        Ok(Some(rule_id)) if rule_id == id
+    );
+    match crate::db::rule_id_in_use(&state.db, &new_id).await {
+        Ok(true) if !own_alias => {
+            let error = format!("The ID '{}' is already in use by another rule or a former rule ID.", new_id);
+            return render_edit_form(&state, &id, Some(error), None).await;
+        }
+        Err(e) => return render_edit_form(&state, &id, Some(format!("Database error: {}", e)), None).await,
+        _ => {}
+    }
+
+    if let Err(e) = crate::db::rename_rule(&state.db, &id, &new_id).await {
+        tracing::error!("Error renaming rule {} to {}: {}", id, new_id, e);
+        return render_edit_form(&state, &id, Some(format!("Database error: {}", e)), None).await;
+    }
+    tracing::info!("Admin renamed rule {} to {}", id, new_id);
+
+    let mut synced = vec![id.clone(), new_id.clone()];
+    synced.extend(retarget_links_to_renamed_rule(&state, &id, &new_id, &admin_actor(&headers)).await);
+
+    if let Err(e) = crate::search::links::index_links_of_rule(&state.db, &new_id).await {
+        tracing::warn!("Failed to index rule links: {}", e);
+    }
+    spawn_rule_sync(&state.db, &state.rules, &state.config.vector, synced);
+
+    Redirect::to(&format!("/admin/rules/{}/edit", new_id)).into_response()
+}
+
+/// Point `[[rule:old_id]]` links in other rules at `new_id`, saving each
+/// rule as an edit by `changed_by` so it gets a revision and a feed entry.
+/// Returns the IDs of the rules rewritten.
+async fn retarget_links_to_renamed_rule(state: &AppState, old_id: &str, new_id: &str, changed_by: &str) -> Vec<String> {
+    let linking = match crate::db::get_rules_linking_to(&state.db, old_id).await {
+        Ok(rules) => rules,
+        Err(e) => {
+            tracing::warn!("Failed to find links to renamed rule {}: {}", old_id, e);
+            return Vec::new();
+        }
+    };
+    let renamed = HashMap::from([(old_id.to_string(), new_id.to_string())]);
+
+    let mut rewritten = Vec::new();
+    for mut rule in linking {
+        let content = crate::search::links::retarget_explicit_links(&rule.content, &renamed);
+        if content == rule.content {
+            continue;
+        }
+        rule.content = content;
+        match state.rules.upsert(&rule, changed_by).await {
+            Ok(_) => rewritten.push(rule.id),
+            Err(e) => tracing::warn!("Failed to retarget links in rule {}: {}", rule.id, e),
+        }
+    }
+    rewritten
+}
+
+async fn admin_synonyms(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    render_synonyms_page(&state, None).await
+}
+
+async fn admin_add_synonym(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<SynonymForm>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    let synonyms: Vec<Synonym> = form
+        .expansion
+        .split(',')
+        .filter_map(|expansion| Synonym::new(&form.term, expansion))
+        .collect();
+    if synonyms.is_empty() {
+        return render_synonyms_page(
+            &state,
+            Some("A term and at least one different expansion are required.".to_string()),
+        )
+        .await;
+    }
+
+    for synonym in &synonyms {
+        match crate::db::add_synonym(&state.db, synonym).await {
+            Ok(_) => tracing::info!("Admin added synonym {} -> {}", synonym.term, synonym.expansion),
+            Err(e) => {
+                tracing::error!("Error adding synonym {}: {}", synonym.term, e);
+                return render_synonyms_page(&state, Some(format!("Database error: {}", e))).await;
+            }
+        }
+    }
+
+    Redirect::to("/admin/synonyms").into_response()
+}
+
+async fn admin_delete_synonym(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<SynonymForm>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    if let Some(synonym) = Synonym::new(&form.term, &form.expansion) {
+        match crate::db::delete_synonym(&state.db, &synonym).await {
+            Ok(_) => tracing::info!("Admin deleted synonym {} -> {}", synonym.term, synonym.expansion),
+            Err(e) => tracing::error!("Error deleting synonym {}: {}", synonym.term, e),
+        }
+    }
+
+    Redirect::to("/admin/synonyms").into_response()
+}
+
+async fn render_synonyms_page(state: &AppState, error: Option<String>) -> axum::response::Response {
+    let template = AdminSynonymsTemplate {
+        title: "Search Synonyms".to_string(),
+        synonyms: crate::db::get_synonyms(&state.db).await.unwrap_or_default(),
+        error,
+    };
+    Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
+}
+
+async fn admin_trash(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    let template = AdminTrashTemplate {
+        title: "Trash".to_string(),
+        rules: state.rules.trash().await.unwrap_or_default(),
+        retention_days: state.config.trash_retention_days,
+    };
+    Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
+}
+
+async fn admin_restore_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    match state.rules.restore(&id).await {
+        Ok(true) => {
+            tracing::info!("Admin restored rule from the trash: {}", id);
+            spawn_rule_sync(&state.db, &state.rules, &state.config.vector, vec![id.clone()]);
+            if let Ok(Some(mut rule)) = state.rules.get(&id).await {
+                rule.tags = crate::db::get_rule_tags(&state.db, &id).await.unwrap_or_default();
+                for warning in refresh_rule_records(&state, &rule).await {
+                    tracing::warn!("Restored rule {}: {}", id, warning);
+                }
+            }
+        }
+        Ok(false) => tracing::warn!("Restore requested for rule {} which is not in the trash", id),
+        Err(e) => tracing::error!("Error restoring rule {}: {}", id, e),
+    }
+
+    Redirect::to("/admin/trash").into_response()
+}
+
+async fn admin_purge_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    // Only rules already in the trash can be purged from here
+    let in_trash = state
+        .rules
+        .trash()
+        .await
+        .map(|trash| trash.iter().any(|rule| rule.id == id))
+        .unwrap_or(false);
+    if !in_trash {
+        tracing::warn!("Purge requested for rule {} which is not in the trash", id);
+        return Redirect::to("/admin/trash").into_response();
+    }
+
+    match state.rules.purge(&id).await {
+        Ok(_) => {
+            tracing::info!("Admin purged rule: {}", id);
+            spawn_rule_sync(&state.db, &state.rules, &state.config.vector, vec![id.clone()]);
+            if let Err(e) = crate::search::links::index_links_of_rule(&state.db, &id).await {
+                tracing::warn!("Failed to index rule links: {}", e);
+            }
+        }
+        Err(e) => tracing::error!("Error purging rule {}: {}", id, e),
+    }
+
+    Redirect::to("/admin/trash").into_response()
+}
+
+async fn admin_rule_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    let rule = match state.rules.get(&id).await {
+        Ok(Some(rule)) => rule,
+        _ => return Redirect::to("/admin").into_response(),
+    };
+    let revisions = crate::db::get_rule_revisions(&state.db, &id).await.unwrap_or_default();
+
+    // Revisions are newest first, so each one was replaced by the previous
+    // entry in the list (or by the live rule for the newest revision).
+    let mut newer = rule.clone();
+    let mut changes = Vec::with_capacity(revisions.len());
+    for revision in revisions {
+        let older = revision.to_rule();
+        changes.push(RevisionChange {
+            fields: field_changes(&older, &newer),
+            diff: diff_lines(&older.content, &newer.content),
+            revision,
+        });
+        newer = older;
+    }
+
+    let template = AdminHistoryTemplate {
+        title: format!("History: {}", rule.title),
+        rule,
+        changes,
+    };
+    Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
+}
+
+async fn admin_revert_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, revision_id)): Path<(String, i64)>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    let history_url = format!("/admin/rules/{}/history", id);
+
+    match crate::db::get_rule_revision(&state.db, &id, revision_id).await {
+        Ok(Some(revision)) => {
+            // Revisions don't record the collection or tags; keep the current ones
+            let mut rule = revision.to_rule();
+            if let Ok(Some(current)) = state.rules.get(&id).await {
+                rule.collection_id = current.collection_id;
+            }
+            rule.tags = crate::db::get_rule_tags(&state.db, &id).await.unwrap_or_default();
+            match save_rule(&state, &rule, &admin_actor(&headers)).await {
+                Ok(warnings) => {
+                    tracing::info!("Admin reverted rule {} to revision {}", id, revision_id);
+                    if !warnings.is_empty() {
+                        return render_save_warnings(&state, &id, &warnings).await;
+                    }
+                }
+                Err(e) => tracing::error!("Error reverting rule {}: {}", id, e),
+            }
+        }
+        Ok(None) => tracing::warn!("Revert requested for unknown revision {} of {}", revision_id, id),
+        Err(e) => tracing::error!("Error loading revision {} of {}: {}", revision_id, id, e),
+    }
+
+    Redirect::to(&history_url).into_response()
+}
+
+async fn admin_rule_errata(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    render_errata_page(&state, &id, None).await
+}
+
+async fn admin_add_erratum(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Form(form): Form<ErratumForm>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    // Errata of an unknown ID would attach to whichever rule takes it later
+    if !matches!(state.rules.get(&id).await, Ok(Some(_))) {
+        tracing::warn!("Erratum submitted for unknown rule {}", id);
+        return Redirect::to("/admin").into_response();
+    }
+
+    let published_on = form.published_on.trim();
+    if !is_valid_errata_date(published_on) {
+        return render_errata_page(&state, &id, Some("Publication date must be YYYY-MM-DD.".to_string())).await;
+    }
+    if form.original_text.trim().is_empty() || form.corrected_text.trim().is_empty() {
+        return render_errata_page(&state, &id, Some("Original and corrected text are required.".to_string())).await;
+    }
+
+    let erratum = Erratum {
+        id: 0,
+        rule_id: id.clone(),
+        published_on: published_on.to_string(),
+        original_text: form.original_text.trim().to_string(),
+        corrected_text: form.corrected_text.trim().to_string(),
+        source_note: form.source_note.as_ref().and_then(|s| {
+            let trimmed = s.trim();
+            if trimmed.is_empty() { None } else { Some(trimmed.to_string()) }
+        }),
+        created_at: String::new(),
+    };
+
+    match crate::db::upsert_erratum(&state.db, &erratum).await {
+        Ok(_) => {
+            tracing::info!("Admin added erratum to rule {} ({})", id, erratum.published_on);
+            Redirect::to(&format!("/admin/rules/{}/errata", id)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error saving erratum for {}: {}", id, e);
+            render_errata_page(&state, &id, Some(format!("Database error: {}", e))).await
+        }
+    }
+}
+
+async fn admin_delete_erratum(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, erratum_id)): Path<(String, i64)>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    match crate::db::delete_erratum(&state.db, &id, erratum_id).await {
+        Ok(_) => tracing::info!("Admin deleted erratum {} of rule {}", erratum_id, id),
+        Err(e) => tracing::error!("Error deleting erratum {} of {}: {}", erratum_id, id, e),
+    }
+
+    Redirect::to(&format!("/admin/rules/{}/errata", id)).into_response()
+}
+
+async fn render_errata_page(state: &AppState, id: &str, error: Option<String>) -> axum::response::Response {
+    let rule = match state.rules.get(id).await {
+        Ok(Some(rule)) => rule,
+        _ => return Redirect::to("/admin").into_response(),
+    };
+    let errata = crate::db::get_rule_errata(&state.db, id).await.unwrap_or_default();
+
+    let template = AdminErrataTemplate {
+        title: format!("Errata: {}", rule.title),
+        rule,
+        errata,
+        error,
+    };
+    Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
+}
+
+async fn admin_homebrew(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    render_homebrew_page(&state, None).await
+}
+
+async fn admin_save_homebrew(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(form): Form<HomebrewForm>,
+) -> impl IntoResponse {
+    if !is_authenticated(&headers, &state.config.admin_api_key) {
+        return Redirect::to("/admin").into_response();
+    }
+
+    let name = form.name.trim();
+    let owner = form.owner.trim();
+    if name.is_empty() || owner.is_empty() {
+        return render_homebrew_page(&state, Some("Name and owner are required.".to_string())).await;
+    }
+    let Some(visibility) = Visibility::from_param(&form.visibility) else {
+        return render_homebrew_page(&state, Some("Visibility must be public or private.".to_string())).await;
+    };
+    let id = slugify(if form.id.trim().is_empty() { name } else { &form.id });
+    if id.is_empty() {
+        return render_homebrew_page(&state, Some("The name needs letters or digits.".to_string())).await;
+    }
+
+    let collection = HomebrewCollection {
+        id,
+        name: name.to_string(),
+        owner: owner.to_string(),
+        visibility: visibility.as_str().to_string(),
+        description: form
+            .description
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(str::to_string),
+        created_at: now_utc(),
+    };
+    match crate::db::upsert_homebrew_collection(&state.db, &collection).await {
+        Ok(_) => {
+            tracing::info!("Admin saved homebrew collection {}", collection.id);
+            Redirect::to("/admin/homebrew").into_response()
+        }
+        Err(e) => {
+            tracing::error!("Error saving homebrew collection {}: {}", collection.id, e);
+            render_homebrew_page(&state, Some(format!("Database error: {}", e))).await
+        }
+    }
+}
+
+async fn render_homebrew_page(state: &AppState, error: Option<String>) -> axum::response::Response {
+    let template = AdminHomebrewTemplate {
+        title: "Homebrew Collections".to_string(),
+        collections: get_collections(state).await,
+        visibilities: Visibility::ALL.to_vec(),
+        error,
+    };
+    Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
+}
+
 // ── Helpers ────────────────────────────────────────────────
 
-fn slugify(title: &str) -> String {
-    title
-        .to_lowercase()
-        .chars()
-        .map(|c| if c.is_alphanumeric() { c } else { '-' })
-        .collect::<String>()
-        .split('-')
-        .filter(|s| !s.is_empty())
-        .collect::<Vec<&str>>()
-        .join("-")
+/// Upsert a rule from the admin form together with its tags, its spell or
+/// equipment records and the cross-reference graph. Returns the problems
+/// [`refresh_rule_records`] finds; errors mean the rule was not saved.
+async fn save_rule(state: &AppState, rule: &Rule, changed_by: &str) -> Result<Vec<String>, sqlx::Error> {
+    state.rules.upsert(rule, changed_by).await?;
+    spawn_rule_sync(&state.db, &state.rules, &state.config.vector, vec![rule.id.clone()]);
+
+    Ok(refresh_rule_records(state, rule).await)
+}
+
+/// Bring the tags, spell and equipment records and the cross-references of
+/// a stored rule in line with `rule`. Returns problems the admin should fix:
+/// an unreadable spell block or item line, `[[rule:id]]` links to missing
+/// rules, or records that failed to save.
+async fn refresh_rule_records(state: &AppState, rule: &Rule) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let spell = if rule.is_spell() {
+        Spell::from_rule(rule)
+            .map_err(|e| warnings.push(format!("Spell details could not be read: {}", e)))
+            .ok()
+    } else {
+        None
+    };
+
+    let equipment = if rule.is_equipment() {
+        EquipmentSet::from_rule(rule).unwrap_or_else(|e| {
+            warnings.push(format!("Equipment stats could not be read: {}", e));
+            EquipmentSet::default()
+        })
+    } else {
+        EquipmentSet::default()
+    };
+
+    if let Err(e) = crate::db::set_rule_details(&state.db, &rule.id, &rule.tags, spell.as_ref(), &equipment).await {
+        tracing::error!("Error saving details of rule {}: {}", rule.id, e);
+        warnings.push(format!(
+            "The rule was saved, but its tags, spell and equipment details were not: {}",
+            e
+        ));
+    }
+
+    match crate::search::links::index_links_of_rule(&state.db, &rule.id).await {
+        Ok(report) => {
+            let broken: Vec<String> = report
+                .broken
+                .into_iter()
+                .filter(|broken| broken.rule_id == rule.id)
+                .map(|broken| format!("[[rule:{}]]", broken.target_id))
+                .collect();
+            if !broken.is_empty() {
+                warnings.push(format!(
+                    "These links point at rules that do not exist: {}",
+                    broken.join(", ")
+                ));
+            }
+        }
+        Err(e) => {
+            tracing::error!("Error indexing links of rule {}: {}", rule.id, e);
+            warnings.push(format!("The rule was saved, but its cross-references were not updated: {}", e));
+        }
+    }
+
+    warnings
+}
+
+/// Re-open the edit form of a just-saved rule, listing what needs attention
+async fn render_save_warnings(state: &AppState, id: &str, warnings: &[String]) -> axum::response::Response {
+    render_edit_form(state, id, None, Some(format!("Saved. {}", warnings.join(" ")))).await
+}
+
+/// The edit form of a stored rule, or back to the dashboard if it is gone
+async fn render_edit_form(
+    state: &AppState,
+    id: &str,
+    error: Option<String>,
+    warning: Option<String>,
+) -> axum::response::Response {
+    let mut rule = match state.rules.get(id).await {
+        Ok(Some(rule)) => rule,
+        _ => return Redirect::to("/admin").into_response(),
+    };
+    rule.tags = crate::db::get_rule_tags(&state.db, id).await.unwrap_or_default();
+
+    let template = AdminFormTemplate {
+        title: format!("Edit: {}", rule.title),
+        is_edit: true,
+        rule: Some(rule),
+        categories: get_categories(state).await,
+        sources: get_sources(state).await,
+        collections: get_collections(state).await,
+        error,
+        warning,
+        aliases: crate::db::get_rule_aliases(&state.db, id).await.unwrap_or_default(),
+    };
+    Html(template.render().unwrap_or_else(|_| "Error".to_string())).into_response()
 }
 
 fn form_to_rule(form: &RuleForm) -> Rule {
+    // Rule IDs are always slugs, whether typed in or taken from the title
     let id = if form.id.trim().is_empty() {
         slugify(&form.title)
     } else {
-        form.id.clone()
+        slugify(&form.id)
     };
 
     Rule {
@@ -358,16 +1130,31 @@ fn form_to_rule(form: &RuleForm) -> Rule {
         }),
         content: form.content.clone(),
         source: form.source.trim().to_string(),
+        source_id: None,
         page: form.page,
-        created_at: String::new(),
-        updated_at: String::new(),
+        created_at: now_utc(),
+        updated_at: now_utc(),
+        collection_id: Some(form.collection_id.trim().to_string()).filter(|id| !id.is_empty()),
+        tags: parse_tag_list(&form.tags),
+        equipment: EquipmentSet::default(),
+        collection: None,
+        section: None,
+        highlight: None,
     }
 }
 
 async fn get_categories(state: &AppState) -> Vec<String> {
-    let rules = crate::db::get_all_rules(&state.db).await.unwrap_or_default();
+    let rules = state.rules.list(&RuleFilter::default().with_homebrew(None, true)).await.unwrap_or_default();
     let mut categories: Vec<String> = rules.iter().map(|r| r.category.clone()).collect();
     categories.sort();
     categories.dedup();
     categories
 }
+
+async fn get_sources(state: &AppState) -> Vec<Source> {
+    crate::db::get_sources(&state.db).await.unwrap_or_default()
+}
+
+async fn get_collections(state: &AppState) -> Vec<HomebrewCollection> {
+    crate::db::get_homebrew_collections(&state.db).await.unwrap_or_default()
+}
@@ -0,0 +1,89 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::{Html, IntoResponse},
+    routing::get,
+    Router,
+};
+use askama::Template;
+
+use super::AppState;
+use crate::models::{now_utc, Rule};
+
+/// How many changes `/changes` and `/feed.atom` list
+const CHANGES_LIMIT: i64 = 50;
+
+#[derive(Template)]
+#[template(path = "changes/list.html")]
+struct ChangesTemplate {
+    title: String,
+    rules: Vec<Rule>,
+}
+
+struct FeedEntry {
+    url: String,
+    title: String,
+    summary: String,
+    published: String,
+    updated: String,
+    category: String,
+}
+
+#[derive(Template)]
+#[template(path = "changes/feed.xml")]
+struct FeedTemplate {
+    base_url: String,
+    updated: String,
+    entries: Vec<FeedEntry>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/changes", get(list_changes))
+        .route("/feed.atom", get(atom_feed))
+}
+
+async fn list_changes(State(state): State<AppState>) -> Html<String> {
+    let mut rules = crate::db::get_recent_changes(&state.db, CHANGES_LIMIT).await.unwrap_or_default();
+    crate::db::attach_collections(&state.db, &mut rules).await.ok();
+    let template = ChangesTemplate {
+        title: "Recent Changes".to_string(),
+        rules,
+    };
+    Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string()))
+}
+
+async fn atom_feed(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    let base_url = state.config.public_url.clone().unwrap_or_else(|| {
+        let host = headers
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("localhost:{}", state.config.port));
+        format!("http://{}", host)
+    });
+
+    let rules = crate::db::get_recent_changes(&state.db, CHANGES_LIMIT).await.unwrap_or_default();
+    let updated = rules.first().map(|rule| rule.updated_at).unwrap_or_else(now_utc);
+
+    let template = FeedTemplate {
+        updated: updated.to_rfc3339(),
+        entries: rules
+            .into_iter()
+            .map(|rule| FeedEntry {
+                url: format!("{}/rules/{}", base_url, rule.id),
+                title: format!("{}: {}", if rule.was_updated() { "Updated" } else { "Added" }, rule.title),
+                summary: rule.excerpt(300),
+                published: rule.created_at.to_rfc3339(),
+                updated: rule.updated_at.to_rfc3339(),
+                category: rule.category,
+            })
+            .collect(),
+        base_url,
+    };
+
+    (
+        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        template.render().unwrap_or_else(|_| "Error rendering feed".to_string()),
+    )
+}
@@ -0,0 +1,167 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Json, Redirect},
+    routing::get,
+    Router,
+};
+use askama::Template;
+use serde::Deserialize;
+
+use super::{AppState, FilterOption};
+use crate::models::{Armor, EquipmentFilter, EquipmentKind, EquipmentSort, Gear, Weapon};
+
+#[derive(Deserialize)]
+pub struct EquipmentQuery {
+    category: Option<String>,
+    property: Option<String>,
+    mastery: Option<String>,
+    damage_type: Option<String>,
+    stealth: Option<String>,
+    sort: Option<String>,
+}
+
+impl EquipmentQuery {
+    fn equipment_filter(&self, is_admin: bool) -> EquipmentFilter {
+        EquipmentFilter {
+            include_private_homebrew: is_admin,
+            ..EquipmentFilter::from_params(
+                self.category.as_deref(),
+                self.property.as_deref(),
+                self.mastery.as_deref(),
+                self.damage_type.as_deref(),
+                self.stealth.as_deref(),
+                self.sort.as_deref(),
+            )
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "equipment/list.html")]
+struct EquipmentTemplate {
+    title: String,
+    kind: String,
+    kinds: Vec<FilterOption>,
+    weapons: Vec<Weapon>,
+    armor: Vec<Armor>,
+    gear: Vec<Gear>,
+    count: usize,
+    categories: Vec<FilterOption>,
+    properties: Vec<FilterOption>,
+    masteries: Vec<FilterOption>,
+    damage_types: Vec<FilterOption>,
+    stealth: Vec<FilterOption>,
+    sorts: Vec<FilterOption>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/equipment", get(|| async { Redirect::to("/equipment/weapons") }))
+        .route("/equipment/:kind", get(list_equipment))
+        .route("/api/equipment/:kind", get(api_equipment))
+}
+
+async fn list_equipment(
+    State(state): State<AppState>,
+    Path(kind): Path<String>,
+    Query(params): Query<EquipmentQuery>,
+    headers: HeaderMap,
+) -> Html<String> {
+    let Some(kind) = EquipmentKind::from_slug(&kind) else {
+        return Html("Equipment list not found".to_string());
+    };
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+    let filter = params.equipment_filter(is_admin);
+    let facets = crate::db::get_equipment_facets(&state.db, kind, is_admin).await.unwrap_or_default();
+
+    let (mut weapons, mut armor, mut gear) = (Vec::new(), Vec::new(), Vec::new());
+    let sorts: &[(EquipmentSort, &str)] = match kind {
+        EquipmentKind::Weapons => {
+            weapons = crate::db::search_weapons(&state.db, &filter).await.unwrap_or_default();
+            &[
+                (EquipmentSort::Name, "Name"),
+                (EquipmentSort::Damage, "Damage"),
+                (EquipmentSort::Cost, "Cost"),
+                (EquipmentSort::Weight, "Weight"),
+            ]
+        }
+        EquipmentKind::Armor => {
+            armor = crate::db::search_armor(&state.db, &filter).await.unwrap_or_default();
+            &[
+                (EquipmentSort::Name, "Name"),
+                (EquipmentSort::ArmorClass, "AC"),
+                (EquipmentSort::Cost, "Cost"),
+                (EquipmentSort::Weight, "Weight"),
+            ]
+        }
+        EquipmentKind::Gear => {
+            gear = crate::db::search_gear(&state.db, &filter).await.unwrap_or_default();
+            &[
+                (EquipmentSort::Name, "Name"),
+                (EquipmentSort::Cost, "Cost"),
+                (EquipmentSort::Weight, "Weight"),
+            ]
+        }
+    };
+
+    let template = EquipmentTemplate {
+        title: kind.label().to_string(),
+        kind: kind.slug().to_string(),
+        kinds: EquipmentKind::ALL
+            .iter()
+            .map(|other| FilterOption {
+                value: other.slug().to_string(),
+                label: other.label().to_string(),
+                selected: *other == kind,
+            })
+            .collect(),
+        count: weapons.len() + armor.len() + gear.len(),
+        weapons,
+        armor,
+        gear,
+        categories: FilterOption::from_values(facets.categories, &filter.category),
+        properties: FilterOption::from_values(facets.properties, &filter.property),
+        masteries: FilterOption::from_values(facets.masteries, &filter.mastery),
+        damage_types: FilterOption::from_values(facets.damage_types, &filter.damage_type),
+        stealth: FilterOption::yes_no(filter.stealth_disadvantage),
+        sorts: sorts
+            .iter()
+            .map(|(sort, label)| FilterOption {
+                value: sort.as_param().to_string(),
+                label: label.to_string(),
+                selected: *sort == filter.sort,
+            })
+            .collect(),
+    };
+    Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string()))
+}
+
+async fn api_equipment(
+    State(state): State<AppState>,
+    Path(kind): Path<String>,
+    Query(params): Query<EquipmentQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+    let filter = params.equipment_filter(is_admin);
+
+    match EquipmentKind::from_slug(&kind) {
+        Some(EquipmentKind::Weapons) => {
+            Json(crate::db::search_weapons(&state.db, &filter).await.unwrap_or_default()).into_response()
+        }
+        Some(EquipmentKind::Armor) => {
+            Json(crate::db::search_armor(&state.db, &filter).await.unwrap_or_default()).into_response()
+        }
+        Some(EquipmentKind::Gear) => {
+            Json(crate::db::search_gear(&state.db, &filter).await.unwrap_or_default()).into_response()
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": "Unknown equipment kind; use weapons, armor or gear"
+            })),
+        )
+            .into_response(),
+    }
+}
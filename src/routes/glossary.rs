@@ -0,0 +1,24 @@
+use axum::{extract::State, response::Html, routing::get, Router};
+use askama::Template;
+
+use super::AppState;
+use crate::models::GlossaryTerm;
+
+#[derive(Template)]
+#[template(path = "glossary/index.html")]
+struct GlossaryTemplate {
+    title: String,
+    terms: Vec<GlossaryTerm>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/glossary", get(glossary_index))
+}
+
+async fn glossary_index(State(state): State<AppState>) -> Html<String> {
+    let template = GlossaryTemplate {
+        title: "Glossary".to_string(),
+        terms: crate::db::get_glossary_terms(&state.db).await.unwrap_or_default(),
+    };
+    Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string()))
+}
@@ -1,26 +1,70 @@
 use axum::{
-    response::Html,
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
     routing::get,
     Router,
 };
 use askama::Template;
 use sqlx::SqlitePool;
+use std::sync::Arc;
 
+use crate::db::RuleRepository;
 use crate::middleware::RateLimitState;
 use crate::Config;
 
+pub mod changes;
 pub mod rules;
 pub mod search;
 pub mod scenario;
+pub mod spells;
+pub mod equipment;
+pub mod tags;
+pub mod glossary;
 pub mod admin;
 
 #[derive(Clone)]
 pub struct AppState {
+    /// Side tables (tags, errata, spells, equipment, revisions) and sources
     pub db: SqlitePool,
+    /// The rules themselves
+    pub rules: Arc<dyn RuleRepository>,
     pub config: Config,
     pub rate_limiter: RateLimitState,
 }
 
+/// One `<option>` of a filter `<select>` on the spell and equipment pages
+pub(crate) struct FilterOption {
+    pub value: String,
+    pub label: String,
+    pub selected: bool,
+}
+
+impl FilterOption {
+    /// Options labelled by their own value; `active` is matched case-insensitively
+    pub fn from_values(values: Vec<String>, active: &Option<String>) -> Vec<Self> {
+        values
+            .into_iter()
+            .map(|value| Self {
+                selected: active.as_deref().map(|a| a.eq_ignore_ascii_case(&value)).unwrap_or(false),
+                label: value.clone(),
+                value,
+            })
+            .collect()
+    }
+
+    /// "Yes" / "No" options for an optional boolean filter
+    pub fn yes_no(active: Option<bool>) -> Vec<Self> {
+        [("true", "Yes", true), ("false", "No", false)]
+            .into_iter()
+            .map(|(value, label, flag)| Self {
+                value: value.to_string(),
+                label: label.to_string(),
+                selected: active == Some(flag),
+            })
+            .collect()
+    }
+}
+
 #[derive(Template)]
 #[template(path = "index.html")]
 struct IndexTemplate {
@@ -28,10 +72,20 @@ struct IndexTemplate {
 }
 
 #[derive(Template)]
-#[template(path = "base.html")]
-struct BaseTemplate {
+#[template(path = "not_found.html")]
+struct NotFoundTemplate {
     title: String,
-    content: String,
+    message: String,
+}
+
+/// A 404 response with the site's not-found page
+pub(crate) fn not_found(title: &str, message: &str) -> Response {
+    let template = NotFoundTemplate {
+        title: title.to_string(),
+        message: message.to_string(),
+    };
+    let page = template.render().unwrap_or_else(|_| title.to_string());
+    (StatusCode::NOT_FOUND, Html(page)).into_response()
 }
 
 pub fn router() -> Router<AppState> {
@@ -41,6 +95,11 @@ pub fn router() -> Router<AppState> {
         .merge(rules::router())
         .merge(search::router())
         .merge(scenario::router())
+        .merge(spells::router())
+        .merge(equipment::router())
+        .merge(tags::router())
+        .merge(glossary::router())
+        .merge(changes::router())
         .merge(admin::router())
 }
 
@@ -54,3 +113,367 @@ pub async fn index() -> Html<String> {
 pub async fn health() -> &'static str {
     "OK"
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{to_bytes, Body},
+        extract::ConnectInfo,
+        http::{Request, StatusCode},
+    };
+    use std::net::SocketAddr;
+    use tower::ServiceExt;
+
+    use crate::db::{InMemoryRuleRepository, RuleRepository};
+    use crate::middleware::RateLimitConfig;
+    use crate::models::Rule;
+
+    fn rule(id: &str, title: &str, content: &str) -> Rule {
+        Rule {
+            category: "Conditions".to_string(),
+            source: "PHB".to_string(),
+            source_id: Some("PHB".to_string()),
+            ..Rule::test(id, title, content)
+        }
+    }
+
+    /// The full router over in-memory rules and an in-memory side-table database
+    async fn app(rules: Arc<InMemoryRuleRepository>) -> Router {
+        app_over(rules, crate::db::test_pool().await)
+    }
+
+    /// The full router with rules and side tables in one in-memory SQLite database
+    async fn sqlite_app() -> (Router, SqlitePool) {
+        let pool = crate::db::test_pool().await;
+        let rules = Arc::new(crate::db::SqliteRuleRepository::new(pool.clone()));
+        (app_over(rules, pool.clone()), pool)
+    }
+
+    fn app_over(rules: Arc<dyn RuleRepository>, db: SqlitePool) -> Router {
+        let mut config = Config::from_env();
+        config.admin_api_key = Some("secret".to_string());
+        config.vector.enabled = false;
+
+        let state = AppState {
+            db,
+            rules,
+            config,
+            rate_limiter: RateLimitState::new(RateLimitConfig {
+                ai_requests_per_hour: 5,
+                search_requests_per_minute: 100,
+                general_requests_per_minute: 100,
+            }),
+        };
+        router().with_state(state)
+    }
+
+    async fn get(app: Router, uri: &str) -> (StatusCode, String) {
+        send(app, Request::get(uri).body(Body::empty()).unwrap()).await
+    }
+
+    async fn get_as_admin(app: Router, uri: &str) -> (StatusCode, String) {
+        let request = Request::get(uri)
+            .header("cookie", "admin_token=secret")
+            .body(Body::empty())
+            .unwrap();
+        send(app, request).await
+    }
+
+    async fn send(app: Router, mut request: Request<Body>) -> (StatusCode, String) {
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn post_as_admin(app: Router, uri: &str) -> StatusCode {
+        post_form_as_admin(app, uri, "").await
+    }
+
+    async fn post_form_as_admin(app: Router, uri: &str, form: &str) -> StatusCode {
+        let request = Request::post(uri)
+            .header("cookie", "admin_token=secret")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from(form.to_string()))
+            .unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    async fn add_private_collection(pool: &SqlitePool, id: &str) {
+        crate::db::upsert_homebrew_collection(
+            pool,
+            &crate::models::HomebrewCollection {
+                id: id.to_string(),
+                name: "Secret Table".to_string(),
+                owner: "DM".to_string(),
+                visibility: "private".to_string(),
+                description: None,
+                created_at: chrono::Utc::now(),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn router_serves_rules_from_the_repository() {
+        let rules = Arc::new(InMemoryRuleRepository::new().with_rules([
+            rule("grappled", "Grappled Condition", "Your speed becomes 0."),
+            rule("prone", "Prone Condition", "You can only crawl."),
+        ]));
+        let app = app(rules).await;
+
+        let (status, body) = get(app.clone(), "/rules/grappled").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("Grappled Condition"));
+
+        let (_, body) = get(app.clone(), "/search?q=crawl").await;
+        assert!(body.contains("Prone Condition"));
+        assert!(!body.contains("Grappled Condition"));
+
+        let (_, body) = get(app, "/api/search?q=grap").await;
+        let page: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(page["results"][0]["id"], "grappled");
+    }
+
+    #[tokio::test]
+    async fn unknown_rules_and_tags_are_not_found() {
+        let app = app(Arc::new(InMemoryRuleRepository::new())).await;
+
+        let (status, body) = get(app.clone(), "/rules/no-such-rule").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.contains("Rule Not Found"));
+
+        let (status, body) = get(app, "/tags/no-such-tag").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.contains("Tag Not Found"));
+    }
+
+    #[tokio::test]
+    async fn search_resolves_citations_and_reports_malformed_queries() {
+        let mut prone = rule("prone", "Prone Condition", "You can only crawl.");
+        prone.page = Some(367);
+        let phb = crate::models::Source {
+            abbreviation: "PHB".to_string(),
+            name: "Player's Handbook 2024".to_string(),
+            edition: "2024".to_string(),
+            published_year: None,
+            display_order: 0,
+        };
+        let rules = Arc::new(InMemoryRuleRepository::new().with_sources(vec![phb]).with_rules([
+            rule("grappled", "Grappled Condition", "Your speed becomes 0."),
+            prone,
+        ]));
+        let app = app(rules).await;
+
+        let (status, _) = get(app.clone(), "/search?q=PHB%20p.%20367").await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+
+        let (_, body) = get(app.clone(), "/search?q=condition%20-crawl").await;
+        assert!(body.contains("Grappled Condition"));
+        assert!(!body.contains("Prone Condition"));
+
+        let (_, body) = get(app.clone(), "/search?q=%22speed").await;
+        assert!(body.contains("Unclosed quote"));
+
+        let (status, body) = get(app, "/api/search?q=colour:red").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("Unknown field"));
+    }
+
+    #[tokio::test]
+    async fn search_pages_and_counts_facets() {
+        let rules = Arc::new(InMemoryRuleRepository::new().with_rules((1..=25).map(|n| {
+            let mut rule = rule(&format!("rule-{n:02}"), &format!("Rule {n:02}"), "Creatures can hide.");
+            if n > 20 {
+                rule.category = "Combat".to_string();
+            }
+            rule
+        })));
+        let app = app(rules).await;
+
+        let (_, body) = get(app.clone(), "/api/search?q=hide&per_page=10&page=3").await;
+        let page: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!((page["total"].as_u64(), page["page"].as_u64()), (Some(25), Some(3)));
+        assert_eq!(page["results"].as_array().unwrap().len(), 5);
+        assert_eq!(page["facets"]["categories"][0]["value"], "Conditions");
+        assert_eq!(page["facets"]["categories"][0]["count"], 20);
+        assert_eq!(page["facets"]["categories"][1]["qualifier"], "category:\"Combat\"");
+
+        let (_, body) = get(app.clone(), "/search?q=hide").await;
+        assert!(body.contains("Found 25 inscription(s), showing 1–20"));
+        assert!(body.contains("/search?q=hide%20category%3A%22Combat%22"));
+        assert!(body.contains(r#"hx-get="/search?q=hide&amp;page=2""#));
+
+        // Without vector search, hybrid mode keeps the keyword ranking and carries over to later pages
+        let (_, body) = get(app.clone(), "/search?q=hide&mode=hybrid").await;
+        assert!(body.contains("Found 25 inscription(s), showing 1–20"));
+        assert!(body.contains(r#"hx-get="/search?q=hide&amp;mode=hybrid&amp;page=2""#));
+
+        let mut request = Request::get("/search?q=hide&page=2").header("hx-request", "true").body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+        let response = app.oneshot(request).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let fragment = String::from_utf8(body.to_vec()).unwrap();
+        assert_eq!(fragment.matches("<article class=\"rule-card").count(), 5);
+        assert!(!fragment.contains("<html"));
+        assert!(fragment.contains(r#"id="load-more" class="load-more" hx-swap-oob="true""#));
+    }
+
+    #[tokio::test]
+    async fn search_corrects_misspellings_that_match_nothing() {
+        let rules = Arc::new(InMemoryRuleRepository::new().with_rules([
+            rule("opportunity-attack", "Opportunity Attack", "Strike a creature that leaves your reach."),
+            rule("grappled", "Grappled Condition", "Your speed becomes 0."),
+        ]));
+        let app = app(rules).await;
+
+        let (_, body) = get(app.clone(), "/search?q=oportunity%20atack").await;
+        assert!(body.contains("Did you mean"));
+        assert!(body.contains(r#"href="/search?q=opportunity%20attack"><em>opportunity attack</em>"#));
+        assert!(body.contains("Opportunity Attack"));
+
+        let (_, body) = get(app.clone(), "/api/search?q=grapled").await;
+        let page: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(page["suggestion"], "grappled");
+        assert_eq!(page["results"][0]["id"], "grappled");
+
+        let (_, body) = get(app, "/api/search?q=grappled").await;
+        let page: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(page["suggestion"].is_null());
+    }
+
+    #[tokio::test]
+    async fn api_create_rule_writes_to_the_repository() {
+        let rules = Arc::new(InMemoryRuleRepository::new());
+        let app = app(rules.clone()).await;
+
+        let request = Request::post("/api/rules")
+            .header("content-type", "application/json")
+            .header("x-admin-key", "secret")
+            .body(Body::from(
+                r#"{"title":"Cover","category":"Combat","content":"Half cover grants +2 AC.","source":"PHB"}"#,
+            ))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(created["id"], "cover");
+        let stored = rules.get("cover").await.unwrap().unwrap();
+        assert_eq!(stored.title, "Cover");
+    }
+
+    #[tokio::test]
+    async fn reverting_a_spell_restores_its_spell_record() {
+        let (app, pool) = sqlite_app().await;
+        let block = "**Classes:** Wizard\n\n**Casting Time:** 1 Action\n\n**Range:** Self\n\n**Components:** V, S\n\n**Duration:** Instantaneous\n\nYou sense magic.";
+        let spell = |subcategory: &str| Rule {
+            category: "Spells".to_string(),
+            subcategory: Some(subcategory.to_string()),
+            ..Rule::test("detect-magic", "Detect Magic", block)
+        };
+        crate::db::upsert_rule(&pool, &spell("Level 1 Divination"), "test").await.unwrap();
+        let revised = spell("Level 2 Divination");
+        crate::db::upsert_rule(&pool, &revised, "test").await.unwrap();
+        crate::db::upsert_spell(&pool, &crate::models::Spell::from_rule(&revised).unwrap()).await.unwrap();
+
+        let revision = crate::db::get_rule_revisions(&pool, "detect-magic").await.unwrap().remove(0);
+        let uri = format!("/admin/rules/detect-magic/revisions/{}/revert", revision.id);
+        assert_eq!(post_as_admin(app, &uri).await, StatusCode::SEE_OTHER);
+
+        let stored = crate::db::get_spell(&pool, "detect-magic").await.unwrap().unwrap();
+        assert_eq!(stored.level, 1);
+    }
+
+    #[tokio::test]
+    async fn editing_a_former_id_updates_the_renamed_rule() {
+        let (app, pool) = sqlite_app().await;
+        crate::db::upsert_rule(&pool, &rule("grappled", "Grappled Condition", "Speed 0."), "test").await.unwrap();
+        crate::db::rename_rule(&pool, "grappled", "held").await.unwrap();
+
+        let form = "id=grappled&title=Held&category=Conditions&source=PHB&content=Speed+becomes+0.";
+        assert_eq!(post_form_as_admin(app.clone(), "/admin/rules/grappled/edit", form).await, StatusCode::SEE_OTHER);
+        assert!(crate::db::get_rule_by_id(&pool, "grappled").await.unwrap().is_none());
+        assert_eq!(crate::db::get_rule_by_id(&pool, "held").await.unwrap().unwrap().content, "Speed becomes 0.");
+
+        assert_eq!(post_form_as_admin(app, "/admin/rules/missing/edit", form).await, StatusCode::NOT_FOUND);
+        assert!(crate::db::get_rule_by_id(&pool, "missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn renaming_a_rule_saves_retargeted_links_as_edits() {
+        let (app, pool) = sqlite_app().await;
+        crate::db::upsert_rule(&pool, &rule("grappled", "Grappled Condition", "Speed 0."), "test").await.unwrap();
+        crate::db::upsert_rule(&pool, &rule("prone", "Prone Condition", "See [[rule:grappled|held]]."), "test")
+            .await
+            .unwrap();
+
+        let status = post_form_as_admin(app, "/admin/rules/grappled/rename", "new_id=held").await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+
+        let prone = crate::db::get_rule_by_id(&pool, "prone").await.unwrap().unwrap();
+        assert_eq!(prone.content, "See [[rule:held|held]].");
+        let revisions = crate::db::get_rule_revisions(&pool, "prone").await.unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].content, "See [[rule:grappled|held]].");
+        assert_eq!(revisions[0].changed_by, "admin");
+    }
+
+    #[tokio::test]
+    async fn spelling_suggestions_never_offer_private_homebrew_words() {
+        let (app, pool) = sqlite_app().await;
+        add_private_collection(&pool, "secret-table").await;
+        crate::db::upsert_rule(&pool, &rule("fireball", "Fireball", "A ball of fire."), "test").await.unwrap();
+        let private = Rule {
+            collection_id: Some("secret-table".to_string()),
+            ..rule("whisper", "Whisper", "The secretword opens the vault.")
+        };
+        crate::db::upsert_rule(&pool, &private, "test").await.unwrap();
+
+        let (status, body) = get(app.clone(), "/api/search?q=fireblal+secrtword").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("fireball"));
+        assert!(!body.contains("secretword"));
+
+        let (_, body) = get_as_admin(app, "/api/search?q=fireblal+secrtword").await;
+        assert!(body.contains("secretword"));
+    }
+
+    #[tokio::test]
+    async fn private_homebrew_spells_and_their_classes_are_hidden_from_visitors() {
+        let (app, pool) = sqlite_app().await;
+        add_private_collection(&pool, "secret-table").await;
+        let rule = Rule {
+            category: "Spells".to_string(),
+            subcategory: Some("Level 1 Evocation".to_string()),
+            collection_id: Some("secret-table".to_string()),
+            ..Rule::test(
+                "hidden-bolt",
+                "Hidden Bolt",
+                "**Classes:** Hexblade\n\n**Casting Time:** 1 Action\n\n**Range:** 60 feet\n\n**Components:** V, S\n\n**Duration:** Instantaneous\n\nA bolt nobody else knows.",
+            )
+        };
+        crate::db::upsert_rule(&pool, &rule, "test").await.unwrap();
+        crate::db::upsert_spell(&pool, &crate::models::Spell::from_rule(&rule).unwrap()).await.unwrap();
+
+        let (status, body) = get(app.clone(), "/api/spells").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!body.contains("hidden-bolt"));
+
+        let (_, body) = get(app.clone(), "/spells").await;
+        assert!(!body.contains("Hexblade"));
+
+        let (_, body) = get_as_admin(app.clone(), "/api/spells").await;
+        assert!(body.contains("hidden-bolt"));
+        let (_, body) = get_as_admin(app, "/spells").await;
+        assert!(body.contains("Hexblade"));
+    }
+}
@@ -1,20 +1,27 @@
 use axum::{
     extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    response::{Html, IntoResponse, Json},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
 use askama::Template;
 use serde::Deserialize;
-use pulldown_cmark::{Parser, html};
+use std::collections::HashMap;
 
 use super::AppState;
-use crate::models::Rule;
+use crate::models::{
+    apply_errata, errata_today, now_utc, render_markdown, rule_sections, EquipmentSet, Erratum, Rule, RuleFilter,
+    RuleSection, Source,
+};
+use crate::search::links::{explicit_links, render_explicit_links};
 
 #[derive(Deserialize)]
 pub struct RulesListQuery {
     category: Option<String>,
+    source: Option<String>,
+    exclude_source: Option<String>,
+    edition: Option<String>,
 }
 
 struct CategoryTab {
@@ -29,6 +36,8 @@ struct RulesListTemplate {
     rules: Vec<Rule>,
     categories: Vec<CategoryTab>,
     active_category: String,
+    sources: Vec<Source>,
+    active_source: String,
 }
 
 #[derive(Template)]
@@ -36,6 +45,12 @@ struct RulesListTemplate {
 struct RuleDetailTemplate {
     title: String,
     rule: Rule,
+    errata: Vec<Erratum>,
+    related: Vec<Rule>,
+    /// Former IDs, so bookmarks saved under them can be moved over
+    aliases: Vec<String>,
+    /// Sections with anchors, listed for deep links
+    sections: Vec<RuleSection>,
 }
 
 #[derive(Deserialize)]
@@ -46,6 +61,9 @@ pub struct CreateRuleRequest {
     pub content: String,
     pub source: String,
     pub page: Option<i32>,
+    /// Homebrew collection to add the rule to; omit for official content
+    #[serde(default)]
+    pub collection_id: Option<String>,
 }
 
 #[derive(Debug)]
@@ -122,7 +140,7 @@ impl CreateRuleRequest {
 
         // Page: 1-2000 range
         if let Some(page) = self.page {
-            if page < 1 || page > 2000 {
+            if !(1..=2000).contains(&page) {
                 errors.push(ValidationError {
                     field: "page".to_string(),
                     message: "Page must be between 1 and 2000".to_string(),
@@ -147,10 +165,20 @@ pub fn router() -> Router<AppState> {
 
 async fn list_rules(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Query(params): Query<RulesListQuery>,
 ) -> Html<String> {
-    let all_rules = crate::db::get_all_rules(&state.db).await.unwrap_or_default();
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+    let filter = RuleFilter::from_params(
+        params.source.as_deref(),
+        params.exclude_source.as_deref(),
+        params.edition.as_deref(),
+    )
+    .with_homebrew(None, is_admin);
+    let all_rules = state.rules.list(&filter).await.unwrap_or_default();
+    let sources = crate::db::get_sources(&state.db).await.unwrap_or_default();
     let active_category = params.category.unwrap_or_default();
+    let active_source = filter.sources.join(",");
 
     // Extract unique categories
     let mut cat_names: Vec<String> = all_rules.iter().map(|r| r.category.clone()).collect();
@@ -160,11 +188,14 @@ async fn list_rules(
         let active = name == active_category;
         CategoryTab { name, active }
     }).collect();
-    let rules = if active_category.is_empty() {
+    let mut rules: Vec<Rule> = if active_category.is_empty() {
         all_rules
     } else {
         all_rules.into_iter().filter(|r| r.category == active_category).collect()
     };
+    crate::db::attach_tags(&state.db, &mut rules).await.ok();
+    crate::db::attach_equipment(&state.db, &mut rules).await.ok();
+    crate::db::attach_collections(&state.db, &mut rules).await.ok();
 
     let template = RulesListTemplate {
         title: if active_category.is_empty() {
@@ -175,29 +206,79 @@ async fn list_rules(
         rules,
         categories,
         active_category,
+        sources,
+        active_source,
     };
     Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string()))
 }
 
 async fn get_rule(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Html<String> {
-    match crate::db::get_rule_by_id(&state.db, &id).await {
-        Ok(Some(mut rule)) => {
-            // Render markdown to HTML for the rule content
-            let parser = Parser::new(&rule.content);
-            let mut html_output = String::new();
-            html::push_html(&mut html_output, parser);
-            rule.content = html_output;
+) -> Response {
+    // Private homebrew is only shown to admins
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+    let visibility = RuleFilter::default().with_homebrew(None, is_admin);
+    let collections = crate::db::get_homebrew_collections(&state.db).await.unwrap_or_default();
+
+    match state.rules.get(&id).await {
+        Ok(Some(mut rule)) if visibility.matches_collection(rule.collection_id.as_deref(), &collections) => {
+            rule.tags = crate::db::get_rule_tags(&state.db, &rule.id).await.unwrap_or_default();
+            crate::db::attach_equipment(&state.db, std::slice::from_mut(&mut rule)).await.ok();
+            crate::db::attach_collections(&state.db, std::slice::from_mut(&mut rule)).await.ok();
+            let today = errata_today();
+            let errata: Vec<Erratum> = crate::db::get_rule_errata(&state.db, &rule.id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|erratum| erratum.is_effective(&today))
+                .collect();
+            let content = apply_errata(&rule.content, &errata, &today);
+
+            // Resolve [[rule:id]] links to the titles of the rules they point at
+            let link_ids: Vec<String> = explicit_links(&content).into_iter().map(|l| l.rule_id).collect();
+            let titles: HashMap<String, String> = state.rules.get_many(&link_ids)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|r| (r.id, r.title))
+                .collect();
+            let content = render_explicit_links(&content, &titles);
+            let mut related = crate::db::get_related_rules(&state.db, &rule.id).await.unwrap_or_default();
+            related.retain(|r| visibility.matches_collection(r.collection_id.as_deref(), &collections));
+
+            // Render markdown to HTML with glossary tooltips, leaving out the
+            // term this rule itself defines
+            let mut glossary = crate::db::get_glossary_terms(&state.db).await.unwrap_or_default();
+            glossary.retain(|term| term.rule_id.as_deref() != Some(rule.id.as_str()));
+            let sections = rule_sections(&content)
+                .into_iter()
+                .filter(|section| section.anchor.is_some())
+                .collect();
+            rule.content = render_markdown(&content, &glossary);
+            let aliases = crate::db::get_rule_aliases(&state.db, &rule.id).await.unwrap_or_default();
 
             let template = RuleDetailTemplate {
                 title: rule.title.clone(),
                 rule,
+                errata,
+                related,
+                aliases,
+                sections,
             };
-            Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string()))
+            Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string())).into_response()
         }
-        _ => Html("Rule not found".to_string()),
+        Ok(None) => match crate::db::resolve_rule_alias(&state.db, &id).await {
+            // Old IDs stay reachable after a rename
+            Ok(Some(canonical)) => (
+                StatusCode::MOVED_PERMANENTLY,
+                [(header::LOCATION, format!("/rules/{}", canonical))],
+            )
+                .into_response(),
+            _ => super::not_found("Rule Not Found", "There is no rule with that ID."),
+        },
+        _ => super::not_found("Rule Not Found", "There is no rule with that ID."),
     }
 }
 
@@ -263,22 +344,58 @@ async fn create_rule(
         );
     }
 
-    let now = chrono::Utc::now().to_rfc3339();
+    if let Some(collection_id) = &payload.collection_id {
+        if !matches!(crate::db::get_homebrew_collection(&state.db, collection_id).await, Ok(Some(_))) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "Validation failed",
+                    "details": [format!("collection_id: no homebrew collection '{}'", collection_id)]
+                })),
+            );
+        }
+    }
+
+    let id = match crate::db::available_rule_id(&state.db, &payload.title).await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to pick a rule ID: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": "Failed to create rule"
+                })),
+            );
+        }
+    };
+
+    let now = now_utc();
     let rule = Rule {
-        id: uuid::Uuid::new_v4().to_string(),
+        id,
         title: payload.title,
         category: payload.category,
         subcategory: payload.subcategory,
         content: payload.content,
         source: payload.source,
+        source_id: None,
         page: payload.page,
-        created_at: now.clone(),
+        created_at: now,
         updated_at: now,
+        collection_id: payload.collection_id,
+        tags: Vec::new(),
+        equipment: EquipmentSet::default(),
+        collection: None,
+        section: None,
+        highlight: None,
     };
 
-    match crate::db::create_rule(&state.db, &rule).await {
+    match state.rules.upsert(&rule, "api").await {
         Ok(_) => {
             tracing::info!("Rule created: {} ({})", rule.title, rule.id);
+            crate::search::vector_sync::spawn_rule_sync(&state.db, &state.rules, &state.config.vector, vec![rule.id.clone()]);
+            if let Err(e) = crate::search::links::index_links_of_rule(&state.db, &rule.id).await {
+                tracing::warn!("Failed to index rule links: {}", e);
+            }
             (
                 StatusCode::CREATED,
                 Json(serde_json::json!({
@@ -9,21 +9,27 @@ use axum::{
 use askama::Template;
 use serde::Deserialize;
 use std::net::SocketAddr;
-use pulldown_cmark::{Parser, html};
 
 use super::AppState;
 use crate::middleware::extract_client_ip;
-use crate::models::Rule;
+use crate::models::{matching_section, render_markdown, Rule, RuleFilter, Source};
 
 #[derive(Deserialize)]
 pub struct ScenarioQuery {
     question: String,
+    source: Option<String>,
+    exclude_source: Option<String>,
+    edition: Option<String>,
+    /// "exclude" keeps house rules out of the Oracle's context
+    homebrew: Option<String>,
 }
 
 #[derive(Template)]
 #[template(path = "scenario/ask.html")]
 struct ScenarioAskTemplate {
     title: String,
+    sources: Vec<Source>,
+    editions: Vec<String>,
 }
 
 #[derive(Template)]
@@ -47,9 +53,16 @@ pub fn router() -> Router<AppState> {
         .route("/scenario/ask", post(ask_scenario))
 }
 
-async fn scenario_form() -> Html<String> {
+async fn scenario_form(State(state): State<AppState>) -> Html<String> {
+    let sources = crate::db::get_sources(&state.db).await.unwrap_or_default();
+    let mut editions: Vec<String> = sources.iter().map(|s| s.edition.clone()).collect();
+    editions.sort();
+    editions.dedup();
+
     let template = ScenarioAskTemplate {
         title: "Ask a Scenario Question".to_string(),
+        sources,
+        editions,
     };
     Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string()))
 }
@@ -60,6 +73,8 @@ async fn ask_scenario(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Form(query): Form<ScenarioQuery>,
 ) -> impl IntoResponse {
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+
     // Build a minimal request to extract IP
     let mut req = axum::http::Request::new(());
     *req.headers_mut() = headers;
@@ -67,7 +82,7 @@ async fn ask_scenario(
     let client_ip = extract_client_ip(&req);
 
     // Check rate limit for AI endpoint
-    if let Err(_) = state.rate_limiter.check_rate_limit(client_ip, "/scenario/ask", "POST").await {
+    if state.rate_limiter.check_rate_limit(client_ip, "/scenario/ask", "POST").await.is_err() {
         tracing::warn!("AI rate limit exceeded for IP {}", client_ip);
         let template = ScenarioErrorTemplate {
             title: "Rate Limit Exceeded".to_string(),
@@ -94,18 +109,38 @@ async fn ask_scenario(
         return Html(template.render().unwrap_or_else(|_| "Error".to_string()));
     }
 
+    let filter = RuleFilter::from_params(
+        query.source.as_deref(),
+        query.exclude_source.as_deref(),
+        query.edition.as_deref(),
+    )
+    .with_homebrew(query.homebrew.as_deref(), is_admin);
+
     // Get relevant rules for context. Vector retrieval is optional and falls back to FTS5.
-    let relevant_rules = crate::search::hybrid::retrieve_oracle_rules(
+    let mut relevant_rules = crate::search::hybrid::retrieve_oracle_rules(
         &state.db,
+        state.rules.as_ref(),
         &state.config.vector,
         question,
+        &filter,
     )
     .await;
+    crate::db::attach_collections(&state.db, &mut relevant_rules).await.ok();
+    if state.config.vector.oracle_section_context {
+        for rule in &mut relevant_rules {
+            rule.section = matching_section(&rule.content, question).filter(|section| section.anchor.is_some());
+        }
+    }
+
+    let rule_ids: Vec<String> = relevant_rules.iter().map(|rule| rule.id.clone()).collect();
+    let errata = crate::db::get_errata_for_rules(&state.db, &rule_ids)
+        .await
+        .unwrap_or_default();
 
     // Call Claude API for ruling
     let answer = match &state.config.claude_api_key {
         Some(api_key) => {
-            crate::ai::claude::get_ruling(api_key, &state.config.claude_model, question, &relevant_rules)
+            crate::ai::claude::get_ruling(api_key, &state.config.claude_model, question, &relevant_rules, &errata)
                 .await
                 .unwrap_or_else(|e| {
                     tracing::error!("Claude API error: {}", e);
@@ -115,10 +150,9 @@ async fn ask_scenario(
         None => "AI rulings are currently unavailable. Please contact the administrator.".to_string(),
     };
 
-    // Render markdown to HTML
-    let parser = Parser::new(&answer);
-    let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
+    // Render markdown to HTML with glossary tooltips
+    let glossary = crate::db::get_glossary_terms(&state.db).await.unwrap_or_default();
+    let html_output = render_markdown(&answer, &glossary);
 
     let template = ScenarioResponseTemplate {
         question: question.to_string(),
@@ -1,7 +1,7 @@
 use axum::{
     extract::{ConnectInfo, Query, State},
     http::{HeaderMap, StatusCode},
-    response::{Html, IntoResponse, Json},
+    response::{Html, IntoResponse, Json, Redirect},
     routing::get,
     Router,
 };
@@ -11,11 +11,64 @@ use std::net::SocketAddr;
 
 use super::AppState;
 use crate::middleware::extract_client_ip;
-use crate::models::Rule;
+use crate::models::{matching_section, FacetCount, Rule, RuleFilter, SearchFacets, SearchHighlight, SearchPage, Source, Synonym};
+use crate::search::fulltext::MAX_SEARCH_HITS;
+use crate::search::query::ParsedQuery;
+
+/// Results per `/search` page
+const PAGE_SIZE: usize = 20;
+/// Default and largest `per_page` for `/api/search`
+const API_PAGE_SIZE: usize = 8;
+const API_MAX_PAGE_SIZE: usize = 50;
 
 #[derive(Deserialize)]
 pub struct SearchQuery {
     q: Option<String>,
+    source: Option<String>,
+    exclude_source: Option<String>,
+    edition: Option<String>,
+    /// "exclude" leaves homebrew rules out
+    homebrew: Option<String>,
+    /// 1-based page number
+    page: Option<usize>,
+    /// Results per page; `/api/search` only
+    per_page: Option<usize>,
+    /// "hybrid" fuses semantic hits into the full-text ranking when vector
+    /// search is enabled
+    mode: Option<String>,
+}
+
+impl SearchQuery {
+    fn rule_filter(&self, is_admin: bool) -> RuleFilter {
+        RuleFilter::from_params(
+            self.source.as_deref(),
+            self.exclude_source.as_deref(),
+            self.edition.as_deref(),
+        )
+        .with_homebrew(self.homebrew.as_deref(), is_admin)
+    }
+
+    fn is_hybrid(&self) -> bool {
+        self.mode.as_deref().is_some_and(|mode| mode.trim().eq_ignore_ascii_case("hybrid"))
+    }
+
+    /// The filter parameters as a query-string suffix (`&source=PHB...`), so
+    /// page and facet links keep them
+    fn link_params(&self) -> String {
+        [
+            ("source", &self.source),
+            ("exclude_source", &self.exclude_source),
+            ("edition", &self.edition),
+            ("homebrew", &self.homebrew),
+            ("mode", &self.mode),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let value = value.as_deref().filter(|value| !value.is_empty())?;
+            Some(format!("&{}={}", name, encode(value)))
+        })
+        .collect()
+    }
 }
 
 #[derive(Template)]
@@ -23,15 +76,113 @@ pub struct SearchQuery {
 struct SearchResultsTemplate {
     title: String,
     query: String,
-    results: Vec<Rule>,
+    /// Why the query could not be parsed
+    error: Option<String>,
+    /// "PHB p. 195" when the query was a citation resolved to its page
+    citation: Option<String>,
+    /// Corrected query whose results are shown when the query matched nothing
+    suggestion: Option<String>,
+    results: SearchPage,
+    sources: Vec<Source>,
+    editions: Vec<String>,
+    active_source: String,
+    active_edition: String,
+    exclude_homebrew: bool,
+    /// Whether semantic hits are fused into the ranking
+    hybrid: bool,
+    /// Whether vector search is enabled, so hybrid mode can be offered
+    hybrid_available: bool,
+    link_params: String,
+}
+
+impl SearchResultsTemplate {
+    fn next_href(&self) -> Option<String> {
+        next_href(&self.query, &self.link_params, &self.results)
+    }
+
+    fn facet_groups(&self) -> [(&'static str, &[FacetCount]); 3] {
+        [
+            ("Category", &self.results.facets.categories),
+            ("Subcategory", &self.results.facets.subcategories),
+            ("Source", &self.results.facets.sources),
+        ]
+    }
+
+    /// Link searching for the corrected query
+    fn suggestion_href(&self) -> Option<String> {
+        let suggestion = self.suggestion.as_ref()?;
+        Some(format!("/search?q={}{}", encode(suggestion), self.link_params))
+    }
+
+    /// Link narrowing the search to a facet value
+    fn facet_href(&self, facet: &FacetCount) -> String {
+        let narrowed = format!("{} {}", self.query, facet.qualifier);
+        format!("/search?q={}{}", encode(narrowed.trim()), self.link_params)
+    }
+}
+
+/// The cards of a later page and the next "Load more" link, fetched by
+/// HTMX and appended to the results list
+#[derive(Template)]
+#[template(path = "rules/search_more.html")]
+struct SearchMoreTemplate {
+    query: String,
+    results: SearchPage,
+    link_params: String,
+}
+
+impl SearchMoreTemplate {
+    fn next_href(&self) -> Option<String> {
+        next_href(&self.query, &self.link_params, &self.results)
+    }
+}
+
+/// Link to the page after `results`, if there is one
+fn next_href(query: &str, link_params: &str, results: &SearchPage) -> Option<String> {
+    let page = results.next_page()?;
+    Some(format!("/search?q={}{}&page={}", encode(query), link_params, page))
+}
+
+fn encode(value: &str) -> String {
+    askama::filters::urlencode_strict(value).unwrap_or_default()
 }
 
 #[derive(Serialize)]
 struct SearchSuggestion {
     id: String,
+    /// Anchor of the matching section on the rule page
+    section: Option<String>,
     title: String,
     category: String,
     excerpt: String,
+    /// Title and passage with the matched terms in `<mark>`, as escaped HTML
+    highlight: Option<SearchHighlight>,
+    tags: Vec<String>,
+}
+
+/// One page of `/api/search` results
+#[derive(Serialize)]
+struct SearchResponse {
+    results: Vec<SearchSuggestion>,
+    total: usize,
+    page: usize,
+    per_page: usize,
+    facets: SearchFacets,
+    /// Corrected query whose results these are when the query matched nothing
+    suggestion: Option<String>,
+}
+
+/// Every hit of a parsed query, best first
+struct SearchHits {
+    rules: Vec<Rule>,
+    /// "PHB p. 195" when the query was a citation resolved to its page
+    citation: Option<String>,
+    /// Spelling-corrected query the hits are for, when the query as typed
+    /// matched nothing
+    suggestion: Option<String>,
+    /// The searched words, for section matching
+    text: String,
+    synonyms: Vec<Synonym>,
 }
 
 pub fn router() -> Router<AppState> {
@@ -46,6 +197,9 @@ async fn search_rules(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Query(params): Query<SearchQuery>,
 ) -> impl IntoResponse {
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+    let is_htmx = headers.contains_key("hx-request");
+
     // Build a minimal request to extract IP
     let mut req = axum::http::Request::new(());
     *req.headers_mut() = headers;
@@ -58,7 +212,8 @@ async fn search_rules(
         return e.into_response();
     }
 
-    let query = params.q.unwrap_or_default();
+    let mut filter = params.rule_filter(is_admin);
+    let query = params.q.clone().unwrap_or_default();
 
     // Validate query length
     let query = if query.len() > 500 {
@@ -67,27 +222,59 @@ async fn search_rules(
         query
     };
 
-    let results = if query.is_empty() {
-        vec![]
-    } else {
-        let fts_results = crate::search::fulltext::search(&state.db, &query)
-            .await
-            .unwrap_or_default();
+    let active_source = filter.sources.join(",");
+    let sources = crate::db::get_sources(&state.db).await.unwrap_or_default();
+    let page = params.page.unwrap_or(1);
+    let hybrid = params.is_hybrid();
+    let (mut results, error, citation, suggestion) = match ParsedQuery::parse(&query) {
+        Ok(parsed) => {
+            parsed.apply_to(&mut filter);
+            let hits = find_hits(&state, &query, &parsed, &filter, &sources, hybrid).await;
 
-        // Fall back to fuzzy search if FTS returns no results
-        if fts_results.is_empty() {
-            crate::db::fuzzy_search(&state.db, &query, 20)
-                .await
-                .unwrap_or_default()
-        } else {
-            fts_results
+            // A citation naming a single rule goes straight to it
+            if let (Some(_), [rule]) = (&hits.citation, hits.rules.as_slice()) {
+                return Redirect::to(&format!("/rules/{}", rule.id)).into_response();
+            }
+
+            let mut results = SearchPage::new(hits.rules, page, PAGE_SIZE, &filter);
+            attach_matching_sections(&mut results.rules, &hits.text, &hits.synonyms);
+            (results, None, hits.citation, hits.suggestion)
         }
+        Err(error) => (SearchPage::new(Vec::new(), 1, PAGE_SIZE, &filter), Some(error.to_string()), None, None),
     };
+    crate::db::attach_tags(&state.db, &mut results.rules).await.ok();
+    crate::db::attach_equipment(&state.db, &mut results.rules).await.ok();
+    crate::db::attach_collections(&state.db, &mut results.rules).await.ok();
+
+    let link_params = params.link_params();
+    if is_htmx && results.page > 1 {
+        let template = SearchMoreTemplate {
+            query,
+            results,
+            link_params,
+        };
+        return Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string())).into_response();
+    }
+
+    let mut editions: Vec<String> = sources.iter().map(|s| s.edition.clone()).collect();
+    editions.sort();
+    editions.dedup();
 
     let template = SearchResultsTemplate {
         title: format!("Search: {}", query),
         query,
+        error,
+        citation,
+        suggestion,
         results,
+        sources,
+        editions,
+        active_source,
+        exclude_homebrew: filter.exclude_homebrew,
+        active_edition: filter.edition.unwrap_or_default(),
+        hybrid,
+        hybrid_available: state.config.vector.enabled,
+        link_params,
     };
     Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string())).into_response()
 }
@@ -98,6 +285,8 @@ async fn api_search(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Query(params): Query<SearchQuery>,
 ) -> impl IntoResponse {
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+
     // Build a minimal request to extract IP
     let mut req = axum::http::Request::new(());
     *req.headers_mut() = headers;
@@ -105,7 +294,7 @@ async fn api_search(
     let client_ip = extract_client_ip(&req);
 
     // Check rate limit
-    if let Err(_) = state.rate_limiter.check_rate_limit(client_ip, "/api/search", "GET").await {
+    if state.rate_limiter.check_rate_limit(client_ip, "/api/search", "GET").await.is_err() {
         tracing::warn!("API search rate limit exceeded for IP {}", client_ip);
         return (
             StatusCode::TOO_MANY_REQUESTS,
@@ -113,36 +302,203 @@ async fn api_search(
         ).into_response();
     }
 
-    let query = params.q.unwrap_or_default();
+    let mut filter = params.rule_filter(is_admin);
+    let query = params.q.clone().unwrap_or_default();
+    let page = params.page.unwrap_or(1);
+    let per_page = params.per_page.unwrap_or(API_PAGE_SIZE).clamp(1, API_MAX_PAGE_SIZE);
 
     // Validate query length
-    if query.len() < 2 {
-        return Json(Vec::<SearchSuggestion>::new()).into_response();
-    }
-
     let query = if query.len() > 500 {
         query[..500].to_string()
     } else {
         query
     };
 
-    // Use fuzzy search for suggestions
-    let results = crate::db::fuzzy_search(&state.db, &query, 8)
-        .await
-        .unwrap_or_default();
+    let (mut results, text, synonyms, suggestion) = if query.len() < 2 {
+        (SearchPage::new(Vec::new(), page, per_page, &filter), String::new(), Vec::new(), None)
+    } else {
+        let parsed = match ParsedQuery::parse(&query) {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": error.to_string()}))).into_response();
+            }
+        };
+        parsed.apply_to(&mut filter);
+
+        let sources = crate::db::get_sources(&state.db).await.unwrap_or_default();
+        let hits = find_hits(&state, &query, &parsed, &filter, &sources, params.is_hybrid()).await;
+        (SearchPage::new(hits.rules, page, per_page, &filter), hits.text, hits.synonyms, hits.suggestion)
+    };
+    attach_matching_sections(&mut results.rules, &text, &synonyms);
+    crate::db::attach_tags(&state.db, &mut results.rules).await.ok();
 
     let suggestions: Vec<SearchSuggestion> = results
+        .rules
         .into_iter()
         .map(|rule| {
             let excerpt = rule.excerpt(100);
             SearchSuggestion {
+                section: rule.section.and_then(|section| section.anchor),
                 id: rule.id,
                 title: rule.title,
                 category: rule.category,
                 excerpt,
+                highlight: rule.highlight,
+                tags: rule.tags.into_iter().map(|tag| tag.slug).collect(),
             }
         })
         .collect();
 
-    Json(suggestions).into_response()
+    Json(SearchResponse {
+        results: suggestions,
+        total: results.total,
+        page: results.page,
+        per_page: results.per_page,
+        facets: results.facets,
+        suggestion,
+    })
+    .into_response()
+}
+
+/// Run a query: the rules on the cited page for a citation, else full-text
+/// search (fused with semantic hits when `hybrid`) with a substring fallback
+/// and then a spelling correction, else everything passing the field
+/// qualifiers
+async fn find_hits(
+    state: &AppState,
+    query: &str,
+    parsed: &ParsedQuery,
+    filter: &RuleFilter,
+    sources: &[Source],
+    hybrid: bool,
+) -> SearchHits {
+    if let Some((label, on_page)) = citation_rules(state, parsed, filter, sources).await {
+        return SearchHits {
+            rules: on_page,
+            citation: Some(label),
+            suggestion: None,
+            text: String::new(),
+            synonyms: Vec::new(),
+        };
+    }
+
+    let hits = find_text_hits(state, parsed, filter, hybrid).await;
+    if !hits.rules.is_empty() || parsed.fts_expression().is_none() {
+        return hits;
+    }
+
+    // Nothing matched as typed - try the closest spelling the index knows
+    let vocabulary = state.rules.vocabulary(filter).await.unwrap_or_default();
+    let corrected = crate::search::spelling::suggest(query, &vocabulary)
+        .and_then(|suggestion| ParsedQuery::parse(&suggestion).ok().map(|parsed| (suggestion, parsed)));
+    let Some((suggestion, corrected)) = corrected else {
+        return hits;
+    };
+
+    // Only offer corrections that find something the reader may see
+    let corrected_hits = find_text_hits(state, &corrected, filter, hybrid).await;
+    if corrected_hits.rules.is_empty() {
+        return hits;
+    }
+    SearchHits {
+        suggestion: Some(suggestion),
+        ..corrected_hits
+    }
+}
+
+/// Full-text search with a substring fallback, or everything passing the
+/// field qualifiers when the query has no words. With `hybrid`, semantic
+/// hits for the words are fused in unless phrases or `title:` terms must
+/// match exactly.
+async fn find_text_hits(state: &AppState, parsed: &ParsedQuery, filter: &RuleFilter, hybrid: bool) -> SearchHits {
+    let text = parsed.text();
+    let synonyms = if text.is_empty() {
+        Vec::new()
+    } else {
+        crate::db::get_synonyms(&state.db).await.unwrap_or_default()
+    };
+    let exact = !parsed.required.is_empty() || !parsed.title.is_empty();
+    let mut rules = if parsed.fts_expression().is_some() {
+        let fts_results =
+            crate::search::fulltext::search(state.rules.as_ref(), &synonyms, parsed, MAX_SEARCH_HITS, filter)
+                .await
+                .unwrap_or_default();
+
+        // Fall back to fuzzy search if FTS returns no results, unless
+        // phrases or title: terms must match exactly
+        if fts_results.is_empty() && !exact {
+            crate::search::fulltext::fuzzy_search(state.rules.as_ref(), &synonyms, &text, MAX_SEARCH_HITS, filter)
+                .await
+                .unwrap_or_default()
+        } else {
+            fts_results
+        }
+    } else if parsed.has_filters() {
+        // Only field qualifiers - list everything passing them
+        state.rules.list(filter).await.unwrap_or_default()
+    } else {
+        vec![]
+    };
+
+    if hybrid && state.config.vector.enabled && !text.is_empty() && !exact {
+        let ranked = crate::search::hybrid::hybrid_rank(
+            &state.db,
+            state.rules.as_ref(),
+            &state.config.vector,
+            &text,
+            rules,
+            filter,
+            MAX_SEARCH_HITS as usize,
+        )
+        .await;
+        rules = ranked.into_iter().map(|scored| scored.rule).collect();
+        crate::search::fulltext::highlight_unmarked(&mut rules, &text);
+    }
+
+    SearchHits {
+        rules,
+        citation: None,
+        suggestion: None,
+        text,
+        synonyms,
+    }
+}
+
+/// The rules on the cited page when the query is a citation such as
+/// "PHB 195" of a known source, with a label like "PHB p. 195"; None when
+/// the query is no citation or nothing is on that page
+async fn citation_rules(
+    state: &AppState,
+    parsed: &ParsedQuery,
+    filter: &RuleFilter,
+    sources: &[Source],
+) -> Option<(String, Vec<Rule>)> {
+    let citation = parsed.citation.as_ref()?;
+    if !sources.iter().any(|source| source.abbreviation == citation.source) {
+        return None;
+    }
+
+    let on_page = RuleFilter {
+        sources: vec![citation.source.clone()],
+        pages: vec![citation.page],
+        ..filter.clone()
+    };
+    let rules = state.rules.list(&on_page).await.unwrap_or_default();
+    (!rules.is_empty()).then(|| (format!("{} p. {}", citation.source, citation.page), rules))
+}
+
+/// Point each result at the section that matches the query (or one of its
+/// synonym expansions) best, when that is not the rule's opening text
+fn attach_matching_sections(results: &mut [Rule], text: &str, synonyms: &[Synonym]) {
+    if text.is_empty() {
+        return;
+    }
+
+    let mut terms = vec![text.to_string()];
+    terms.extend(crate::search::fulltext::expand_synonyms(text, synonyms));
+    let query = terms.join(" ");
+
+    for rule in results.iter_mut() {
+        rule.section = matching_section(&rule.content, &query).filter(|section| section.anchor.is_some());
+    }
 }
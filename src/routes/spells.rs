@@ -0,0 +1,115 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{Html, IntoResponse, Json},
+    routing::get,
+    Router,
+};
+use askama::Template;
+use serde::Deserialize;
+
+use super::{AppState, FilterOption};
+use crate::models::{level_label, Spell, SpellFilter};
+
+/// Casting time kinds offered in the filter, as (slug, label)
+const CASTING_TIMES: [(&str, &str); 5] = [
+    ("action", "Action"),
+    ("bonus-action", "Bonus Action"),
+    ("reaction", "Reaction"),
+    ("minute", "Minutes"),
+    ("hour", "Hours"),
+];
+
+#[derive(Deserialize)]
+pub struct SpellQuery {
+    class: Option<String>,
+    level: Option<String>,
+    school: Option<String>,
+    ritual: Option<String>,
+    concentration: Option<String>,
+    casting_time: Option<String>,
+}
+
+impl SpellQuery {
+    fn spell_filter(&self, is_admin: bool) -> SpellFilter {
+        SpellFilter {
+            include_private_homebrew: is_admin,
+            ..SpellFilter::from_params(
+                self.class.as_deref(),
+                self.level.as_deref(),
+                self.school.as_deref(),
+                self.ritual.as_deref(),
+                self.concentration.as_deref(),
+                self.casting_time.as_deref(),
+            )
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "spells/list.html")]
+struct SpellsTemplate {
+    title: String,
+    spells: Vec<Spell>,
+    classes: Vec<FilterOption>,
+    levels: Vec<FilterOption>,
+    schools: Vec<FilterOption>,
+    casting_times: Vec<FilterOption>,
+    ritual: Vec<FilterOption>,
+    concentration: Vec<FilterOption>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/spells", get(list_spells))
+        .route("/api/spells", get(api_spells))
+}
+
+async fn list_spells(
+    State(state): State<AppState>,
+    Query(params): Query<SpellQuery>,
+    headers: HeaderMap,
+) -> Html<String> {
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+    let filter = params.spell_filter(is_admin);
+    let spells = crate::db::search_spells(&state.db, &filter).await.unwrap_or_default();
+    let (classes, schools) = crate::db::get_spell_facets(&state.db, is_admin).await.unwrap_or_default();
+
+    let template = SpellsTemplate {
+        title: "Spells".to_string(),
+        spells,
+        classes: FilterOption::from_values(classes, &filter.class),
+        levels: (0..=9)
+            .map(|level| FilterOption {
+                value: level.to_string(),
+                label: level_label(level),
+                selected: filter.level == Some(level),
+            })
+            .collect(),
+        schools: FilterOption::from_values(schools, &filter.school),
+        casting_times: CASTING_TIMES
+            .iter()
+            .map(|(value, label)| FilterOption {
+                value: value.to_string(),
+                label: label.to_string(),
+                selected: filter.casting_time.as_deref() == Some(*value),
+            })
+            .collect(),
+        ritual: FilterOption::yes_no(filter.ritual),
+        concentration: FilterOption::yes_no(filter.concentration),
+    };
+    Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string()))
+}
+
+async fn api_spells(
+    State(state): State<AppState>,
+    Query(params): Query<SpellQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+    let spells = crate::db::search_spells(&state.db, &params.spell_filter(is_admin))
+        .await
+        .unwrap_or_default();
+
+    Json(spells)
+}
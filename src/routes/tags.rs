@@ -0,0 +1,70 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use askama::Template;
+
+use super::AppState;
+use crate::models::{slugify, Rule, RuleFilter, Tag, TagSummary};
+
+#[derive(Template)]
+#[template(path = "tags/index.html")]
+struct TagsIndexTemplate {
+    title: String,
+    tags: Vec<TagSummary>,
+}
+
+#[derive(Template)]
+#[template(path = "tags/show.html")]
+struct TagTemplate {
+    title: String,
+    tag: Tag,
+    rules: Vec<Rule>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/tags", get(list_tags))
+        .route("/tags/:tag", get(show_tag))
+}
+
+async fn list_tags(State(state): State<AppState>, headers: HeaderMap) -> Html<String> {
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+    let template = TagsIndexTemplate {
+        title: "Tags".to_string(),
+        tags: crate::db::get_tags(&state.db, is_admin).await.unwrap_or_default(),
+    };
+    Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string()))
+}
+
+async fn show_tag(
+    State(state): State<AppState>,
+    Path(tag): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let tag = match crate::db::get_tag(&state.db, &slugify(&tag)).await {
+        Ok(Some(tag)) => tag,
+        _ => return super::not_found("Tag Not Found", "No rules are tagged with that tag."),
+    };
+
+    let is_admin = super::admin::is_authenticated(&headers, &state.config.admin_api_key);
+    let filter = RuleFilter {
+        tags: vec![tag.slug.clone()],
+        ..RuleFilter::default()
+    }
+    .with_homebrew(None, is_admin);
+    let mut rules = state.rules.list(&filter).await.unwrap_or_default();
+    crate::db::attach_tags(&state.db, &mut rules).await.ok();
+    crate::db::attach_equipment(&state.db, &mut rules).await.ok();
+    crate::db::attach_collections(&state.db, &mut rules).await.ok();
+
+    let template = TagTemplate {
+        title: format!("#{}", tag.name),
+        tag,
+        rules,
+    };
+    Html(template.render().unwrap_or_else(|_| "Error rendering template".to_string())).into_response()
+}
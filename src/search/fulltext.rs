@@ -1,28 +1,130 @@
-use sqlx::SqlitePool;
-use crate::models::Rule;
+use super::query::{ParsedQuery, QueryTerm};
+use crate::db::RuleRepository;
+use crate::models::{normalize_phrase, Rule, RuleFilter, SearchHighlight, Synonym};
 
-/// Search rules using SQLite FTS5 full-text search
-pub async fn search(pool: &SqlitePool, query: &str) -> Result<Vec<Rule>, sqlx::Error> {
-    // Escape special FTS5 characters and prepare query
-    let sanitized = sanitize_fts_query(query);
+/// Most hits `/search` and `/api/search` collect; pages and facet counts
+/// are cut from these
+pub const MAX_SEARCH_HITS: i32 = 500;
 
-    if sanitized.is_empty() {
+/// Search rules using SQLite FTS5 full-text search. Plain query words with
+/// a synonym ("oa") are also searched for its expansion ("opportunity
+/// attack"), under the query's phrases and `title:` terms. The query's field
+/// qualifiers must already be applied to `filter`.
+pub async fn search(
+    rules: &dyn RuleRepository,
+    synonyms: &[Synonym],
+    query: &ParsedQuery,
+    limit: i32,
+    filter: &RuleFilter,
+) -> Result<Vec<Rule>, sqlx::Error> {
+    let Some(expression) = query.fts_expression() else {
         return Ok(vec![]);
+    };
+
+    let direct = rules.search(&expression, limit, filter).await?;
+    let plain_words = query.any.iter().map(|term| term.words.join(" ")).collect::<Vec<_>>().join(" ");
+    let expansions: Vec<QueryTerm> = expand_synonyms(&plain_words, synonyms)
+        .into_iter()
+        .map(|expansion| QueryTerm {
+            words: expansion.split(' ').map(str::to_string).collect(),
+            prefix: false,
+        })
+        .collect();
+    let mut results = match query.fts_expression_with(&expansions).filter(|_| !expansions.is_empty()) {
+        Some(expression) => {
+            let via_synonym = rules.search(&expression, limit, filter).await?;
+            merge_synonym_hits(direct, via_synonym, limit.max(0) as usize)
+        }
+        None => direct,
+    };
+
+    let mut words = query.text();
+    for expansion in &expansions {
+        words.push(' ');
+        words.push_str(&expansion.words.join(" "));
+    }
+    highlight_unmarked(&mut results, &words);
+    Ok(results)
+}
+
+/// Substring search with the same synonym expansion as [`search`]
+pub async fn fuzzy_search(
+    rules: &dyn RuleRepository,
+    synonyms: &[Synonym],
+    query: &str,
+    limit: i32,
+    filter: &RuleFilter,
+) -> Result<Vec<Rule>, sqlx::Error> {
+    let direct = rules.fuzzy_search(query, limit, filter).await?;
+
+    let mut words = query.to_string();
+    let mut via_synonym = Vec::new();
+    for expansion in expand_synonyms(query, synonyms) {
+        via_synonym.extend(rules.fuzzy_search(&expansion, limit, filter).await?);
+        words.push(' ');
+        words.push_str(&expansion);
+    }
+
+    let mut results = merge_synonym_hits(direct, via_synonym, limit.max(0) as usize);
+    highlight_unmarked(&mut results, &words);
+    Ok(results)
+}
+
+/// Mark the words of `text` in results the repository returned without
+/// FTS5 highlights
+pub fn highlight_unmarked(results: &mut [Rule], text: &str) {
+    let terms: Vec<String> = normalize_phrase(text).split(' ').map(str::to_string).collect();
+    for rule in results.iter_mut().filter(|rule| rule.highlight.is_none()) {
+        rule.highlight = SearchHighlight::for_terms(&rule.title, &rule.content, &terms);
+    }
+}
+
+/// Expansions of the synonym terms in `query`. Multi-word terms win over
+/// the single words inside them, and expansions the query already spells
+/// out are skipped.
+pub fn expand_synonyms(query: &str, synonyms: &[Synonym]) -> Vec<String> {
+    let normalized = normalize_phrase(query);
+    let words: Vec<&str> = normalized.split(' ').filter(|word| !word.is_empty()).collect();
+    let padded = format!(" {} ", normalized);
+    let mut expansions: Vec<String> = Vec::new();
+
+    let mut start = 0;
+    while start < words.len() {
+        let matched = (start + 1..=words.len()).rev().find_map(|end| {
+            let phrase = words[start..end].join(" ");
+            let found: Vec<&Synonym> = synonyms.iter().filter(|s| s.term == phrase).collect();
+            (!found.is_empty()).then_some((end, found))
+        });
+
+        match matched {
+            Some((end, found)) => {
+                for synonym in found {
+                    let spelled_out = padded.contains(&format!(" {} ", synonym.expansion));
+                    if !spelled_out && !expansions.contains(&synonym.expansion) {
+                        expansions.push(synonym.expansion.clone());
+                    }
+                }
+                start = end;
+            }
+            None => start += 1,
+        }
     }
 
-    crate::db::search_rules_fts(pool, &sanitized).await
+    expansions
 }
 
-/// Sanitize query for FTS5
-fn sanitize_fts_query(query: &str) -> String {
-    // Remove special FTS5 operators for basic search
-    query
-        .chars()
-        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
-        .collect::<String>()
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" OR ")
+/// Merge direct hits with hits found through synonyms. Rules found only
+/// through a synonym rank after every direct hit, in their synonym order; a
+/// rule found both ways keeps its direct position.
+fn merge_synonym_hits(mut direct: Vec<Rule>, via_synonym: Vec<Rule>, limit: usize) -> Vec<Rule> {
+    for rule in via_synonym {
+        if !direct.iter().any(|found| found.id == rule.id) {
+            direct.push(rule);
+        }
+    }
+
+    direct.truncate(limit);
+    direct
 }
 
 #[cfg(test)]
@@ -30,8 +132,42 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_sanitize_fts_query() {
-        assert_eq!(sanitize_fts_query("attack action"), "attack OR action");
-        assert_eq!(sanitize_fts_query("AC (armor class)"), "AC OR armor OR class");
+    fn test_expand_synonyms() {
+        let synonyms = vec![
+            Synonym::new("ac", "armor class").unwrap(),
+            Synonym::new("oa", "opportunity attack").unwrap(),
+            Synonym::new("conc", "concentration").unwrap(),
+            Synonym::new("conc save", "concentration saving throw").unwrap(),
+        ];
+
+        assert_eq!(expand_synonyms("AC vs OA?", &synonyms), vec!["armor class", "opportunity attack"]);
+        assert_eq!(expand_synonyms("conc save DC", &synonyms), vec!["concentration saving throw"]);
+        assert_eq!(expand_synonyms("ac (armor class)", &synonyms), Vec::<String>::new());
+        assert!(expand_synonyms("acrobatics", &synonyms).is_empty());
+    }
+
+    #[tokio::test]
+    async fn synonym_hits_rank_after_direct_hits() {
+        use crate::db::InMemoryRuleRepository;
+
+        let repository = InMemoryRuleRepository::new().with_rules([
+            Rule::test("dexterity", "Dexterity", "Dexterity adds to your AC."),
+            Rule::test("shields", "Shields", "A Shield adds 2 to your AC."),
+            Rule::test("armor", "Armor", "Armor sets your Armor Class."),
+            Rule::test("cover", "Cover", "Cover adds to armor rolls."),
+        ]);
+        let synonyms = vec![Synonym::new("ac", "armor class").unwrap()];
+        let filter = RuleFilter::default();
+        let ids = |rules: Vec<Rule>| rules.into_iter().map(|r| r.id).collect::<Vec<_>>();
+
+        let query = ParsedQuery::parse("AC").unwrap();
+        let found = search(&repository, &synonyms, &query, 20, &filter).await.unwrap();
+        assert_eq!(ids(found), vec!["dexterity", "shields", "armor"]);
+
+        let found = search(&repository, &[], &query, 20, &filter).await.unwrap();
+        assert_eq!(ids(found), vec!["dexterity", "shields"]);
+
+        let found = fuzzy_search(&repository, &synonyms, "ac", 10, &filter).await.unwrap();
+        assert_eq!(ids(found), vec!["dexterity", "shields", "armor"]);
     }
 }
@@ -1,56 +1,236 @@
 use sqlx::SqlitePool;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    config::VectorSearchConfig,
-    models::Rule,
+    config::{EmbeddingProvider, VectorBackend, VectorSearchConfig},
+    db::RuleRepository,
+    models::{Rule, RuleFilter},
     search::{
+        local_embeddings::LocalEmbeddingClient,
         openai_embeddings::OpenAiEmbeddingClient,
         qdrant::QdrantVectorIndex,
-        vector::{EmbeddingClient, VectorIndex, VectorSearchError},
+        sqlite_vectors::SqliteVectorIndex,
+        vector::{EmbeddingClient, EmbeddingError, VectorHit, VectorIndex, VectorSearchError},
     },
 };
 
+/// The embedding client `VectorSearchConfig::embedding_provider` selects
+#[derive(Clone)]
+pub enum ConfiguredEmbeddingClient {
+    OpenAi(OpenAiEmbeddingClient),
+    Local(LocalEmbeddingClient),
+}
+
+impl ConfiguredEmbeddingClient {
+    /// Fails with `NotConfigured` when OpenAI is selected without an API key
+    pub fn from_config(vector_config: &VectorSearchConfig) -> Result<Self, VectorSearchError> {
+        match vector_config.embedding_provider {
+            EmbeddingProvider::OpenAi => {
+                let api_key = vector_config
+                    .openai_api_key
+                    .clone()
+                    .ok_or(VectorSearchError::NotConfigured)?;
+                Ok(Self::OpenAi(OpenAiEmbeddingClient::new(
+                    api_key,
+                    vector_config.openai_embedding_model.clone(),
+                    vector_config.openai_embedding_dimension,
+                )))
+            }
+            EmbeddingProvider::Local => Ok(Self::Local(LocalEmbeddingClient::new(
+                vector_config.local_embedding_dimension,
+            ))),
+        }
+    }
+}
+
+impl EmbeddingClient for ConfiguredEmbeddingClient {
+    async fn embed(
+        &self,
+        input: &str,
+    ) -> Result<Vec<f32>, EmbeddingError> {
+        match self {
+            Self::OpenAi(client) => client.embed(input).await,
+            Self::Local(client) => client.embed(input).await,
+        }
+    }
+}
+
+/// The vector index `VectorSearchConfig::backend` selects
+#[derive(Clone)]
+pub enum ConfiguredVectorIndex {
+    Qdrant(QdrantVectorIndex),
+    Sqlite(SqliteVectorIndex),
+}
+
+impl ConfiguredVectorIndex {
+    /// The index sized for the configured embeddings; the SQLite backend
+    /// keeps its vectors in `pool`
+    pub fn from_config(vector_config: &VectorSearchConfig, pool: &SqlitePool) -> Self {
+        let dimension = vector_config.embedding_dimension();
+        match vector_config.backend {
+            VectorBackend::Qdrant => Self::Qdrant(QdrantVectorIndex::new(
+                vector_config.qdrant_url.clone(),
+                vector_config.qdrant_collection.clone(),
+                dimension,
+            )),
+            VectorBackend::Sqlite => Self::Sqlite(SqliteVectorIndex::new(pool.clone(), dimension)),
+        }
+    }
+}
+
+impl VectorIndex for ConfiguredVectorIndex {
+    async fn ensure_collection(&self) -> Result<(), VectorSearchError> {
+        match self {
+            Self::Qdrant(index) => index.ensure_collection().await,
+            Self::Sqlite(index) => index.ensure_collection().await,
+        }
+    }
+
+    async fn upsert_rule(
+        &self,
+        rule: &Rule,
+        vector: Vec<f32>,
+    ) -> Result<(), VectorSearchError> {
+        match self {
+            Self::Qdrant(index) => index.upsert_rule(rule, vector).await,
+            Self::Sqlite(index) => index.upsert_rule(rule, vector).await,
+        }
+    }
+
+    async fn search(
+        &self,
+        vector: Vec<f32>,
+        limit: usize,
+    ) -> Result<Vec<VectorHit>, VectorSearchError> {
+        match self {
+            Self::Qdrant(index) => index.search(vector, limit).await,
+            Self::Sqlite(index) => index.search(vector, limit).await,
+        }
+    }
+
+    async fn delete_rule(
+        &self,
+        rule_id: &str,
+    ) -> Result<(), VectorSearchError> {
+        match self {
+            Self::Qdrant(index) => index.delete_rule(rule_id).await,
+            Self::Sqlite(index) => index.delete_rule(rule_id).await,
+        }
+    }
+
+    async fn clear(&self) -> Result<(), VectorSearchError> {
+        match self {
+            Self::Qdrant(index) => index.clear().await,
+            Self::Sqlite(index) => index.clear().await,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ScoredRule {
     pub rule: Rule,
+    /// What the rule is ranked by: the fused score after
+    /// [`fuse_rankings`], the vector similarity for a plain semantic hit
     pub score: f32,
+    /// 1-based position in the full-text ranking
+    pub fts_rank: Option<usize>,
+    /// 1-based position among the vector hits above the score threshold
+    pub vector_rank: Option<usize>,
+    /// Similarity reported by the vector index
+    pub vector_score: Option<f32>,
+}
+
+/// How the full-text and vector rankings are combined into one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fusion {
+    /// Reciprocal Rank Fusion: every ranking a rule appears in adds
+    /// `1 / (k + rank)`, so a rule both rankings agree on beats the top hit
+    /// of either alone
+    Rrf { k: f32 },
+    /// Weighted sum of the per-source scores: the full-text position scaled
+    /// from 1 for the first hit down to `1 / n` for the last, and the vector
+    /// similarity
+    Weighted { fts_weight: f32, vector_weight: f32 },
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Fusion::Rrf { k: 60.0 }
+    }
+}
+
+impl Fusion {
+    fn score(&self, fts_rank: Option<usize>, fts_hits: usize, vector_rank: Option<usize>, vector_score: Option<f32>) -> f32 {
+        match *self {
+            Fusion::Rrf { k } => [fts_rank, vector_rank]
+                .into_iter()
+                .flatten()
+                .map(|rank| 1.0 / (k + rank as f32))
+                .sum(),
+            Fusion::Weighted {
+                fts_weight,
+                vector_weight,
+            } => {
+                let fts = fts_rank.map_or(0.0, |rank| (fts_hits - rank + 1) as f32 / fts_hits as f32);
+                fts_weight * fts + vector_weight * vector_score.unwrap_or(0.0)
+            }
+        }
+    }
 }
 
 pub async fn retrieve_oracle_rules(
     pool: &SqlitePool,
+    rules: &dyn RuleRepository,
     vector_config: &VectorSearchConfig,
     query: &str,
+    filter: &RuleFilter,
 ) -> Vec<Rule> {
-    let fts_rules = crate::search::fulltext::search(pool, query)
+    let synonyms = crate::db::get_synonyms(pool).await.unwrap_or_default();
+    let question = crate::search::query::ParsedQuery::plain(query);
+    let limit = vector_config.oracle_max_context_rules;
+    let fts_rules = crate::search::fulltext::search(rules, &synonyms, &question, limit as i32, filter)
         .await
         .unwrap_or_default();
 
-    if !vector_config.enabled {
-        return fts_rules
-            .into_iter()
-            .take(vector_config.oracle_max_context_rules)
-            .collect();
-    }
+    hybrid_rank(pool, rules, vector_config, query, fts_rules, filter, limit)
+        .await
+        .into_iter()
+        .map(|scored| scored.rule)
+        .collect()
+}
 
-    let vector_rules = match semantic_search(pool, vector_config, query).await {
-        Ok(rules) => rules,
+/// Rank full-text hits (best first) together with the semantic hits for
+/// `query` that pass `filter`, fused as `vector_config.fusion` says. The
+/// full-text hits keep their order when vector search is disabled or fails.
+pub async fn hybrid_rank(
+    pool: &SqlitePool,
+    rules: &dyn RuleRepository,
+    vector_config: &VectorSearchConfig,
+    query: &str,
+    fts_rules: Vec<Rule>,
+    filter: &RuleFilter,
+    limit: usize,
+) -> Vec<ScoredRule> {
+    let vector_rules = match semantic_search(pool, rules, vector_config, query).await {
+        Ok(found) => retain_allowed_rules(pool, rules, found, filter).await,
         Err(error) => {
-            tracing::warn!("Vector Oracle retrieval failed; falling back to FTS5: {}", error);
+            tracing::warn!("Vector retrieval failed; ranking FTS5 hits only: {}", error);
             Vec::new()
         }
     };
 
-    merge_oracle_results(
+    fuse_rankings(
         fts_rules,
         vector_rules,
+        vector_config.fusion,
         vector_config.score_threshold,
-        vector_config.oracle_max_context_rules,
+        limit,
     )
 }
 
 pub async fn semantic_search(
     pool: &SqlitePool,
+    rules: &dyn RuleRepository,
     vector_config: &VectorSearchConfig,
     query: &str,
 ) -> Result<Vec<ScoredRule>, VectorSearchError> {
@@ -58,24 +238,11 @@ pub async fn semantic_search(
         return Ok(vec![]);
     }
 
-    let api_key = vector_config
-        .openai_api_key
-        .clone()
-        .ok_or(VectorSearchError::NotConfigured)?;
-
-    let embedding_client = OpenAiEmbeddingClient::new(
-        api_key,
-        vector_config.openai_embedding_model.clone(),
-        vector_config.openai_embedding_dimension,
-    );
-    let vector_index = QdrantVectorIndex::new(
-        vector_config.qdrant_url.clone(),
-        vector_config.qdrant_collection.clone(),
-        vector_config.openai_embedding_dimension,
-    );
+    let embedding_client = ConfiguredEmbeddingClient::from_config(vector_config)?;
+    let vector_index = ConfiguredVectorIndex::from_config(vector_config, pool);
 
     semantic_search_with_clients(
-        pool,
+        rules,
         query,
         vector_config.top_k,
         &embedding_client,
@@ -85,7 +252,7 @@ pub async fn semantic_search(
 }
 
 pub async fn semantic_search_with_clients<E, V>(
-    pool: &SqlitePool,
+    repository: &dyn RuleRepository,
     query: &str,
     limit: usize,
     embedding_client: &E,
@@ -100,10 +267,13 @@ where
     let mut rules = Vec::with_capacity(hits.len());
 
     for hit in hits {
-        match crate::db::get_rule_by_id(pool, &hit.rule_id).await {
+        match repository.get(&hit.rule_id).await {
             Ok(Some(rule)) => rules.push(ScoredRule {
                 rule,
                 score: hit.score,
+                fts_rank: None,
+                vector_rank: Some(rules.len() + 1),
+                vector_score: Some(hit.score),
             }),
             Ok(None) => {
                 tracing::warn!("Qdrant returned unknown rule id '{}'", hit.rule_id);
@@ -117,40 +287,105 @@ where
     Ok(rules)
 }
 
-pub fn merge_oracle_results(
+/// Drop semantic hits `filter` excludes. Source, homebrew and placement are
+/// checked here; tags and excluded words through a filtered listing.
+async fn retain_allowed_rules(
+    pool: &SqlitePool,
+    repository: &dyn RuleRepository,
+    rules: Vec<ScoredRule>,
+    filter: &RuleFilter,
+) -> Vec<ScoredRule> {
+    if filter.is_empty() && !rules.iter().any(|scored| scored.rule.is_homebrew()) {
+        return rules;
+    }
+
+    let sources = crate::db::get_sources(pool).await.unwrap_or_default();
+    let collections = crate::db::get_homebrew_collections(pool).await.unwrap_or_default();
+    let listed: Option<HashSet<String>> = if filter.tags.is_empty() && filter.excluded_terms.is_empty() {
+        None
+    } else {
+        let listing = repository.list(filter).await.unwrap_or_default();
+        Some(listing.into_iter().map(|rule| rule.id).collect())
+    };
+
+    rules
+        .into_iter()
+        .filter(|scored| {
+            let source = scored
+                .rule
+                .source_id
+                .as_deref()
+                .and_then(|id| sources.iter().find(|source| source.abbreviation == id));
+            filter.matches_source(source)
+                && filter.matches_collection(scored.rule.collection_id.as_deref(), &collections)
+                && filter.matches_placement(&scored.rule.category, scored.rule.subcategory.as_deref(), scored.rule.page)
+                && listed.as_ref().is_none_or(|ids| ids.contains(&scored.rule.id))
+        })
+        .collect()
+}
+
+/// Fuse the full-text ranking (best first) with vector hits at or above
+/// `score_threshold` into one ranking of at most `limit` rules. Each rule
+/// keeps its rank in both and its vector similarity; ties keep full-text
+/// hits first.
+pub fn fuse_rankings(
     fts_rules: Vec<Rule>,
     vector_rules: Vec<ScoredRule>,
+    fusion: Fusion,
     score_threshold: f32,
     limit: usize,
-) -> Vec<Rule> {
-    let mut seen = HashSet::new();
-    let mut merged = Vec::new();
+) -> Vec<ScoredRule> {
+    let mut fused: Vec<ScoredRule> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
 
     for rule in fts_rules {
-        if seen.insert(rule.id.clone()) {
-            merged.push(rule);
-        }
-
-        if merged.len() >= limit {
-            return merged;
+        if positions.contains_key(&rule.id) {
+            continue;
         }
+        positions.insert(rule.id.clone(), fused.len());
+        fused.push(ScoredRule {
+            rule,
+            score: 0.0,
+            fts_rank: Some(fused.len() + 1),
+            vector_rank: None,
+            vector_score: None,
+        });
     }
+    let fts_hits = fused.len();
 
-    for scored_rule in vector_rules {
-        if scored_rule.score < score_threshold {
+    let mut vector_rank = 0;
+    for scored in vector_rules {
+        let similarity = scored.vector_score.unwrap_or(scored.score);
+        if similarity < score_threshold {
             continue;
         }
-
-        if seen.insert(scored_rule.rule.id.clone()) {
-            merged.push(scored_rule.rule);
-        }
-
-        if merged.len() >= limit {
-            break;
+        match positions.get(&scored.rule.id) {
+            Some(&position) if fused[position].vector_rank.is_some() => continue,
+            Some(&position) => {
+                vector_rank += 1;
+                fused[position].vector_rank = Some(vector_rank);
+                fused[position].vector_score = Some(similarity);
+            }
+            None => {
+                vector_rank += 1;
+                positions.insert(scored.rule.id.clone(), fused.len());
+                fused.push(ScoredRule {
+                    rule: scored.rule,
+                    score: 0.0,
+                    fts_rank: None,
+                    vector_rank: Some(vector_rank),
+                    vector_score: Some(similarity),
+                });
+            }
         }
     }
 
-    merged
+    for scored in &mut fused {
+        scored.score = fusion.score(scored.fts_rank, fts_hits, scored.vector_rank, scored.vector_score);
+    }
+    fused.sort_by(|a, b| b.score.total_cmp(&a.score));
+    fused.truncate(limit);
+    fused
 }
 
 #[cfg(test)]
@@ -159,77 +394,101 @@ mod tests {
 
     fn rule(id: &str, title: &str) -> Rule {
         Rule {
-            id: id.to_string(),
-            title: title.to_string(),
-            category: "Combat".to_string(),
-            subcategory: None,
-            content: "Rule text".to_string(),
-            source: "Player's Handbook 2024".to_string(),
-            page: None,
-            created_at: "now".to_string(),
-            updated_at: "now".to_string(),
+            source_id: Some("PHB".to_string()),
+            ..Rule::test(id, title, "Rule text")
         }
     }
 
     fn scored(rule: Rule, score: f32) -> ScoredRule {
-        ScoredRule { rule, score }
+        ScoredRule {
+            rule,
+            score,
+            fts_rank: None,
+            vector_rank: None,
+            vector_score: Some(score),
+        }
+    }
+
+    fn ids(fused: &[ScoredRule]) -> Vec<&str> {
+        fused.iter().map(|scored| scored.rule.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn local_provider_embeds_without_an_api_key() {
+        let mut config = VectorSearchConfig::from_env();
+        config.openai_api_key = None;
+        config.embedding_provider = EmbeddingProvider::OpenAi;
+        assert!(matches!(ConfiguredEmbeddingClient::from_config(&config), Err(VectorSearchError::NotConfigured)));
+
+        config.embedding_provider = EmbeddingProvider::Local;
+        config.local_embedding_dimension = 128;
+        let client = ConfiguredEmbeddingClient::from_config(&config).unwrap();
+        assert_eq!(client.embed("grappled condition").await.unwrap().len(), config.embedding_dimension());
     }
 
     #[test]
-    fn merge_keeps_fts_results_before_semantic_results() {
-        let merged = merge_oracle_results(
-            vec![rule("attack-action", "Attack Action")],
-            vec![scored(rule("grappled", "Grappled"), 0.92)],
+    fn rrf_ranks_rules_both_searches_found_first() {
+        let fused = fuse_rankings(
+            vec![rule("attack-action", "Attack Action"), rule("shove", "Shove"), rule("grappled", "Grappled")],
+            vec![scored(rule("grappled", "Grappled"), 0.92), scored(rule("escape", "Escaping a Grapple"), 0.8)],
+            Fusion::default(),
             0.35,
             10,
         );
 
-        let ids: Vec<_> = merged.into_iter().map(|rule| rule.id).collect();
-        assert_eq!(ids, vec!["attack-action", "grappled"]);
+        assert_eq!(ids(&fused), vec!["grappled", "attack-action", "shove", "escape"]);
+        let grappled = &fused[0];
+        assert_eq!((grappled.fts_rank, grappled.vector_rank, grappled.vector_score), (Some(3), Some(1), Some(0.92)));
+        assert!((grappled.score - (1.0 / 63.0 + 1.0 / 61.0)).abs() < 1e-6);
     }
 
     #[test]
-    fn merge_dedupes_vector_rules_already_found_by_fts() {
-        let merged = merge_oracle_results(
-            vec![rule("grappled", "Grappled")],
-            vec![
-                scored(rule("grappled", "Grappled"), 0.95),
-                scored(rule("escape", "Escaping a Grapple"), 0.9),
-            ],
+    fn weighted_fusion_lets_strong_semantic_hits_beat_weak_keyword_hits() {
+        let fusion = Fusion::Weighted {
+            fts_weight: 0.3,
+            vector_weight: 0.7,
+        };
+        let fused = fuse_rankings(
+            vec![rule("cover", "Cover"), rule("hiding", "Hiding")],
+            vec![scored(rule("invisible", "Invisible"), 0.9)],
+            fusion,
             0.35,
             10,
         );
 
-        let ids: Vec<_> = merged.into_iter().map(|rule| rule.id).collect();
-        assert_eq!(ids, vec!["grappled", "escape"]);
+        assert_eq!(ids(&fused), vec!["invisible", "cover", "hiding"]);
+        assert_eq!(fused[2].fts_rank, Some(2));
+        assert!((fused[2].score - 0.15).abs() < 1e-6);
     }
 
     #[test]
-    fn merge_excludes_low_score_vector_results() {
-        let merged = merge_oracle_results(
-            vec![],
+    fn fusion_dedupes_and_excludes_low_score_vector_results() {
+        let fused = fuse_rankings(
+            vec![rule("grappled", "Grappled"), rule("grappled", "Grappled")],
             vec![
                 scored(rule("cover", "Cover"), 0.7),
+                scored(rule("cover", "Cover"), 0.6),
                 scored(rule("swimming", "Swimming"), 0.2),
             ],
+            Fusion::default(),
             0.35,
             10,
         );
 
-        let ids: Vec<_> = merged.into_iter().map(|rule| rule.id).collect();
-        assert_eq!(ids, vec!["cover"]);
+        assert_eq!(ids(&fused), vec!["grappled", "cover"]);
+        assert_eq!((fused[1].vector_rank, fused[1].vector_score), (Some(1), Some(0.7)));
     }
 
     #[test]
-    fn merge_respects_context_limit() {
-        let merged = merge_oracle_results(
-            vec![rule("a", "A"), rule("b", "B")],
-            vec![scored(rule("c", "C"), 0.9)],
+    fn fusion_respects_limit_and_keeps_fts_order_without_vector_hits() {
+        let fused = fuse_rankings(
+            vec![rule("a", "A"), rule("b", "B"), rule("c", "C")],
+            Vec::new(),
+            Fusion::default(),
             0.35,
             2,
         );
 
-        let ids: Vec<_> = merged.into_iter().map(|rule| rule.id).collect();
-        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(ids(&fused), vec!["a", "b"]);
     }
 }
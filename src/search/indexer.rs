@@ -1,11 +1,20 @@
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+
 use crate::{
-    models::Rule,
-    search::vector::{rule_to_embedding_text, EmbeddingClient, VectorIndex, VectorSearchError},
+    config::{VectorBackend, VectorSearchConfig},
+    models::{Rule, VectorRecord},
+    search::vector::{rule_to_embedding_text, stable_hash, EmbeddingClient, VectorIndex, VectorSearchError},
 };
 
 #[derive(Debug, Default, PartialEq)]
 pub struct IndexReport {
-    pub indexed: usize,
+    /// Rules embedded because they are new or their text, model or index changed
+    pub changed: usize,
+    /// Rules whose vectors were already up to date
+    pub unchanged: usize,
+    /// Vectors deleted because their rule no longer exists
+    pub removed: usize,
     pub failed: Vec<IndexFailure>,
 }
 
@@ -15,32 +24,127 @@ pub struct IndexFailure {
     pub error: String,
 }
 
+/// What vectors are made with and how a run goes
+#[derive(Debug, Clone)]
+pub struct IndexOptions {
+    /// Recorded with each vector; rules embedded by another model are embedded again
+    pub embedding_model: String,
+    /// Recorded with each vector, so moving to another index embeds everything again
+    pub vector_index: String,
+    /// Clear the index and embed every rule
+    pub full: bool,
+    /// Stop on the first rule that fails
+    pub fail_fast: bool,
+}
+
+impl IndexOptions {
+    /// Incremental, error-tolerant indexing with the configured model and index
+    pub fn from_config(vector_config: &VectorSearchConfig) -> Self {
+        let vector_index = match vector_config.backend {
+            VectorBackend::Qdrant => format!("qdrant:{}", vector_config.qdrant_collection),
+            VectorBackend::Sqlite => "sqlite".to_string(),
+        };
+
+        Self {
+            embedding_model: vector_config.embedding_model(),
+            vector_index,
+            full: false,
+            fail_fast: false,
+        }
+    }
+
+    fn record(&self, rule_id: &str, content_hash: String) -> VectorRecord {
+        VectorRecord {
+            rule_id: rule_id.to_string(),
+            content_hash,
+            embedding_model: self.embedding_model.clone(),
+            vector_index: self.vector_index.clone(),
+        }
+    }
+}
+
+/// The work an indexing run has to do
+#[derive(Debug, Default, PartialEq)]
+pub struct IndexPlan {
+    /// IDs of the rules to embed
+    pub changed: Vec<String>,
+    pub unchanged: usize,
+    /// IDs of recorded rules that no longer exist
+    pub removed: Vec<String>,
+}
+
+/// Hash of the text embedded for a rule
+pub fn content_hash(rule: &Rule) -> String {
+    format!("{:016x}", stable_hash(rule_to_embedding_text(rule).as_bytes()))
+}
+
+/// Compare `rules` with what `records` say the index holds
+pub fn plan_index(rules: &[Rule], records: &[VectorRecord], options: &IndexOptions) -> IndexPlan {
+    let recorded: HashMap<&str, &VectorRecord> = records.iter().map(|record| (record.rule_id.as_str(), record)).collect();
+    let mut plan = IndexPlan::default();
+
+    for rule in rules {
+        let current = recorded.get(rule.id.as_str()).is_some_and(|record| {
+            **record == options.record(&rule.id, content_hash(rule))
+        });
+        if current && !options.full {
+            plan.unchanged += 1;
+        } else {
+            plan.changed.push(rule.id.clone());
+        }
+    }
+
+    let live: HashSet<&str> = rules.iter().map(|rule| rule.id.as_str()).collect();
+    plan.removed = records
+        .iter()
+        .filter(|record| !live.contains(record.rule_id.as_str()))
+        .map(|record| record.rule_id.clone())
+        .collect();
+
+    plan
+}
+
+/// Bring the vector index in line with `rules`: embed the rules whose text,
+/// model or index changed since the records in `pool` were written (every
+/// rule, into a cleared index, with `options.full` or when nothing is
+/// recorded yet) and delete the vectors of recorded rules that are gone.
+/// Records are updated rule by rule, so an interrupted run resumes where it
+/// stopped.
 pub async fn index_rules<E, V>(
+    pool: &SqlitePool,
     rules: &[Rule],
     embedding_client: &E,
     vector_index: &V,
-    fail_fast: bool,
+    options: &IndexOptions,
 ) -> Result<IndexReport, VectorSearchError>
 where
     E: EmbeddingClient,
     V: VectorIndex,
 {
+    let database_error = |e: sqlx::Error| VectorSearchError::SearchError(e.to_string());
+
     vector_index.ensure_collection().await?;
+    let mut records = crate::db::get_vector_records(pool).await.map_err(database_error)?;
 
-    let mut report = IndexReport::default();
+    // Without records every rule is embedded anyway, and vectors written
+    // before records were kept cannot be told apart from orphans
+    if options.full || records.is_empty() {
+        vector_index.clear().await?;
+        crate::db::clear_vector_records(pool).await.map_err(database_error)?;
+        records.clear();
+    }
 
-    for rule in rules {
-        let text = rule_to_embedding_text(rule);
-        let result = async {
-            let embedding = embedding_client.embed(&text).await?;
-            vector_index.upsert_rule(rule, embedding).await?;
-            Ok::<(), VectorSearchError>(())
-        }
-        .await;
+    let plan = plan_index(rules, &records, options);
+    let mut report = IndexReport {
+        unchanged: plan.unchanged,
+        ..IndexReport::default()
+    };
 
-        match result {
-            Ok(()) => report.indexed += 1,
-            Err(error) if fail_fast => return Err(error),
+    let changed: HashSet<&str> = plan.changed.iter().map(String::as_str).collect();
+    for rule in rules.iter().filter(|rule| changed.contains(rule.id.as_str())) {
+        match index_rule(pool, rule, embedding_client, vector_index, options).await {
+            Ok(()) => report.changed += 1,
+            Err(error) if options.fail_fast => return Err(error),
             Err(error) => report.failed.push(IndexFailure {
                 rule_id: rule.id.clone(),
                 error: error.to_string(),
@@ -48,30 +152,68 @@ where
         }
     }
 
+    for rule_id in plan.removed {
+        match unindex_rule(pool, &rule_id, vector_index).await {
+            Ok(()) => report.removed += 1,
+            Err(error) if options.fail_fast => return Err(error),
+            Err(error) => report.failed.push(IndexFailure {
+                rule_id,
+                error: error.to_string(),
+            }),
+        }
+    }
+
     Ok(report)
 }
 
+/// Embed one rule, store its vector and record it; a rule waiting in the
+/// sync retry queue leaves it
+pub async fn index_rule<E, V>(
+    pool: &SqlitePool,
+    rule: &Rule,
+    embedding_client: &E,
+    vector_index: &V,
+    options: &IndexOptions,
+) -> Result<(), VectorSearchError>
+where
+    E: EmbeddingClient,
+    V: VectorIndex,
+{
+    let embedding = embedding_client.embed(&rule_to_embedding_text(rule)).await?;
+    vector_index.upsert_rule(rule, embedding).await?;
+    let database_error = |e: sqlx::Error| VectorSearchError::SearchError(e.to_string());
+    crate::db::upsert_vector_record(pool, &options.record(&rule.id, content_hash(rule)))
+        .await
+        .map_err(database_error)?;
+    crate::db::dequeue_vector_sync(pool, &rule.id).await.map_err(database_error)
+}
+
+/// Delete the vector of a rule that is gone, with its record and any
+/// queued sync retry
+pub async fn unindex_rule<V>(
+    pool: &SqlitePool,
+    rule_id: &str,
+    vector_index: &V,
+) -> Result<(), VectorSearchError>
+where
+    V: VectorIndex,
+{
+    let database_error = |e: sqlx::Error| VectorSearchError::SearchError(e.to_string());
+    vector_index.delete_rule(rule_id).await?;
+    crate::db::delete_vector_record(pool, rule_id).await.map_err(database_error)?;
+    crate::db::dequeue_vector_sync(pool, rule_id).await.map_err(database_error)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::search::vector::{EmbeddingError, VectorHit};
-    use std::{
-        collections::HashSet,
-        future::Future,
-        sync::{Arc, Mutex},
-    };
+    use std::sync::{Arc, Mutex};
 
     fn rule(id: &str) -> Rule {
         Rule {
-            id: id.to_string(),
-            title: format!("Rule {id}"),
-            category: "Combat".to_string(),
-            subcategory: None,
-            content: "Rule text".to_string(),
-            source: "Player's Handbook 2024".to_string(),
-            page: None,
-            created_at: "now".to_string(),
-            updated_at: "now".to_string(),
+            source_id: Some("PHB".to_string()),
+            ..Rule::test(id, &format!("Rule {id}"), "Rule text")
         }
     }
 
@@ -81,23 +223,21 @@ mod tests {
     }
 
     impl EmbeddingClient for MockEmbeddingClient {
-        fn embed<'a>(
-            &'a self,
-            input: &'a str,
-        ) -> impl Future<Output = Result<Vec<f32>, EmbeddingError>> + Send + 'a {
-            async move {
-                self.calls.lock().unwrap().push(input.to_string());
-
-                if self
-                    .fail_ids
-                    .iter()
-                    .any(|id| input.contains(&format!("Title: Rule {id}")))
-                {
-                    return Err(EmbeddingError::RequestError("forced failure".to_string()));
-                }
-
-                Ok(vec![0.1, 0.2, 0.3])
+        async fn embed(
+            &self,
+            input: &str,
+        ) -> Result<Vec<f32>, EmbeddingError> {
+            self.calls.lock().unwrap().push(input.to_string());
+
+            if self
+                .fail_ids
+                .iter()
+                .any(|id| input.contains(&format!("Title: Rule {id}")))
+            {
+                return Err(EmbeddingError::RequestError("forced failure".to_string()));
             }
+
+            Ok(vec![0.1, 0.2, 0.3])
         }
     }
 
@@ -105,80 +245,160 @@ mod tests {
     struct MockVectorIndex {
         ensured: Arc<Mutex<usize>>,
         upserted: Arc<Mutex<Vec<String>>>,
+        deleted: Arc<Mutex<Vec<String>>>,
+        cleared: Arc<Mutex<usize>>,
     }
 
     impl VectorIndex for MockVectorIndex {
-        fn ensure_collection(&self) -> impl Future<Output = Result<(), VectorSearchError>> + Send + '_ {
-            async move {
-                *self.ensured.lock().unwrap() += 1;
-                Ok(())
-            }
+        async fn ensure_collection(&self) -> Result<(), VectorSearchError> {
+            *self.ensured.lock().unwrap() += 1;
+            Ok(())
         }
 
-        fn upsert_rule<'a>(
-            &'a self,
-            rule: &'a Rule,
+        async fn upsert_rule(
+            &self,
+            rule: &Rule,
             _vector: Vec<f32>,
-        ) -> impl Future<Output = Result<(), VectorSearchError>> + Send + 'a {
-            async move {
-                self.upserted.lock().unwrap().push(rule.id.clone());
-                Ok(())
-            }
+        ) -> Result<(), VectorSearchError> {
+            self.upserted.lock().unwrap().push(rule.id.clone());
+            Ok(())
         }
 
-        fn search(
+        async fn search(
             &self,
             _vector: Vec<f32>,
             _limit: usize,
-        ) -> impl Future<Output = Result<Vec<VectorHit>, VectorSearchError>> + Send + '_ {
-            async { Ok(vec![]) }
+        ) -> Result<Vec<VectorHit>, VectorSearchError> {
+            Ok(vec![])
+        }
+
+        async fn delete_rule(&self, rule_id: &str) -> Result<(), VectorSearchError> {
+            self.deleted.lock().unwrap().push(rule_id.to_string());
+            Ok(())
         }
 
-        fn delete_rule<'a>(
-            &'a self,
-            _rule_id: &'a str,
-        ) -> impl Future<Output = Result<(), VectorSearchError>> + Send + 'a {
-            async { Ok(()) }
+        async fn clear(&self) -> Result<(), VectorSearchError> {
+            *self.cleared.lock().unwrap() += 1;
+            Ok(())
+        }
+    }
+
+    fn options() -> IndexOptions {
+        IndexOptions {
+            embedding_model: "mock-embeddings".to_string(),
+            vector_index: "mock".to_string(),
+            full: false,
+            fail_fast: false,
+        }
+    }
+
+    fn client(fail_ids: &[&str]) -> MockEmbeddingClient {
+        MockEmbeddingClient {
+            fail_ids: fail_ids.iter().map(|id| id.to_string()).collect(),
+            calls: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
     #[tokio::test]
     async fn index_rules_embeds_and_upserts_each_rule() {
-        let calls = Arc::new(Mutex::new(Vec::new()));
-        let embedding_client = MockEmbeddingClient {
-            fail_ids: HashSet::new(),
-            calls: Arc::clone(&calls),
-        };
+        let pool = crate::db::test_pool().await;
+        let embedding_client = client(&[]);
         let vector_index = MockVectorIndex::default();
         let rules = vec![rule("a"), rule("b")];
 
-        let report = index_rules(&rules, &embedding_client, &vector_index, false)
+        let report = index_rules(&pool, &rules, &embedding_client, &vector_index, &options())
             .await
             .unwrap();
 
-        assert_eq!(report.indexed, 2);
+        assert_eq!(report.changed, 2);
         assert!(report.failed.is_empty());
         assert_eq!(*vector_index.ensured.lock().unwrap(), 1);
         assert_eq!(*vector_index.upserted.lock().unwrap(), vec!["a", "b"]);
-        assert_eq!(calls.lock().unwrap().len(), 2);
+        assert_eq!(embedding_client.calls.lock().unwrap().len(), 2);
+
+        let records = crate::db::get_vector_records(&pool).await.unwrap();
+        assert_eq!(records[0], options().record("a", content_hash(&rules[0])));
     }
 
     #[tokio::test]
     async fn index_rules_reports_failed_ids_without_aborting() {
-        let embedding_client = MockEmbeddingClient {
-            fail_ids: HashSet::from(["b".to_string()]),
-            calls: Arc::new(Mutex::new(Vec::new())),
-        };
+        let pool = crate::db::test_pool().await;
         let vector_index = MockVectorIndex::default();
         let rules = vec![rule("a"), rule("b"), rule("c")];
 
-        let report = index_rules(&rules, &embedding_client, &vector_index, false)
+        let report = index_rules(&pool, &rules, &client(&["b"]), &vector_index, &options())
             .await
             .unwrap();
 
-        assert_eq!(report.indexed, 2);
+        assert_eq!(report.changed, 2);
         assert_eq!(report.failed.len(), 1);
         assert_eq!(report.failed[0].rule_id, "b");
         assert_eq!(*vector_index.upserted.lock().unwrap(), vec!["a", "c"]);
+
+        // The failed rule is retried on the next run
+        let retry = index_rules(&pool, &rules, &client(&[]), &vector_index, &options()).await.unwrap();
+        assert_eq!((retry.changed, retry.unchanged), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn reindexing_embeds_only_changed_rules_and_removes_deleted_ones() {
+        let pool = crate::db::test_pool().await;
+        let mut rules = vec![rule("a"), rule("b"), rule("c")];
+        index_rules(&pool, &rules, &client(&[]), &MockVectorIndex::default(), &options()).await.unwrap();
+
+        rules.remove(0);
+        rules[0].content = "Revised rule text".to_string();
+        let embedding_client = client(&[]);
+        let vector_index = MockVectorIndex::default();
+        let report = index_rules(&pool, &rules, &embedding_client, &vector_index, &options()).await.unwrap();
+
+        assert_eq!((report.changed, report.unchanged, report.removed), (1, 1, 1));
+        assert_eq!(*vector_index.upserted.lock().unwrap(), vec!["b"]);
+        assert_eq!(*vector_index.deleted.lock().unwrap(), vec!["a"]);
+        let recorded: Vec<String> = crate::db::get_vector_records(&pool).await.unwrap().into_iter().map(|r| r.rule_id).collect();
+        assert_eq!(recorded, vec!["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn model_changes_and_full_runs_embed_everything() {
+        let pool = crate::db::test_pool().await;
+        let rules = vec![rule("a"), rule("b")];
+        index_rules(&pool, &rules, &client(&[]), &MockVectorIndex::default(), &options()).await.unwrap();
+
+        let new_model = IndexOptions {
+            embedding_model: "other-embeddings".to_string(),
+            ..options()
+        };
+        let plan = plan_index(&rules, &crate::db::get_vector_records(&pool).await.unwrap(), &new_model);
+        assert_eq!((plan.changed.len(), plan.unchanged), (2, 0));
+
+        let vector_index = MockVectorIndex::default();
+        let full = IndexOptions {
+            full: true,
+            ..options()
+        };
+        let report = index_rules(&pool, &rules, &client(&[]), &vector_index, &full).await.unwrap();
+        assert_eq!((report.changed, report.unchanged, report.removed), (2, 0, 0));
+        assert_eq!(*vector_index.cleared.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn first_run_without_records_drops_unrecorded_vectors() {
+        use crate::search::sqlite_vectors::SqliteVectorIndex;
+
+        let pool = crate::db::test_pool().await;
+        crate::db::upsert_rule_vector(&pool, "deleted-long-ago", &[0.1, 0.2, 0.3]).await.unwrap();
+        let rules = vec![rule("a"), rule("b")];
+        let vector_index = SqliteVectorIndex::new(pool.clone(), 3);
+
+        let report = index_rules(&pool, &rules, &client(&[]), &vector_index, &options()).await.unwrap();
+        assert_eq!(report.changed, 2);
+        let stored: Vec<String> = crate::db::get_rule_vectors(&pool, 3).await.unwrap().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(stored, vec!["a", "b"]);
+
+        // Once records exist, incremental runs leave the index alone
+        let mock_index = MockVectorIndex::default();
+        index_rules(&pool, &rules, &client(&[]), &mock_index, &options()).await.unwrap();
+        assert_eq!(*mock_index.cleared.lock().unwrap(), 0);
     }
 }
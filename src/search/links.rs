@@ -0,0 +1,438 @@
+//! Cross-references between rules.
+//!
+//! Two kinds of edges are stored in `rule_links`: explicit `[[rule:id]]`
+//! (or `[[rule:id|label]]`) links written into rule Markdown, and mentions of
+//! another rule's title or alias found in the rule text.
+
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{LinkKind, Rule, RuleLink};
+
+const LINK_OPEN: &str = "[[rule:";
+const LINK_CLOSE: &str = "]]";
+
+/// Aliases shorter than this are too likely to be ordinary words
+const MIN_ALIAS_LEN: usize = 4;
+
+/// An explicit `[[rule:id]]` link pointing at a rule that does not exist
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrokenLink {
+    pub rule_id: String,
+    pub target_id: String,
+}
+
+#[derive(Debug, Default)]
+pub struct LinkReport {
+    pub links: usize,
+    pub broken: Vec<BrokenLink>,
+}
+
+/// One `[[rule:id|label]]` occurrence, with its byte range in the Markdown
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplicitLink {
+    pub start: usize,
+    pub end: usize,
+    pub rule_id: String,
+    pub label: Option<String>,
+}
+
+/// Every well-formed explicit link in `content`, in order
+pub fn explicit_links(content: &str) -> Vec<ExplicitLink> {
+    let mut links = Vec::new();
+    let mut offset = 0;
+
+    while let Some(open) = content[offset..].find(LINK_OPEN) {
+        let start = offset + open;
+        let inner_start = start + LINK_OPEN.len();
+        let Some(close) = content[inner_start..].find(LINK_CLOSE) else {
+            break;
+        };
+        let end = inner_start + close + LINK_CLOSE.len();
+        let inner = &content[inner_start..inner_start + close];
+
+        let (rule_id, label) = match inner.split_once('|') {
+            Some((id, label)) => (id.trim(), Some(label.trim().to_string())),
+            None => (inner.trim(), None),
+        };
+
+        if !rule_id.is_empty() && !rule_id.contains(char::is_whitespace) {
+            links.push(ExplicitLink {
+                start,
+                end,
+                rule_id: rule_id.to_string(),
+                label: label.filter(|l| !l.is_empty()),
+            });
+        }
+
+        offset = end;
+    }
+
+    links
+}
+
+/// Replace explicit links with Markdown links to the rule pages.
+///
+/// `titles` maps rule IDs to titles; links to unknown rules are rendered as
+/// their label (or ID) in plain text.
+pub fn render_explicit_links(content: &str, titles: &HashMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(content.len());
+    let mut last = 0;
+
+    for link in explicit_links(content) {
+        rendered.push_str(&content[last..link.start]);
+
+        match titles.get(&link.rule_id) {
+            Some(title) => {
+                let text = escape_markdown(link.label.as_deref().unwrap_or(title));
+                rendered.push_str(&format!("[{}](/rules/{})", text, link.rule_id));
+            }
+            None => rendered.push_str(&escape_markdown(link.label.as_deref().unwrap_or(&link.rule_id))),
+        }
+
+        last = link.end;
+    }
+
+    rendered.push_str(&content[last..]);
+    rendered
+}
+
+/// Point `[[rule:id]]` links at renamed rules' current IDs. `renamed` maps
+/// former IDs to current ones; labels are kept.
+pub fn retarget_explicit_links(content: &str, renamed: &HashMap<String, String>) -> String {
+    let mut retargeted = String::with_capacity(content.len());
+    let mut last = 0;
+
+    for link in explicit_links(content) {
+        let Some(current) = renamed.get(&link.rule_id) else {
+            continue;
+        };
+        retargeted.push_str(&content[last..link.start]);
+        match &link.label {
+            Some(label) => retargeted.push_str(&format!("[[rule:{}|{}]]", current, label)),
+            None => retargeted.push_str(&format!("[[rule:{}]]", current)),
+        }
+        last = link.end;
+    }
+
+    retargeted.push_str(&content[last..]);
+    retargeted
+}
+
+/// Names a rule may be mentioned by: its title, the title without a trailing
+/// "Condition" or parenthetical, and each half of "X and Y" titles
+pub fn rule_aliases(title: &str) -> Vec<String> {
+    let title = title.trim();
+    let mut aliases = vec![title.to_string()];
+
+    let base = match title.find(" (") {
+        Some(paren) => title[..paren].trim(),
+        None => title,
+    };
+    aliases.push(base.to_string());
+
+    if let Some(stripped) = base.strip_suffix(" Condition") {
+        aliases.push(stripped.to_string());
+    }
+
+    if let Some((left, right)) = base.split_once(" and ") {
+        aliases.push(left.to_string());
+        aliases.push(right.to_string());
+    }
+
+    let mut unique: Vec<String> = Vec::new();
+    for alias in aliases {
+        let alias = alias.to_lowercase();
+        if alias.chars().count() >= MIN_ALIAS_LEN && !unique.contains(&alias) {
+            unique.push(alias);
+        }
+    }
+    unique
+}
+
+/// Links from `rule` to the other `rules`: explicit links first, then any
+/// rule whose title or alias appears in the text as a whole word
+pub fn find_links(rule: &Rule, rules: &[Rule]) -> Vec<RuleLink> {
+    let known: HashSet<&str> = rules.iter().map(|r| r.id.as_str()).collect();
+    let mut links: Vec<RuleLink> = Vec::new();
+
+    for link in explicit_links(&rule.content) {
+        if link.rule_id != rule.id
+            && known.contains(link.rule_id.as_str())
+            && !links.iter().any(|l| l.target_id == link.rule_id)
+        {
+            links.push(RuleLink {
+                source_id: rule.id.clone(),
+                target_id: link.rule_id,
+                kind: LinkKind::Explicit,
+            });
+        }
+    }
+
+    let text = strip_explicit_links(&rule.content).to_lowercase();
+
+    for target in rules {
+        if target.id == rule.id || links.iter().any(|l| l.target_id == target.id) {
+            continue;
+        }
+
+        if rule_aliases(&target.title)
+            .iter()
+            .any(|alias| contains_word(&text, alias))
+        {
+            links.push(RuleLink {
+                source_id: rule.id.clone(),
+                target_id: target.id.clone(),
+                kind: LinkKind::Mention,
+            });
+        }
+    }
+
+    links
+}
+
+/// The link from `source` to `target`, if `source` links to it explicitly
+/// or mentions one of `target_aliases` (see [`rule_aliases`])
+fn link_between(source: &Rule, target: &Rule, target_aliases: &[String]) -> Option<RuleLink> {
+    if source.id == target.id {
+        return None;
+    }
+
+    let kind = if explicit_links(&source.content).iter().any(|link| link.rule_id == target.id) {
+        LinkKind::Explicit
+    } else {
+        let text = strip_explicit_links(&source.content).to_lowercase();
+        if !target_aliases.iter().any(|alias| contains_word(&text, alias)) {
+            return None;
+        }
+        LinkKind::Mention
+    };
+
+    Some(RuleLink {
+        source_id: source.id.clone(),
+        target_id: target.id.clone(),
+        kind,
+    })
+}
+
+/// Explicit links in any of `rules` whose target is not among `rules`
+pub fn find_broken_links(rules: &[Rule]) -> Vec<BrokenLink> {
+    let known: HashSet<&str> = rules.iter().map(|r| r.id.as_str()).collect();
+
+    rules.iter().flat_map(|rule| broken_links(rule, &known)).collect()
+}
+
+fn broken_links(rule: &Rule, known: &HashSet<&str>) -> Vec<BrokenLink> {
+    explicit_links(&rule.content)
+        .into_iter()
+        .filter(|link| !known.contains(link.rule_id.as_str()))
+        .map(|link| BrokenLink {
+            rule_id: rule.id.clone(),
+            target_id: link.rule_id,
+        })
+        .collect()
+}
+
+/// Rebuild `rule_links` from the current rules and report broken explicit links
+pub async fn index_rule_links(pool: &SqlitePool) -> Result<LinkReport, sqlx::Error> {
+    let rules = crate::db::get_all_rules(pool).await?;
+    let links: Vec<RuleLink> = rules
+        .iter()
+        .flat_map(|rule| find_links(rule, &rules))
+        .collect();
+
+    crate::db::replace_rule_links(pool, &links).await?;
+
+    Ok(LinkReport {
+        links: links.len(),
+        broken: find_broken_links(&rules),
+    })
+}
+
+/// Re-index the links from and to one rule after it was saved, trashed,
+/// restored, renamed or purged, leaving links between other rules alone.
+/// Incoming links are those of rules that link to its ID or mention its
+/// title. Reports the broken explicit links of this rule only.
+pub async fn index_links_of_rule(pool: &SqlitePool, rule_id: &str) -> Result<LinkReport, sqlx::Error> {
+    let rules = crate::db::get_all_rules(pool).await?;
+    let Some(rule) = rules.iter().find(|rule| rule.id == rule_id) else {
+        crate::db::replace_links_of_rule(pool, rule_id, &[]).await?;
+        return Ok(LinkReport::default());
+    };
+
+    let aliases = rule_aliases(&rule.title);
+    let mut links = find_links(rule, &rules);
+    links.extend(rules.iter().filter_map(|source| link_between(source, rule, &aliases)));
+
+    crate::db::replace_links_of_rule(pool, rule_id, &links).await?;
+
+    let known: HashSet<&str> = rules.iter().map(|r| r.id.as_str()).collect();
+    Ok(LinkReport {
+        links: links.len(),
+        broken: broken_links(rule, &known),
+    })
+}
+
+/// `text` with the characters that would end or restyle Markdown link text
+/// backslash-escaped
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn strip_explicit_links(content: &str) -> String {
+    let mut stripped = String::with_capacity(content.len());
+    let mut last = 0;
+
+    for link in explicit_links(content) {
+        stripped.push_str(&content[last..link.start]);
+        stripped.push(' ');
+        last = link.end;
+    }
+
+    stripped.push_str(&content[last..]);
+    stripped
+}
+
+/// Whether `needle` occurs in `haystack` not surrounded by letters or digits
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    haystack.match_indices(needle).any(|(start, _)| {
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[start + needle.len()..].chars().next();
+        !before.map(char::is_alphanumeric).unwrap_or(false)
+            && !after.map(char::is_alphanumeric).unwrap_or(false)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, title: &str, content: &str) -> Rule {
+        Rule::test(id, title, content)
+    }
+
+    #[test]
+    fn parses_explicit_links_with_labels() {
+        let links = explicit_links("See [[rule:prone|falling over]] and [[rule: grappled ]]. [[rule:bad id]] [[rule:open");
+
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].rule_id, "prone");
+        assert_eq!(links[0].label.as_deref(), Some("falling over"));
+        assert_eq!(links[1].rule_id, "grappled");
+        assert_eq!(links[1].label, None);
+    }
+
+    #[test]
+    fn renders_known_links_and_flattens_broken_ones() {
+        let titles = HashMap::from([("prone".to_string(), "Prone Condition".to_string())]);
+
+        assert_eq!(
+            render_explicit_links("Fall [[rule:prone]], then [[rule:missing|stand]].", &titles),
+            "Fall [Prone Condition](/rules/prone), then stand."
+        );
+    }
+
+    async fn edges(pool: &SqlitePool) -> Vec<(String, String)> {
+        sqlx::query_as("SELECT source_rule_id, target_rule_id FROM rule_links ORDER BY 1, 2")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn escapes_markdown_in_link_text() {
+        let titles = HashMap::from([("bonus".to_string(), "Bonus [Action] *Rules*".to_string())]);
+
+        assert_eq!(
+            render_explicit_links("Use a [[rule:bonus]] or [[rule:gone|a_b]].", &titles),
+            "Use a [Bonus \\[Action\\] \\*Rules\\*](/rules/bonus) or a\\_b."
+        );
+    }
+
+    #[tokio::test]
+    async fn indexing_one_rule_updates_its_outgoing_and_incoming_links() {
+        let pool = crate::db::test_pool().await;
+        for rule in [
+            rule("sneak-attack", "Sneak Attack", "Needs Advantage. See [[rule:cover]]."),
+            rule("cover", "Cover", "Walls and trees."),
+            rule("dash", "Dash", "Double your speed, unlike a Sneak Attack."),
+        ] {
+            crate::db::upsert_rule(&pool, &rule, "test").await.unwrap();
+        }
+        index_rule_links(&pool).await.unwrap();
+        let pair = |a: &str, b: &str| (a.to_string(), b.to_string());
+        assert_eq!(edges(&pool).await, vec![pair("dash", "sneak-attack"), pair("sneak-attack", "cover")]);
+
+        // A new rule picks up its own mentions and the rules that mention it
+        let advantage = rule("advantage", "Advantage", "Roll twice, then Dash.");
+        crate::db::upsert_rule(&pool, &advantage, "test").await.unwrap();
+        let report = index_links_of_rule(&pool, "advantage").await.unwrap();
+        assert_eq!(report.links, 2);
+        assert_eq!(
+            edges(&pool).await,
+            vec![pair("advantage", "dash"), pair("dash", "sneak-attack"), pair("sneak-attack", "advantage"), pair("sneak-attack", "cover")]
+        );
+
+        // A rule that is gone loses its links both ways
+        crate::db::purge_rule(&pool, "cover").await.unwrap();
+        index_links_of_rule(&pool, "cover").await.unwrap();
+        assert!(!edges(&pool).await.contains(&pair("sneak-attack", "cover")));
+    }
+
+    #[test]
+    fn aliases_drop_condition_suffix_and_split_pairs() {
+        assert_eq!(rule_aliases("Grappled Condition"), vec!["grappled condition", "grappled"]);
+        assert_eq!(
+            rule_aliases("Advantage and Disadvantage"),
+            vec!["advantage and disadvantage", "advantage", "disadvantage"]
+        );
+        assert_eq!(rule_aliases("Dash"), vec!["dash"]);
+    }
+
+    #[test]
+    fn finds_mentions_as_whole_words_and_explicit_links() {
+        let rules = vec![
+            rule("sneak-attack", "Sneak Attack", "Needs Advantage on the roll. See [[rule:cover]]."),
+            rule("adv-disadv", "Advantage and Disadvantage", "Roll twice."),
+            rule("cover", "Cover", "Walls and trees."),
+            rule("prone", "Prone Condition", "Discovering the prone creature."),
+        ];
+
+        let links = find_links(&rules[0], &rules);
+        let edges: Vec<_> = links.iter().map(|l| (l.target_id.as_str(), l.kind)).collect();
+        assert_eq!(edges, vec![("cover", LinkKind::Explicit), ("adv-disadv", LinkKind::Mention)]);
+
+        // "cover" inside "Discovering" is not a mention, and rules never link to themselves
+        assert!(find_links(&rules[3], &rules).is_empty());
+    }
+
+    #[test]
+    fn retargets_links_to_renamed_rules() {
+        let renamed = HashMap::from([("opportunity-attack".to_string(), "opportunity-attacks".to_string())]);
+
+        assert_eq!(
+            retarget_explicit_links("See [[rule:opportunity-attack]], [[rule:opportunity-attack|OAs]] and [[rule:reach]].", &renamed),
+            "See [[rule:opportunity-attacks]], [[rule:opportunity-attacks|OAs]] and [[rule:reach]]."
+        );
+    }
+
+    #[test]
+    fn reports_links_to_missing_rules() {
+        let rules = vec![rule("grappled", "Grappled Condition", "See [[rule:speed]] and [[rule:grappled]].")];
+
+        assert_eq!(
+            find_broken_links(&rules),
+            vec![BrokenLink {
+                rule_id: "grappled".to_string(),
+                target_id: "speed".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Embeddings computed on the machine itself, for servers without internet
+//! access.
+//!
+//! Text is turned into a hashing-vectorizer embedding: each word, each pair
+//! of neighbouring words and each character trigram of a word is hashed into
+//! one of `dimension` buckets with a hash-chosen sign, counts are damped
+//! logarithmically and the vector is scaled to unit length, so cosine
+//! similarity rewards shared words and spellings ("grapple" and "grappled"
+//! share most trigrams). Nothing is trained, so embeddings stay valid as
+//! rules are added and the index and queries always agree.
+
+use std::collections::HashMap;
+
+use crate::search::vector::{stable_hash, EmbeddingClient, EmbeddingError};
+
+/// Vector size when `LOCAL_EMBEDDING_DIMENSION` is not set
+pub const DEFAULT_LOCAL_DIMENSION: usize = 1024;
+
+/// `VECTOR_SCORE_THRESHOLD` default for local embeddings, whose
+/// similarities run lower than OpenAI's because only shared words and
+/// spellings count
+pub const DEFAULT_LOCAL_SCORE_THRESHOLD: f32 = 0.1;
+
+/// Weight of a word pair and of a character trigram relative to a word
+const PAIR_WEIGHT: f32 = 0.5;
+const TRIGRAM_WEIGHT: f32 = 0.5;
+
+/// Words too common in rules text to say anything about a rule
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "can", "do", "for", "from", "has", "have", "if", "in", "is",
+    "it", "its", "of", "on", "or", "that", "the", "their", "then", "this", "to", "was", "when", "which", "with",
+    "you", "your",
+];
+
+#[derive(Debug, Clone)]
+pub struct LocalEmbeddingClient {
+    dimension: usize,
+}
+
+impl LocalEmbeddingClient {
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension: dimension.max(1),
+        }
+    }
+
+    /// The embedding of `input`; [`EmbeddingError::EmptyInput`] when it has
+    /// no words other than stop words
+    pub fn vectorize(&self, input: &str) -> Result<Vec<f32>, EmbeddingError> {
+        let words: Vec<String> = input
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+            .collect();
+        if words.is_empty() {
+            return Err(EmbeddingError::EmptyInput);
+        }
+
+        let mut features: HashMap<String, f32> = HashMap::new();
+        for word in &words {
+            *features.entry(format!("w:{word}")).or_default() += 1.0;
+
+            let chars: Vec<char> = format!("<{word}>").chars().collect();
+            for trigram in chars.windows(3) {
+                *features.entry(format!("c:{}", trigram.iter().collect::<String>())).or_default() += TRIGRAM_WEIGHT;
+            }
+        }
+        for pair in words.windows(2) {
+            *features.entry(format!("p:{} {}", pair[0], pair[1])).or_default() += PAIR_WEIGHT;
+        }
+
+        let mut vector = vec![0.0f32; self.dimension];
+        for (feature, count) in features {
+            let hash = stable_hash(feature.as_bytes());
+            let bucket = (hash % self.dimension as u64) as usize;
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[bucket] += sign * count.ln_1p();
+        }
+
+        let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+        if norm == 0.0 {
+            return Err(EmbeddingError::EmptyInput);
+        }
+        vector.iter_mut().for_each(|value| *value /= norm);
+
+        Ok(vector)
+    }
+}
+
+impl EmbeddingClient for LocalEmbeddingClient {
+    async fn embed(
+        &self,
+        input: &str,
+    ) -> Result<Vec<f32>, EmbeddingError> {
+        self.vectorize(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[tokio::test]
+    async fn embeddings_are_unit_length_and_deterministic() {
+        let client = LocalEmbeddingClient::new(256);
+
+        let first = client.embed("Grappled creatures have a speed of 0").await.unwrap();
+        let second = client.embed("Grappled creatures have a speed of 0").await.unwrap();
+
+        assert_eq!(first.len(), 256);
+        assert_eq!(first, second);
+        assert!((cosine(&first, &first) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn related_texts_are_closer_than_unrelated_ones() {
+        let client = LocalEmbeddingClient::new(DEFAULT_LOCAL_DIMENSION);
+        let grapple = client.vectorize("Title: Grappled\nContent: A grappled creature's speed is 0.").unwrap();
+        let question = client.vectorize("can a grappling monster move its speed").unwrap();
+        let swimming = client.vectorize("Title: Swimming\nContent: Each foot of swimming costs extra movement.").unwrap();
+
+        assert!(cosine(&grapple, &question) > cosine(&swimming, &question));
+    }
+
+    #[test]
+    fn stop_words_alone_are_empty_input() {
+        let client = LocalEmbeddingClient::new(64);
+
+        assert!(matches!(client.vectorize("  "), Err(EmbeddingError::EmptyInput)));
+        assert!(matches!(client.vectorize("the and of"), Err(EmbeddingError::EmptyInput)));
+    }
+}
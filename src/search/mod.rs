@@ -1,6 +1,12 @@
 pub mod fulltext;
 pub mod hybrid;
 pub mod indexer;
+pub mod links;
+pub mod local_embeddings;
 pub mod openai_embeddings;
 pub mod qdrant;
+pub mod query;
+pub mod spelling;
+pub mod sqlite_vectors;
 pub mod vector;
+pub mod vector_sync;
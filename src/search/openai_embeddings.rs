@@ -1,6 +1,5 @@
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::future::Future;
 
 use crate::search::vector::{EmbeddingClient, EmbeddingError};
 
@@ -26,56 +25,54 @@ impl OpenAiEmbeddingClient {
 }
 
 impl EmbeddingClient for OpenAiEmbeddingClient {
-    fn embed<'a>(
-        &'a self,
-        input: &'a str,
-    ) -> impl Future<Output = Result<Vec<f32>, EmbeddingError>> + Send + 'a {
-        async move {
-            let input = input.trim();
-            if input.is_empty() {
-                return Err(EmbeddingError::EmptyInput);
-            }
+    async fn embed(
+        &self,
+        input: &str,
+    ) -> Result<Vec<f32>, EmbeddingError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(EmbeddingError::EmptyInput);
+        }
 
-            if self.api_key.trim().is_empty() {
-                return Err(EmbeddingError::MissingApiKey);
-            }
+        if self.api_key.trim().is_empty() {
+            return Err(EmbeddingError::MissingApiKey);
+        }
 
-            let request = OpenAiEmbeddingRequest {
-                model: self.model.clone(),
-                input: input.to_string(),
-            };
-
-            let response = self
-                .client
-                .post(OPENAI_EMBEDDINGS_URL)
-                .bearer_auth(&self.api_key)
-                .json(&request)
-                .send()
-                .await
-                .map_err(|e| EmbeddingError::RequestError(e.to_string()))?;
-
-            if !response.status().is_success() {
-                let status = response.status();
-                let body = response.text().await.unwrap_or_default();
-                return Err(EmbeddingError::RequestError(format!("{}: {}", status, body)));
-            }
+        let request = OpenAiEmbeddingRequest {
+            model: self.model.clone(),
+            input: input.to_string(),
+        };
+
+        let response = self
+            .client
+            .post(OPENAI_EMBEDDINGS_URL)
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| EmbeddingError::RequestError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(EmbeddingError::RequestError(format!("{}: {}", status, body)));
+        }
 
-            let response: OpenAiEmbeddingResponse = response
-                .json()
-                .await
-                .map_err(|e| EmbeddingError::ParseError(e.to_string()))?;
+        let response: OpenAiEmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| EmbeddingError::ParseError(e.to_string()))?;
 
-            let embedding = response
-                .data
-                .into_iter()
-                .next()
-                .map(|item| item.embedding)
-                .ok_or(EmbeddingError::EmptyResponse)?;
+        let embedding = response
+            .data
+            .into_iter()
+            .next()
+            .map(|item| item.embedding)
+            .ok_or(EmbeddingError::EmptyResponse)?;
 
-            validate_embedding_dimension(&embedding, self.expected_dimension)?;
+        validate_embedding_dimension(&embedding, self.expected_dimension)?;
 
-            Ok(embedding)
-        }
+        Ok(embedding)
     }
 }
 
@@ -1,7 +1,6 @@
 use reqwest::{Client, StatusCode};
 use serde::Deserialize;
 use serde_json::{json, Value};
-use std::future::Future;
 
 use crate::{
     models::Rule,
@@ -36,152 +35,162 @@ impl QdrantVectorIndex {
 }
 
 impl VectorIndex for QdrantVectorIndex {
-    fn ensure_collection(&self) -> impl Future<Output = Result<(), VectorSearchError>> + Send + '_ {
-        async move {
-            let body = json!({
-                "vectors": {
-                    "size": self.dimension,
-                    "distance": "Cosine"
-                }
-            });
-
-            let response = self
-                .client
-                .put(self.collection_url())
-                .json(&body)
-                .send()
-                .await
-                .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
-
-            if response.status() == StatusCode::CONFLICT {
-                tracing::info!("Qdrant collection '{}' already exists", self.collection);
-                return Ok(());
-            }
-
-            if !response.status().is_success() {
-                let status = response.status();
-                let body = response.text().await.unwrap_or_default();
-                return Err(VectorSearchError::SearchError(format!("{}: {}", status, body)));
+    async fn ensure_collection(&self) -> Result<(), VectorSearchError> {
+        let body = json!({
+            "vectors": {
+                "size": self.dimension,
+                "distance": "Cosine"
             }
+        });
+
+        let response = self
+            .client
+            .put(self.collection_url())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
+
+        if response.status() == StatusCode::CONFLICT {
+            tracing::info!("Qdrant collection '{}' already exists", self.collection);
+            return Ok(());
+        }
 
-            Ok(())
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(VectorSearchError::SearchError(format!("{}: {}", status, body)));
         }
+
+        Ok(())
     }
 
-    fn upsert_rule<'a>(
-        &'a self,
-        rule: &'a Rule,
+    async fn upsert_rule(
+        &self,
+        rule: &Rule,
         vector: Vec<f32>,
-    ) -> impl Future<Output = Result<(), VectorSearchError>> + Send + 'a {
-        async move {
-            if vector.len() != self.dimension {
-                return Err(VectorSearchError::SearchError(format!(
-                    "vector dimension mismatch: expected {}, got {}",
-                    self.dimension,
-                    vector.len()
-                )));
-            }
-
-            let body = json!({
-                "points": [{
-                    "id": point_id_for_rule_id(&rule.id),
-                    "vector": vector,
-                    "payload": {
-                        "rule_id": rule.id,
-                        "category": rule.category,
-                        "source": rule.source,
-                        "page": rule.page
-                    }
-                }]
-            });
-
-            let response = self
-                .client
-                .put(format!("{}?wait=true", self.points_url()))
-                .json(&body)
-                .send()
-                .await
-                .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
-
-            if !response.status().is_success() {
-                let status = response.status();
-                let body = response.text().await.unwrap_or_default();
-                return Err(VectorSearchError::SearchError(format!("{}: {}", status, body)));
-            }
+    ) -> Result<(), VectorSearchError> {
+        if vector.len() != self.dimension {
+            return Err(VectorSearchError::SearchError(format!(
+                "vector dimension mismatch: expected {}, got {}",
+                self.dimension,
+                vector.len()
+            )));
+        }
 
-            Ok(())
+        let body = json!({
+            "points": [{
+                "id": point_id_for_rule_id(&rule.id),
+                "vector": vector,
+                "payload": {
+                    "rule_id": rule.id,
+                    "category": rule.category,
+                    "source": rule.source,
+                    "page": rule.page
+                }
+            }]
+        });
+
+        let response = self
+            .client
+            .put(format!("{}?wait=true", self.points_url()))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(VectorSearchError::SearchError(format!("{}: {}", status, body)));
         }
+
+        Ok(())
     }
 
-    fn search(
+    async fn search(
         &self,
         vector: Vec<f32>,
         limit: usize,
-    ) -> impl Future<Output = Result<Vec<VectorHit>, VectorSearchError>> + Send + '_ {
-        async move {
-            if vector.len() != self.dimension {
-                return Err(VectorSearchError::SearchError(format!(
-                    "query vector dimension mismatch: expected {}, got {}",
-                    self.dimension,
-                    vector.len()
-                )));
-            }
+    ) -> Result<Vec<VectorHit>, VectorSearchError> {
+        if vector.len() != self.dimension {
+            return Err(VectorSearchError::SearchError(format!(
+                "query vector dimension mismatch: expected {}, got {}",
+                self.dimension,
+                vector.len()
+            )));
+        }
 
-            let body = json!({
-                "vector": vector,
-                "limit": limit,
-                "with_payload": true,
-                "with_vector": false
-            });
-
-            let response = self
-                .client
-                .post(format!("{}/search", self.points_url()))
-                .json(&body)
-                .send()
-                .await
-                .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
-
-            if !response.status().is_success() {
-                let status = response.status();
-                let body = response.text().await.unwrap_or_default();
-                return Err(VectorSearchError::SearchError(format!("{}: {}", status, body)));
-            }
+        let body = json!({
+            "vector": vector,
+            "limit": limit,
+            "with_payload": true,
+            "with_vector": false
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/search", self.points_url()))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(VectorSearchError::SearchError(format!("{}: {}", status, body)));
+        }
 
-            let response: QdrantSearchResponse = response
-                .json()
-                .await
-                .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
+        let response: QdrantSearchResponse = response
+            .json()
+            .await
+            .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
 
-            Ok(hits_from_search_response(response))
-        }
+        Ok(hits_from_search_response(response))
     }
 
-    fn delete_rule<'a>(
-        &'a self,
-        rule_id: &'a str,
-    ) -> impl Future<Output = Result<(), VectorSearchError>> + Send + 'a {
-        async move {
-            let body = json!({
-                "points": [point_id_for_rule_id(rule_id)]
-            });
-
-            let response = self
-                .client
-                .post(format!("{}/delete?wait=true", self.points_url()))
-                .json(&body)
-                .send()
-                .await
-                .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
-
-            if !response.status().is_success() {
-                let status = response.status();
-                let body = response.text().await.unwrap_or_default();
-                return Err(VectorSearchError::SearchError(format!("{}: {}", status, body)));
-            }
+    async fn delete_rule(
+        &self,
+        rule_id: &str,
+    ) -> Result<(), VectorSearchError> {
+        let body = json!({
+            "points": [point_id_for_rule_id(rule_id)]
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/delete?wait=true", self.points_url()))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(VectorSearchError::SearchError(format!("{}: {}", status, body)));
+        }
+
+        Ok(())
+    }
 
-            Ok(())
+    /// Delete the whole collection and create it again empty
+    async fn clear(&self) -> Result<(), VectorSearchError> {
+        let response = self
+            .client
+            .delete(self.collection_url())
+            .send()
+            .await
+            .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
+
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(VectorSearchError::SearchError(format!("{}: {}", status, body)));
         }
+
+        self.ensure_collection().await
     }
 }
 
@@ -271,6 +280,16 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    #[ignore = "needs a running Qdrant at QDRANT_URL"]
+    async fn qdrant_index_passes_vector_index_checks() {
+        let url = std::env::var("QDRANT_URL").unwrap_or_else(|_| "http://localhost:6333".to_string());
+        let collection = format!("rulecraft_test_{}", std::process::id());
+        let index = QdrantVectorIndex::new(url, collection, 4);
+
+        crate::search::vector::check_vector_index(&index, 4).await;
+    }
+
     #[test]
     fn qdrant_conflict_status_is_available_for_idempotent_collection_setup() {
         assert_eq!(StatusCode::CONFLICT.as_u16(), 409);
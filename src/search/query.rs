@@ -0,0 +1,419 @@
+//! The search box query language.
+//!
+//! Plain words match any of them, ranked by FTS5. On top of that:
+//!
+//! | Syntax | Meaning |
+//! |--------|---------|
+//! | `"bonus action"` | the phrase must appear |
+//! | `grap*` | words starting with `grap` |
+//! | `-spell`, `-"bonus action"` | leave out rules containing it |
+//! | `title:grapple` | the title must contain it |
+//! | `category:combat` | only that category (`-category:` leaves it out) |
+//! | `subcategory:actions` | only that subcategory |
+//! | `source:PHB` | only that source (`-source:` leaves it out) |
+//! | `page:195` | only rules on that page |
+//! | `tag:reaction` | only rules carrying the tag |
+//!
+//! A query is turned into an FTS5 MATCH expression built from quoted strings
+//! only, so user input can never inject FTS5 operators, plus [`RuleFilter`]
+//! constraints that become SQL conditions.
+
+use crate::models::{normalize_phrase, slugify, RuleFilter};
+
+/// Field qualifiers the query language knows
+const FIELDS: [&str; 6] = ["title", "category", "subcategory", "source", "page", "tag"];
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum QueryError {
+    #[error("Unclosed quote: add a closing \" after the phrase.")]
+    UnclosedQuote,
+
+    #[error("Unknown field \"{0}:\". Use title:, category:, subcategory:, source:, page: or tag:.")]
+    UnknownField(String),
+
+    #[error("\"{0}:\" needs a value, e.g. {0}:{example}.", example = field_example(.0))]
+    MissingValue(String),
+
+    #[error("page: takes a page number, not \"{0}\".")]
+    InvalidPage(String),
+
+    #[error("\"*\" must follow the start of a word, e.g. grap*.")]
+    LoneWildcard,
+
+    #[error("\"-{0}:\" is not supported; only words, phrases, category: and source: can be excluded.")]
+    CannotExclude(String),
+
+    #[error("Add something to search for besides the excluded words.")]
+    OnlyExclusions,
+}
+
+fn field_example(field: &str) -> &'static str {
+    match field {
+        "title" => "grapple",
+        "category" => "combat",
+        "subcategory" => "actions",
+        "source" => "PHB",
+        "page" => "195",
+        _ => "reaction",
+    }
+}
+
+/// A word or phrase; `prefix` makes its last word match any word it starts
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryTerm {
+    pub words: Vec<String>,
+    pub prefix: bool,
+}
+
+impl QueryTerm {
+    /// The term as a quoted FTS5 string, e.g. `"bonus action"` or `"grap"*`
+    pub fn to_fts(&self) -> String {
+        format!("\"{}\"{}", self.words.join(" "), if self.prefix { "*" } else { "" })
+    }
+}
+
+/// A citation such as "PHB 195" or "dmg p. 12"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Citation {
+    /// Upper-case source abbreviation
+    pub source: String,
+    pub page: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedQuery {
+    /// Plain words, any of which may match
+    pub any: Vec<QueryTerm>,
+    /// Quoted phrases, all of which must match
+    pub required: Vec<QueryTerm>,
+    /// `title:` terms, all of which must match the title
+    pub title: Vec<QueryTerm>,
+    /// `-word` and `-"phrase"` terms
+    pub excluded: Vec<QueryTerm>,
+    pub categories: Vec<String>,
+    pub excluded_categories: Vec<String>,
+    pub subcategories: Vec<String>,
+    pub sources: Vec<String>,
+    pub excluded_sources: Vec<String>,
+    pub pages: Vec<i32>,
+    pub tags: Vec<String>,
+    /// Set when the whole query reads like a citation
+    pub citation: Option<Citation>,
+}
+
+impl ParsedQuery {
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let mut parsed = Self {
+            citation: parse_citation(query),
+            ..Self::default()
+        };
+
+        for token in tokenize(query)? {
+            parsed.add(token)?;
+        }
+
+        if parsed.fts_expression().is_none() && !parsed.has_filters() && !parsed.excluded.is_empty() {
+            return Err(QueryError::OnlyExclusions);
+        }
+        Ok(parsed)
+    }
+
+    /// Every word of `text` as a plain word, operators and all; for Oracle
+    /// questions, which are prose rather than queries
+    pub fn plain(text: &str) -> Self {
+        Self {
+            any: normalize_phrase(text)
+                .split(' ')
+                .filter(|word| !word.is_empty())
+                .map(|word| QueryTerm {
+                    words: vec![word.to_string()],
+                    prefix: false,
+                })
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// The FTS5 MATCH expression for the words, phrases and `title:` terms,
+    /// or None when the query has none. Required parts are joined with AND;
+    /// plain words form one OR group.
+    pub fn fts_expression(&self) -> Option<String> {
+        self.fts_expression_with(&self.any)
+    }
+
+    /// Like [`Self::fts_expression`] with `alternatives` in place of the plain
+    /// words, for searching synonym expansions under the same constraints
+    pub fn fts_expression_with(&self, alternatives: &[QueryTerm]) -> Option<String> {
+        let mut parts: Vec<String> = self.required.iter().map(QueryTerm::to_fts).collect();
+        parts.extend(self.title.iter().map(|term| format!("title:{}", term.to_fts())));
+
+        let group: Vec<String> = alternatives.iter().map(QueryTerm::to_fts).collect();
+        match group.len() {
+            0 => {}
+            1 => parts.extend(group),
+            _ if parts.is_empty() => parts.push(group.join(" OR ")),
+            _ => parts.push(format!("({})", group.join(" OR "))),
+        }
+
+        (!parts.is_empty()).then(|| parts.join(" AND "))
+    }
+
+    /// Whether the query narrows results by field rather than by text
+    pub fn has_filters(&self) -> bool {
+        !self.categories.is_empty()
+            || !self.excluded_categories.is_empty()
+            || !self.subcategories.is_empty()
+            || !self.sources.is_empty()
+            || !self.excluded_sources.is_empty()
+            || !self.pages.is_empty()
+            || !self.tags.is_empty()
+    }
+
+    /// The searched words as plain text, for synonym expansion, section
+    /// matching and the substring fallback
+    pub fn text(&self) -> String {
+        self.any
+            .iter()
+            .chain(&self.required)
+            .chain(&self.title)
+            .map(|term| term.words.join(" "))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Add the field constraints and exclusions to `filter`. `source:` in
+    /// the query replaces the sources chosen outside it.
+    pub fn apply_to(&self, filter: &mut RuleFilter) {
+        if !self.sources.is_empty() {
+            filter.sources = self.sources.clone();
+        }
+        filter.excluded_sources.extend(self.excluded_sources.iter().cloned());
+        filter.categories.extend(self.categories.iter().cloned());
+        filter.excluded_categories.extend(self.excluded_categories.iter().cloned());
+        filter.subcategories.extend(self.subcategories.iter().cloned());
+        filter.pages.extend(&self.pages);
+        filter.tags.extend(self.tags.iter().cloned());
+        filter.excluded_terms.extend(self.excluded.iter().map(QueryTerm::to_fts));
+    }
+
+    fn add(&mut self, token: Token) -> Result<(), QueryError> {
+        let Token { negated, field, value, quoted, prefix } = token;
+
+        match field.as_deref() {
+            None => {
+                let term = to_term(&value, prefix)?;
+                let Some(term) = term else { return Ok(()) };
+                match (negated, quoted) {
+                    (true, _) => self.excluded.push(term),
+                    (false, true) => self.required.push(term),
+                    (false, false) => self.any.push(term),
+                }
+            }
+            Some(field) if value.trim().is_empty() => return Err(QueryError::MissingValue(field.to_string())),
+            Some(field @ ("title" | "subcategory" | "page" | "tag")) if negated => {
+                return Err(QueryError::CannotExclude(field.to_string()))
+            }
+            Some("title") => {
+                if let Some(term) = to_term(&value, prefix)? {
+                    self.title.push(term);
+                }
+            }
+            Some("category") => {
+                let category = value.trim().to_lowercase();
+                if negated {
+                    self.excluded_categories.push(category);
+                } else {
+                    self.categories.push(category);
+                }
+            }
+            Some("subcategory") => self.subcategories.push(value.trim().to_lowercase()),
+            Some("source") => {
+                let source = value.trim().to_uppercase();
+                if negated {
+                    self.excluded_sources.push(source);
+                } else {
+                    self.sources.push(source);
+                }
+            }
+            Some("page") => {
+                let page = value.trim().parse().map_err(|_| QueryError::InvalidPage(value.clone()))?;
+                self.pages.push(page);
+            }
+            Some(_) => {
+                let slug = slugify(&value);
+                if !slug.is_empty() && !self.tags.contains(&slug) {
+                    self.tags.push(slug);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// One whitespace-separated piece of the query
+struct Token {
+    negated: bool,
+    /// Lower-case field name before a colon
+    field: Option<String>,
+    value: String,
+    quoted: bool,
+    /// Ended in `*`
+    prefix: bool,
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        if chars[index].is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        let negated = chars[index] == '-' && chars.get(index + 1).is_some_and(|c| !c.is_whitespace());
+        if negated {
+            index += 1;
+        }
+
+        // A field name is letters followed by a colon
+        let mut field = None;
+        let name_end = (index..chars.len()).find(|&i| !chars[i].is_ascii_alphabetic()).unwrap_or(chars.len());
+        if name_end > index && chars.get(name_end) == Some(&':') {
+            let name: String = chars[index..name_end].iter().collect::<String>().to_lowercase();
+            if !FIELDS.contains(&name.as_str()) {
+                return Err(QueryError::UnknownField(name));
+            }
+            field = Some(name);
+            index = name_end + 1;
+        }
+
+        let quoted = chars.get(index) == Some(&'"');
+        let value: String = if quoted {
+            let close = (index + 1..chars.len())
+                .find(|&i| chars[i] == '"')
+                .ok_or(QueryError::UnclosedQuote)?;
+            let value = chars[index + 1..close].iter().collect();
+            index = close + 1;
+            value
+        } else {
+            let end = (index..chars.len())
+                .find(|&i| chars[i].is_whitespace() || chars[i] == '"')
+                .unwrap_or(chars.len());
+            let value = chars[index..end].iter().collect();
+            index = end;
+            value
+        };
+
+        let (value, prefix) = if quoted && chars.get(index) == Some(&'*') {
+            index += 1;
+            (value, true)
+        } else {
+            match value.strip_suffix('*') {
+                Some(stem) if !quoted => (stem.to_string(), true),
+                _ => (value, false),
+            }
+        };
+
+        tokens.push(Token { negated, field, value, quoted, prefix });
+    }
+
+    Ok(tokens)
+}
+
+/// The words of a token as a term; None when it has no letters or digits
+fn to_term(value: &str, prefix: bool) -> Result<Option<QueryTerm>, QueryError> {
+    let words: Vec<String> = normalize_phrase(value).split(' ').filter(|w| !w.is_empty()).map(str::to_string).collect();
+    if words.is_empty() {
+        return if prefix { Err(QueryError::LoneWildcard) } else { Ok(None) };
+    }
+    Ok(Some(QueryTerm { words, prefix }))
+}
+
+/// "PHB 195", "phb p. 195" or "DMG pg 12"
+fn parse_citation(query: &str) -> Option<Citation> {
+    let mut tokens = query.split_whitespace();
+    let source = tokens.next()?;
+    if !(2..=6).contains(&source.len()) || !source.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let rest: String = tokens.collect::<Vec<_>>().join("").to_lowercase();
+    let number = ["page", "pg.", "pg", "p.", "p"]
+        .iter()
+        .find_map(|marker| rest.strip_prefix(marker))
+        .unwrap_or(&rest);
+    let page = number.parse().ok().filter(|page| *page > 0)?;
+
+    Some(Citation {
+        source: source.to_uppercase(),
+        page,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_quoted_fts_expressions() {
+        let parsed = ParsedQuery::parse(r#"grap* escape "Bonus Action" title:grapple -spell -"two weapon""#).unwrap();
+
+        assert_eq!(
+            parsed.fts_expression().as_deref(),
+            Some(r#""bonus action" AND title:"grapple" AND ("grap"* OR "escape")"#)
+        );
+        assert_eq!(parsed.excluded.iter().map(QueryTerm::to_fts).collect::<Vec<_>>(), vec![r#""spell""#, r#""two weapon""#]);
+        assert_eq!(parsed.text(), "grap escape bonus action grapple");
+
+        // FTS5 operators typed by the user are just words
+        let parsed = ParsedQuery::parse("fire AND NEAR(ice)").unwrap();
+        assert_eq!(parsed.fts_expression().as_deref(), Some(r#""fire" OR "and" OR "near ice""#));
+    }
+
+    #[test]
+    fn field_qualifiers_become_filters() {
+        let parsed =
+            ParsedQuery::parse(r#"tag:reaction category:Combat subcategory:"Bonus Actions" source:phb -source:dmg page:195 TAG:Mounted-Combat attack"#)
+                .unwrap();
+        let mut filter = RuleFilter::from_params(Some("MM"), None, None);
+        parsed.apply_to(&mut filter);
+
+        assert_eq!(filter.sources, vec!["PHB"]);
+        assert_eq!(filter.excluded_sources, vec!["DMG"]);
+        assert_eq!(filter.categories, vec!["combat"]);
+        assert_eq!(filter.subcategories, vec!["bonus actions"]);
+        assert_eq!(filter.pages, vec![195]);
+        assert_eq!(filter.tags, vec!["reaction", "mounted-combat"]);
+        assert_eq!(parsed.fts_expression().as_deref(), Some(r#""attack""#));
+    }
+
+    #[test]
+    fn malformed_queries_explain_themselves() {
+        assert_eq!(ParsedQuery::parse(r#""bonus action"#), Err(QueryError::UnclosedQuote));
+        assert_eq!(ParsedQuery::parse("colour:red"), Err(QueryError::UnknownField("colour".to_string())));
+        assert_eq!(ParsedQuery::parse("tag: grapple"), Err(QueryError::MissingValue("tag".to_string())));
+        assert_eq!(ParsedQuery::parse("page:ten"), Err(QueryError::InvalidPage("ten".to_string())));
+        assert_eq!(ParsedQuery::parse("* fire"), Err(QueryError::LoneWildcard));
+        assert_eq!(ParsedQuery::parse("-tag:spell"), Err(QueryError::CannotExclude("tag".to_string())));
+        assert_eq!(ParsedQuery::parse("-spell"), Err(QueryError::OnlyExclusions));
+        assert!(ParsedQuery::parse("category:spells -fire").is_ok());
+        assert_eq!(
+            QueryError::MissingValue("page".to_string()).to_string(),
+            "\"page:\" needs a value, e.g. page:195."
+        );
+    }
+
+    #[test]
+    fn recognizes_citations() {
+        let citation = |query: &str| ParsedQuery::parse(query).unwrap().citation;
+
+        assert_eq!(citation("PHB 195"), Some(Citation { source: "PHB".to_string(), page: 195 }));
+        assert_eq!(citation("dmg p. 12"), Some(Citation { source: "DMG".to_string(), page: 12 }));
+        assert_eq!(citation("phb pg 7").map(|c| c.page), Some(7));
+        assert_eq!(citation("grapple 2"), None);
+        assert_eq!(citation("PHB grapple"), None);
+    }
+}
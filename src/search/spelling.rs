@@ -0,0 +1,135 @@
+//! "Did you mean" corrections for queries that match nothing.
+//!
+//! Query words missing from the full-text vocabulary are replaced by the
+//! closest vocabulary term within a small edit distance (Damerau-Levenshtein,
+//! so "atack" and "teh" are one edit away), preferring terms found in more
+//! rules when distances tie.
+
+use std::collections::HashMap;
+
+/// Edits allowed to correct a word of `len` characters; short words are
+/// left alone because too many terms sit one edit away from them
+fn max_distance(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// `query` with its misspelled words corrected, or None when every word is
+/// known or nothing close enough exists. `vocabulary` maps lower-case terms
+/// to the number of rules containing them. Field names (`title:`) and
+/// numbers are kept as typed.
+pub fn suggest(query: &str, vocabulary: &HashMap<String, i64>) -> Option<String> {
+    let mut corrected = String::with_capacity(query.len());
+    let mut changed = false;
+    let mut rest = query;
+
+    while let Some(start) = rest.find(|c: char| c.is_alphanumeric()) {
+        corrected.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len());
+        let word = &rest[..end];
+        rest = &rest[end..];
+
+        let is_field = rest.starts_with(':');
+        let is_prefix = rest.starts_with('*');
+        match correct_word(word, vocabulary).filter(|_| !is_field && !is_prefix) {
+            Some(correction) => {
+                corrected.push_str(&correction);
+                changed = true;
+            }
+            None => corrected.push_str(word),
+        }
+    }
+    corrected.push_str(rest);
+
+    changed.then_some(corrected)
+}
+
+/// The closest known term to `word`, if `word` itself is unknown
+fn correct_word(word: &str, vocabulary: &HashMap<String, i64>) -> Option<String> {
+    let word = word.to_lowercase();
+    let len = word.chars().count();
+    let limit = max_distance(len);
+    if limit == 0 || word.chars().all(|c| c.is_ascii_digit()) || vocabulary.contains_key(&word) {
+        return None;
+    }
+
+    let chars: Vec<char> = word.chars().collect();
+    vocabulary
+        .iter()
+        .filter(|(term, _)| term.chars().count().abs_diff(len) <= limit)
+        .filter_map(|(term, docs)| {
+            let distance = edit_distance(&chars, &term.chars().collect::<Vec<_>>());
+            (distance <= limit).then_some((distance, -docs, term))
+        })
+        .min()
+        .map(|(_, _, term)| term.clone())
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions
+/// and swaps of neighbouring characters each cost one edit
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vocabulary() -> HashMap<String, i64> {
+        [("opportunity", 4), ("attack", 30), ("attacks", 12), ("grappled", 6), ("grappler", 2), ("the", 90), ("title", 3)]
+            .into_iter()
+            .map(|(term, docs)| (term.to_string(), docs))
+            .collect()
+    }
+
+    #[test]
+    fn corrects_unknown_words_to_the_closest_common_term() {
+        let vocabulary = vocabulary();
+
+        assert_eq!(suggest("oportunity atack", &vocabulary).as_deref(), Some("opportunity attack"));
+        assert_eq!(suggest("Grapled?", &vocabulary).as_deref(), Some("grappled?"));
+        assert_eq!(suggest("\"opportunity atatck\"", &vocabulary).as_deref(), Some("\"opportunity attack\""));
+        assert_eq!(suggest("attack the", &vocabulary), None);
+        assert_eq!(suggest("teh xyzzyq 195", &vocabulary), None);
+    }
+
+    #[test]
+    fn keeps_field_names_and_prefixes() {
+        let vocabulary = vocabulary();
+
+        assert_eq!(suggest("titel:grapled", &vocabulary).as_deref(), Some("titel:grappled"));
+        assert_eq!(suggest("atack grapl*", &vocabulary).as_deref(), Some("attack grapl*"));
+    }
+
+    #[test]
+    fn counts_swapped_letters_as_one_edit() {
+        let distance = |a: &str, b: &str| edit_distance(&a.chars().collect::<Vec<_>>(), &b.chars().collect::<Vec<_>>());
+
+        assert_eq!(distance("atatck", "attack"), 1);
+        assert_eq!(distance("grapled", "grappled"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+}
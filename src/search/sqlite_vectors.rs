@@ -0,0 +1,135 @@
+//! Vector index stored in the `rule_vectors` table next to the rules, for
+//! deployments too small to be worth a Qdrant container. Search compares
+//! the query with every stored vector (exact cosine similarity), which takes
+//! well under a millisecond for a few thousand rules; a corpus large enough
+//! to need an approximate index is better served by Qdrant.
+
+use sqlx::SqlitePool;
+
+use crate::{
+    models::Rule,
+    search::vector::{VectorHit, VectorIndex, VectorSearchError},
+};
+
+#[derive(Clone)]
+pub struct SqliteVectorIndex {
+    pool: SqlitePool,
+    dimension: usize,
+}
+
+impl SqliteVectorIndex {
+    pub fn new(pool: SqlitePool, dimension: usize) -> Self {
+        Self { pool, dimension }
+    }
+
+    fn check_dimension(&self, vector: &[f32], what: &str) -> Result<(), VectorSearchError> {
+        if vector.len() != self.dimension {
+            return Err(VectorSearchError::SearchError(format!(
+                "{} dimension mismatch: expected {}, got {}",
+                what,
+                self.dimension,
+                vector.len()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl VectorIndex for SqliteVectorIndex {
+    /// The table comes from the migrations; nothing to set up
+    async fn ensure_collection(&self) -> Result<(), VectorSearchError> {
+        Ok(())
+    }
+
+    async fn upsert_rule(
+        &self,
+        rule: &Rule,
+        vector: Vec<f32>,
+    ) -> Result<(), VectorSearchError> {
+        self.check_dimension(&vector, "vector")?;
+
+        crate::db::upsert_rule_vector(&self.pool, &rule.id, &vector)
+            .await
+            .map_err(|e| VectorSearchError::SearchError(e.to_string()))
+    }
+
+    async fn search(
+        &self,
+        vector: Vec<f32>,
+        limit: usize,
+    ) -> Result<Vec<VectorHit>, VectorSearchError> {
+        self.check_dimension(&vector, "query vector")?;
+
+        let stored = crate::db::get_rule_vectors(&self.pool, self.dimension)
+            .await
+            .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
+
+        let mut hits: Vec<VectorHit> = stored
+            .into_iter()
+            .map(|(rule_id, stored)| VectorHit {
+                rule_id,
+                score: cosine_similarity(&vector, &stored),
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit);
+
+        Ok(hits)
+    }
+
+    async fn delete_rule(
+        &self,
+        rule_id: &str,
+    ) -> Result<(), VectorSearchError> {
+        crate::db::delete_rule_vector(&self.pool, rule_id)
+            .await
+            .map_err(|e| VectorSearchError::SearchError(e.to_string()))
+    }
+
+    async fn clear(&self) -> Result<(), VectorSearchError> {
+        crate::db::clear_rule_vectors(&self.pool)
+            .await
+            .map_err(|e| VectorSearchError::SearchError(e.to_string()))
+    }
+}
+
+/// Cosine of the angle between `a` and `b`; 0 when either is all zeros
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::vector::check_vector_index;
+
+    #[tokio::test]
+    async fn sqlite_index_passes_vector_index_checks() {
+        let index = SqliteVectorIndex::new(crate::db::test_pool().await, 4);
+
+        check_vector_index(&index, 4).await;
+    }
+
+    #[tokio::test]
+    async fn vectors_of_another_dimension_are_ignored() {
+        let pool = crate::db::test_pool().await;
+        crate::db::upsert_rule_vector(&pool, "old-provider", &[1.0, 0.0]).await.unwrap();
+        let index = SqliteVectorIndex::new(pool, 3);
+
+        assert!(index.search(vec![1.0, 0.0, 0.0], 10).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn cosine_similarity_ignores_length_and_handles_zero_vectors() {
+        assert!((cosine_similarity(&[2.0, 0.0], &[5.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 1.0]) - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+}
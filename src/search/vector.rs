@@ -30,6 +30,16 @@ pub trait VectorIndex: Send + Sync {
         &'a self,
         rule_id: &'a str,
     ) -> impl Future<Output = Result<(), VectorSearchError>> + Send + 'a;
+    /// Drop every vector, leaving an empty, ready index
+    fn clear(&self) -> impl Future<Output = Result<(), VectorSearchError>> + Send + '_;
+}
+
+/// 64-bit FNV-1a: unlike `DefaultHasher`, guaranteed to hash the same on
+/// every platform and Rust version, which stored vectors and hashes depend on
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
 }
 
 pub fn rule_to_embedding_text(rule: &Rule) -> String {
@@ -78,21 +88,65 @@ pub enum VectorSearchError {
     SearchError(String),
 }
 
+/// Behaviour every [`VectorIndex`] backend must show, for each backend's
+/// tests: idempotent setup, nearest-first search with cosine scores,
+/// replacing and deleting a rule's vector, and rejecting vectors that are
+/// not `dimension` long, and clearing. The index must start out empty.
+#[cfg(test)]
+pub(crate) async fn check_vector_index<V: VectorIndex>(index: &V, dimension: usize) {
+    let rule = |id: &str| Rule::new(id.to_string(), "Combat".to_string(), String::new(), String::new());
+    let axis = |values: &[f32]| {
+        let mut vector = vec![0.0; dimension];
+        vector[..values.len()].copy_from_slice(values);
+        vector
+    };
+    let ids = |hits: &[VectorHit]| hits.iter().map(|hit| hit.rule_id.clone()).collect::<Vec<_>>();
+
+    index.ensure_collection().await.unwrap();
+    index.ensure_collection().await.unwrap();
+
+    index.upsert_rule(&rule("grappled"), axis(&[1.0, 0.0])).await.unwrap();
+    index.upsert_rule(&rule("prone"), axis(&[0.0, 1.0])).await.unwrap();
+    index.upsert_rule(&rule("shove"), axis(&[0.8, 0.6])).await.unwrap();
+
+    let hits = index.search(axis(&[2.0, 0.0]), 2).await.unwrap();
+    assert_eq!(ids(&hits), vec!["grappled", "shove"]);
+    assert!((hits[0].score - 1.0).abs() < 1e-4);
+    assert!((hits[1].score - 0.8).abs() < 1e-4);
+
+    index.upsert_rule(&rule("grappled"), axis(&[0.0, 1.0])).await.unwrap();
+    let hits = index.search(axis(&[1.0, 0.0]), 10).await.unwrap();
+    assert_eq!(hits.len(), 3);
+    assert_eq!(hits[0].rule_id, "shove");
+
+    index.delete_rule("shove").await.unwrap();
+    index.delete_rule("never-indexed").await.unwrap();
+    let hits = index.search(axis(&[1.0, 0.0]), 10).await.unwrap();
+    assert!(!ids(&hits).contains(&"shove".to_string()));
+
+    assert!(index.upsert_rule(&rule("cover"), vec![1.0; dimension + 1]).await.is_err());
+    assert!(index.search(vec![1.0; dimension + 1], 10).await.is_err());
+
+    index.clear().await.unwrap();
+    assert!(index.search(axis(&[1.0, 0.0]), 10).await.unwrap().is_empty());
+    index.upsert_rule(&rule("cover"), axis(&[1.0, 0.0])).await.unwrap();
+    assert_eq!(ids(&index.search(axis(&[1.0, 0.0]), 10).await.unwrap()), vec!["cover"]);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     fn test_rule() -> Rule {
         Rule {
-            id: "opportunity-attack".to_string(),
-            title: "Opportunity Attack".to_string(),
-            category: "Combat".to_string(),
             subcategory: Some("Actions".to_string()),
-            content: "You can make an opportunity attack when a hostile creature leaves your reach.".to_string(),
-            source: "Player's Handbook 2024".to_string(),
+            source_id: Some("PHB".to_string()),
             page: Some(195),
-            created_at: "now".to_string(),
-            updated_at: "now".to_string(),
+            ..Rule::test(
+                "opportunity-attack",
+                "Opportunity Attack",
+                "You can make an opportunity attack when a hostile creature leaves your reach.",
+            )
         }
     }
 
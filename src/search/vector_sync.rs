@@ -0,0 +1,234 @@
+//! Keeping the vector index in step with rule writes.
+//!
+//! Creating, editing, trashing, restoring, renaming or purging a rule syncs
+//! that rule's vector in the background: a live rule is embedded again, a
+//! rule that is gone has its vector deleted. Rules that fail to sync go to
+//! the `vector_sync_queue` table and are retried periodically, and the admin
+//! dashboard reports the index as stale while anything is queued or differs
+//! from the vector records.
+
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+use crate::{
+    config::VectorSearchConfig,
+    db::RuleRepository,
+    models::QueuedVectorSync,
+    search::{
+        hybrid::{ConfiguredEmbeddingClient, ConfiguredVectorIndex},
+        indexer::{index_rule, plan_index, unindex_rule, IndexOptions},
+        vector::{VectorIndex, VectorSearchError},
+    },
+};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncReport {
+    pub synced: usize,
+    /// Rules that failed and wait in the retry queue
+    pub queued: usize,
+}
+
+/// How far the vector index lags behind the rules
+#[derive(Debug, Default)]
+pub struct VectorIndexStatus {
+    /// Rules whose vector is missing, outdated or left over from a rule that is gone
+    pub out_of_sync: usize,
+    pub queued: Vec<QueuedVectorSync>,
+}
+
+impl VectorIndexStatus {
+    pub fn is_stale(&self) -> bool {
+        self.out_of_sync > 0 || !self.queued.is_empty()
+    }
+}
+
+/// Bring the vector of one rule in line with the database: embed the rule
+/// again while it exists, delete its vector once it is trashed, purged or
+/// renamed away
+pub async fn sync_rule_vector(
+    pool: &SqlitePool,
+    rules: &dyn RuleRepository,
+    vector_config: &VectorSearchConfig,
+    rule_id: &str,
+) -> Result<(), VectorSearchError> {
+    let vector_index = ConfiguredVectorIndex::from_config(vector_config, pool);
+    let rule = rules
+        .get(rule_id)
+        .await
+        .map_err(|e| VectorSearchError::SearchError(e.to_string()))?;
+
+    match rule {
+        Some(rule) => {
+            let embedding_client = ConfiguredEmbeddingClient::from_config(vector_config)?;
+            vector_index.ensure_collection().await?;
+            index_rule(pool, &rule, &embedding_client, &vector_index, &IndexOptions::from_config(vector_config)).await
+        }
+        None => unindex_rule(pool, rule_id, &vector_index).await,
+    }
+}
+
+/// Sync the vectors of `rule_ids`, queueing the ones that fail for a retry.
+/// A no-op when vector search is disabled.
+pub async fn sync_rules(
+    pool: &SqlitePool,
+    rules: &dyn RuleRepository,
+    vector_config: &VectorSearchConfig,
+    rule_ids: &[String],
+) -> SyncReport {
+    let mut report = SyncReport::default();
+    if !vector_config.enabled {
+        return report;
+    }
+
+    for rule_id in rule_ids {
+        match sync_rule_vector(pool, rules, vector_config, rule_id).await {
+            Ok(()) => report.synced += 1,
+            Err(e) => {
+                tracing::warn!("Failed to sync vector of rule {}, queued for retry: {}", rule_id, e);
+                if let Err(e) = crate::db::queue_vector_sync(pool, rule_id, &e.to_string()).await {
+                    tracing::error!("Failed to queue vector sync of rule {}: {}", rule_id, e);
+                }
+                report.queued += 1;
+            }
+        }
+    }
+
+    report
+}
+
+/// Sync the vectors of `rule_ids` without waiting for it, so a slow
+/// embedding API does not hold up the request that changed them
+pub fn spawn_rule_sync(
+    pool: &SqlitePool,
+    rules: &Arc<dyn RuleRepository>,
+    vector_config: &VectorSearchConfig,
+    rule_ids: Vec<String>,
+) {
+    if !vector_config.enabled || rule_ids.is_empty() {
+        return;
+    }
+
+    let (pool, rules, vector_config) = (pool.clone(), rules.clone(), vector_config.clone());
+    tokio::spawn(async move {
+        sync_rules(&pool, rules.as_ref(), &vector_config, &rule_ids).await;
+    });
+}
+
+/// Try the queued rules again
+pub async fn retry_queued(
+    pool: &SqlitePool,
+    rules: &dyn RuleRepository,
+    vector_config: &VectorSearchConfig,
+) -> Result<SyncReport, sqlx::Error> {
+    if !vector_config.enabled {
+        return Ok(SyncReport::default());
+    }
+
+    let rule_ids: Vec<String> = crate::db::get_vector_sync_queue(pool)
+        .await?
+        .into_iter()
+        .map(|queued| queued.rule_id)
+        .collect();
+
+    Ok(sync_rules(pool, rules, vector_config, &rule_ids).await)
+}
+
+/// Compare the rules with the vector records and the retry queue; None when
+/// vector search is disabled
+pub async fn vector_index_status(
+    pool: &SqlitePool,
+    vector_config: &VectorSearchConfig,
+) -> Result<Option<VectorIndexStatus>, sqlx::Error> {
+    if !vector_config.enabled {
+        return Ok(None);
+    }
+
+    let rules = crate::db::get_all_rules(pool).await?;
+    let records = crate::db::get_vector_records(pool).await?;
+    let plan = plan_index(&rules, &records, &IndexOptions::from_config(vector_config));
+
+    Ok(Some(VectorIndexStatus {
+        out_of_sync: plan.changed.len() + plan.removed.len(),
+        queued: crate::db::get_vector_sync_queue(pool).await?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{EmbeddingProvider, VectorBackend};
+    use crate::db::{test_pool, SqliteRuleRepository};
+    use crate::models::Rule;
+
+    fn rule(id: &str, content: &str) -> Rule {
+        Rule {
+            category: "Conditions".to_string(),
+            ..Rule::test(id, "Grappled", content)
+        }
+    }
+
+    fn local_config() -> VectorSearchConfig {
+        let mut config = VectorSearchConfig::from_env();
+        config.enabled = true;
+        config.backend = VectorBackend::Sqlite;
+        config.embedding_provider = EmbeddingProvider::Local;
+        config.local_embedding_dimension = 64;
+        config
+    }
+
+    #[tokio::test]
+    async fn syncs_edited_and_trashed_rules() {
+        let pool = test_pool().await;
+        let repository = SqliteRuleRepository::new(pool.clone());
+        let config = local_config();
+        let ids = vec!["grappled".to_string()];
+
+        repository.upsert(&rule("grappled", "Speed 0."), "test").await.unwrap();
+        assert!(vector_index_status(&pool, &config).await.unwrap().unwrap().is_stale());
+        assert_eq!(sync_rules(&pool, &repository, &config, &ids).await, SyncReport { synced: 1, queued: 0 });
+        assert!(!vector_index_status(&pool, &config).await.unwrap().unwrap().is_stale());
+
+        repository.upsert(&rule("grappled", "Speed becomes 0."), "test").await.unwrap();
+        assert_eq!(vector_index_status(&pool, &config).await.unwrap().unwrap().out_of_sync, 1);
+        sync_rules(&pool, &repository, &config, &ids).await;
+        assert_eq!(vector_index_status(&pool, &config).await.unwrap().unwrap().out_of_sync, 0);
+
+        repository.delete("grappled").await.unwrap();
+        sync_rules(&pool, &repository, &config, &ids).await;
+        assert!(crate::db::get_vector_records(&pool).await.unwrap().is_empty());
+        assert!(crate::db::get_rule_vectors(&pool, 64).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_syncs_wait_in_the_queue_until_a_retry_succeeds() {
+        let pool = test_pool().await;
+        let repository = SqliteRuleRepository::new(pool.clone());
+        let mut config = local_config();
+        config.embedding_provider = EmbeddingProvider::OpenAi;
+        config.openai_api_key = None;
+
+        repository.upsert(&rule("grappled", "Speed 0."), "test").await.unwrap();
+        let report = sync_rules(&pool, &repository, &config, &["grappled".to_string()]).await;
+        assert_eq!(report, SyncReport { synced: 0, queued: 1 });
+
+        let status = vector_index_status(&pool, &config).await.unwrap().unwrap();
+        assert_eq!(status.queued.len(), 1);
+        assert_eq!(status.queued[0].rule_id, "grappled");
+
+        let config = local_config();
+        assert_eq!(retry_queued(&pool, &repository, &config).await.unwrap(), SyncReport { synced: 1, queued: 0 });
+        assert!(!vector_index_status(&pool, &config).await.unwrap().unwrap().is_stale());
+    }
+
+    #[tokio::test]
+    async fn disabled_vector_search_syncs_nothing() {
+        let pool = test_pool().await;
+        let repository = SqliteRuleRepository::new(pool.clone());
+        let mut config = local_config();
+        config.enabled = false;
+
+        repository.upsert(&rule("grappled", "Speed 0."), "test").await.unwrap();
+        assert_eq!(sync_rules(&pool, &repository, &config, &["grappled".to_string()]).await, SyncReport::default());
+        assert!(vector_index_status(&pool, &config).await.unwrap().is_none());
+    }
+}
@@ -339,8 +339,36 @@ function clearAllBookmarks() {
 }
 
 // Initialize bookmark buttons on page load
-document.addEventListener('DOMContentLoaded', () => {
+/**
+ * Move bookmarks saved under a rule's former IDs to its current ID
+ * (the rule page lists them in data-rule-aliases after a rename)
+ */
+function migrateBookmarkAliases() {
+    const article = document.querySelector('[data-rule-aliases]');
+    if (!article || !article.dataset.ruleAliases) {
+        return;
+    }
+
+    const ruleId = article.dataset.ruleId;
     const bookmarks = getBookmarks();
+    let changed = false;
+    article.dataset.ruleAliases.split(' ').forEach(alias => {
+        if (alias && alias in bookmarks) {
+            if (!(ruleId in bookmarks)) {
+                bookmarks[ruleId] = bookmarks[alias];
+            }
+            delete bookmarks[alias];
+            changed = true;
+        }
+    });
+
+    if (changed) {
+        saveBookmarks(bookmarks);
+    }
+}
+
+document.addEventListener('DOMContentLoaded', () => {
+    migrateBookmarkAliases();
     document.querySelectorAll('.bookmark-btn').forEach(btn => {
         const ruleId = btn.dataset.ruleId;
         if (ruleId && isBookmarked(ruleId)) {
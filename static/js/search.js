@@ -83,9 +83,15 @@ function initSearchAutocomplete() {
 async function fetchSuggestions(query, container, input) {
     try {
         const response = await fetch(`/api/search?q=${encodeURIComponent(query)}`);
-        const suggestions = await response.json();
-
-        displaySuggestions(suggestions, container, input, query);
+        // Half-typed queries (an open quote, a bare "page:") are rejected;
+        // the search page explains why on submit
+        if (!response.ok) {
+            closeSuggestions(container);
+            return;
+        }
+        const page = await response.json();
+
+        displaySuggestions(page.results, container, input, query);
     } catch (error) {
         console.error('Search error:', error);
         closeSuggestions(container);
@@ -110,9 +116,13 @@ function displaySuggestions(suggestions, container, input, query) {
         item.className = 'suggestion-item';
         item.dataset.index = index;
 
-        // Highlight matching text
-        const highlightedTitle = highlightMatch(suggestion.title, query);
-        const highlightedExcerpt = highlightMatch(suggestion.excerpt, query);
+        // The server marks matched terms; its highlight is escaped HTML
+        const highlightedTitle = suggestion.highlight
+            ? suggestion.highlight.title
+            : highlightMatch(suggestion.title, query);
+        const highlightedExcerpt = suggestion.highlight
+            ? suggestion.highlight.snippet
+            : highlightMatch(suggestion.excerpt, query);
 
         item.innerHTML = `
             <div class="suggestion-title">${highlightedTitle}</div>
@@ -123,7 +133,8 @@ function displaySuggestions(suggestions, container, input, query) {
         `;
 
         item.addEventListener('click', () => {
-            window.location.href = `/rules/${suggestion.id}`;
+            const anchor = suggestion.section ? `#${suggestion.section}` : '';
+            window.location.href = `/rules/${suggestion.id}${anchor}`;
         });
 
         item.addEventListener('mouseenter', () => {